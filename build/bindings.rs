@@ -74,7 +74,17 @@ pub fn generate_bindings(rss_path: &Path) -> Result<()> {
 
     // Add headers and generate bindings
     let mut bindings = add_headers_to_bindings(builder, &headers)?;
-    bindings = add_log_wrapper(bindings)?;
+    // The log wrapper is C, the Rust stub backend must build without a C compiler
+    if !cfg!(feature = "rust_stubs") {
+        bindings = add_log_wrapper(bindings)?;
+    }
+    if cfg!(feature = "stub_library") {
+        // Control interface of the hand written stub models
+        bindings = bindings.header("c_src/stub/acc_stub.h");
+        if cfg!(feature = "presence") {
+            bindings = bindings.header("c_src/stub/acc_stub_presence.h");
+        }
+    }
 
     let bindings = bindings
         .generate()
@@ -216,6 +226,15 @@ fn get_riscv_sysroot() -> Result<String> {
 }
 
 fn add_headers_to_bindings(mut bindings: Builder, headers: &Path) -> Result<Builder> {
+    for path in bound_headers(headers)? {
+        bindings = bindings.header(path.to_str().unwrap());
+    }
+    Ok(bindings)
+}
+
+/// SDK headers the bindings are generated from, depending on the enabled detectors
+pub fn bound_headers(headers: &Path) -> Result<Vec<PathBuf>> {
+    let mut bound = Vec::new();
     for entry in fs::read_dir(headers)? {
         let entry = entry?;
         let path = entry.path();
@@ -226,11 +245,11 @@ fn add_headers_to_bindings(mut bindings: Builder, headers: &Path) -> Result<Buil
             let is_other = !filename.contains("distance") && !filename.contains("presence");
 
             if is_distance || is_presence || is_other {
-                bindings = bindings.header(path.to_str().unwrap());
+                bound.push(path);
             }
         }
     }
-    Ok(bindings)
+    Ok(bound)
 }
 
 fn add_log_wrapper(mut bindings: Builder) -> Result<Builder> {
@@ -250,6 +269,34 @@ fn add_log_wrapper(mut bindings: Builder) -> Result<Builder> {
         build.compiler("riscv32-esp-elf-gcc");
     }
 
+    if cfg!(feature = "defmt") {
+        // c_log_stub formats straight into defmt frames instead of a line buffer
+        build.define("A121_SYS_LOG_DEFMT", None);
+    }
+    if cfg!(feature = "log_chunks") {
+        build.define("A121_SYS_LOG_CHUNKS", None);
+    }
+    if cfg!(feature = "bundled_printf") {
+        // Log lines are formatted without the toolchain's vsnprintf. Without builtins the
+        // compiler does not turn the local string loops back into strlen or memcpy calls.
+        build
+            .file("c_src/printf.c")
+            .define("A121_SYS_LOG_BUNDLED_PRINTF", None)
+            .flag("-fno-builtin");
+    }
+    if let Ok(size) = env::var("A121_SYS_LOG_BUFFER_SIZE") {
+        let size = match size.trim().parse::<usize>() {
+            Ok(size) if size >= 2 => size,
+            _ => {
+                return Err(BuildError::InvalidEnvVar(format!(
+                    "A121_SYS_LOG_BUFFER_SIZE must be a number of at least 2, got {:?}",
+                    size
+                )))
+            }
+        };
+        build.define("A121_SYS_LOG_BUFFER_SIZE", size.to_string().as_str());
+    }
+
     build
         .file("c_src/logging.c")
         .include("c_src")
@@ -258,6 +305,10 @@ fn add_log_wrapper(mut bindings: Builder) -> Result<Builder> {
         .compile("log");
 
     println!("cargo:rerun-if-changed=c_src/logging.c");
+    println!("cargo:rerun-if-changed=c_src/logging.h");
+    println!("cargo:rerun-if-changed=c_src/printf.c");
+    println!("cargo:rerun-if-changed=c_src/printf.h");
+    println!("cargo:rerun-if-env-changed=A121_SYS_LOG_BUFFER_SIZE");
     println!("cargo:rustc-link-lib=static=log");
     bindings = bindings.header("c_src/logging.h");
 
@@ -6,6 +6,7 @@ pub type Result<T> = std::result::Result<T, BuildError>;
 #[derive(Debug)]
 pub enum BuildError {
     EnvVar(std::env::VarError),
+    InvalidEnvVar(String),
     Io(io::Error),
     LibraryNotFound(PathBuf),
     RssPathNotFound,
@@ -20,6 +21,7 @@ impl fmt::Display for BuildError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BuildError::EnvVar(e) => write!(f, "Environment variable error: {}", e),
+            BuildError::InvalidEnvVar(msg) => write!(f, "Invalid environment variable: {}", msg),
             BuildError::Io(e) => write!(f, "IO error: {}", e),
             BuildError::LibraryNotFound(path) => {
                 write!(f, "Library not found at: {}", path.display())
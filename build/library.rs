@@ -38,8 +38,8 @@ pub fn discover_library() -> Result<PathBuf> {
 
 pub fn setup_linking(lib_path: &Path) -> Result<()> {
     println!("cargo:rustc-link-search=native={}", lib_path.display());
-    println!("cargo:rustc-link-lib=static=acconeer_a121");
 
+    // Detector libraries depend on the core library, so they have to come first
     if cfg!(feature = "distance") {
         println!("cargo:rustc-link-lib=static=acc_detector_distance_a121");
     }
@@ -48,6 +48,8 @@ pub fn setup_linking(lib_path: &Path) -> Result<()> {
         println!("cargo:rustc-link-lib=static=acc_detector_presence_a121");
     }
 
+    println!("cargo:rustc-link-lib=static=acconeer_a121");
+
     if cfg!(feature = "stub_library") {
         setup_stub_linking()?;
     }
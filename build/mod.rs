@@ -13,21 +13,28 @@ pub use error::Result;
 
 pub fn main() -> Result<()> {
     let rss_path = library::get_rss_path()?;
-    let lib_path = if cfg!(feature = "stub_library") {
+    if cfg!(feature = "rust_stubs") {
+        // The stubs are compiled as part of the crate, there is nothing to link
         let out_dir = PathBuf::from(env::var("OUT_DIR").map_err(BuildError::EnvVar)?);
-        stub::generate_stubs(&rss_path, &out_dir)?;
-        out_dir
+        stub::generate_rust_stubs(&rss_path, &out_dir)?;
     } else {
-        library::discover_library()?
-    };
+        let lib_path = if cfg!(feature = "stub_library") {
+            let out_dir = PathBuf::from(env::var("OUT_DIR").map_err(BuildError::EnvVar)?);
+            stub::generate_stubs(&rss_path, &out_dir)?;
+            out_dir
+        } else {
+            library::discover_library()?
+        };
+        library::setup_linking(&lib_path)?;
+    }
 
-    // Setup linking and generate bindings
-    library::setup_linking(&lib_path)?;
+    // Generate bindings
     bindings::generate_bindings(&rss_path)?;
 
     // Always rerun if these change
     println!("cargo:rerun-if-changed=build/");
     println!("cargo:rerun-if-changed=c_src/");
+    println!("cargo:rerun-if-changed=src/stub/");
     println!("cargo:rerun-if-env-changed=ACC_RSS_LIBS");
 
     Ok(())
@@ -1,6 +1,6 @@
 use crate::error::{BuildError, Result};
 use crate::stub_generator::StubGenerator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn generate_stubs(rss_path: &Path, out_dir: &Path) -> Result<()> {
@@ -13,10 +13,11 @@ pub fn generate_stubs(rss_path: &Path, out_dir: &Path) -> Result<()> {
 
     // Generate stubs using our Rust generator
     let generator = StubGenerator::default();
+    generator.check_header_coverage(&include_dir)?;
     generator.generate_stubs(&include_dir, out_dir)?;
 
     // Compile the generated stubs
-    generate_stub_libraries(out_dir, &include_dir)?;
+    generate_stub_libraries(&generator, out_dir, &include_dir)?;
 
     // Validate the generated libraries if the tools are available
     let nm_tool = if cfg!(target_arch = "arm") {
@@ -31,31 +32,49 @@ pub fn generate_stubs(rss_path: &Path, out_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn generate_stub_libraries(out_dir: &Path, include_dir: &Path) -> Result<()> {
+/// Generates the Rust stub backend, included by `src/stub/mod.rs`
+pub fn generate_rust_stubs(rss_path: &Path, out_dir: &Path) -> Result<()> {
+    let include_dir = rss_path.join("include");
+    if !include_dir.exists() {
+        return Err(BuildError::StubGenerationFailed(
+            "Include directory not found".into(),
+        ));
+    }
+
+    let generator = StubGenerator::default();
+    generator.check_header_coverage(&include_dir)?;
+    generator.generate_rust_stubs(&include_dir, &out_dir.join("stubs.rs"))
+}
+
+pub fn generate_stub_libraries(
+    generator: &StubGenerator,
+    out_dir: &Path,
+    include_dir: &Path,
+) -> Result<()> {
     compile_and_archive(
+        generator,
         out_dir,
         include_dir,
         "acconeer_a121_stubs.c",
-        "acconeer_a121_stubs.o",
         "libacconeer_a121.a",
     )?;
 
     if cfg!(feature = "distance") {
         compile_and_archive(
+            generator,
             out_dir,
             include_dir,
             "acc_detector_distance_a121_stubs.c",
-            "acc_detector_distance_a121_stubs.o",
             "libacc_detector_distance_a121.a",
         )?;
     }
 
     if cfg!(feature = "presence") {
         compile_and_archive(
+            generator,
             out_dir,
             include_dir,
             "acc_detector_presence_a121_stubs.c",
-            "acc_detector_presence_a121_stubs.o",
             "libacc_detector_presence_a121.a",
         )?;
     }
@@ -64,21 +83,62 @@ pub fn generate_stub_libraries(out_dir: &Path, include_dir: &Path) -> Result<()>
 }
 
 fn compile_and_archive(
+    generator: &StubGenerator,
     out_dir: &Path,
     include_dir: &Path,
     source_file: &str,
-    obj_file_name: &str,
     lib_name: &str,
 ) -> Result<()> {
-    let source_path = out_dir.join(source_file);
-    let obj_path = out_dir.join(obj_file_name);
     let lib_path = out_dir.join(lib_name);
 
+    // The generated stubs plus the hand written models sharing the archive
+    let mut sources = vec![out_dir.join(source_file)];
+    sources.extend(
+        generator
+            .model_sources(source_file)
+            .iter()
+            .map(PathBuf::from),
+    );
+
+    let mut objects = Vec::new();
+    for source_path in &sources {
+        let obj_path = out_dir
+            .join(source_path.file_name().unwrap())
+            .with_extension("o");
+        compile_source(source_path, &obj_path, include_dir)?;
+        objects.push(obj_path);
+    }
+
+    let archiver = if cfg!(target_arch = "arm") {
+        "arm-none-eabi-ar"
+    } else {
+        "ar"
+    };
+
+    // Create archive, starting over so objects of a previous build do not linger
+    let _ = std::fs::remove_file(&lib_path);
+    let status = Command::new(archiver)
+        .arg("rcs")
+        .arg(&lib_path)
+        .args(&objects)
+        .status()
+        .map_err(|e| BuildError::CompilationError(e.to_string()))?;
+
+    if !status.success() {
+        return Err(BuildError::CompilationError(format!(
+            "Failed to create archive {}",
+            lib_name
+        )));
+    }
+
+    Ok(())
+}
+
+fn compile_source(source_path: &Path, obj_path: &Path, include_dir: &Path) -> Result<()> {
     // Choose compiler based on target architecture
-    let (compiler, archiver, compile_args) = if cfg!(target_arch = "arm") {
+    let (compiler, compile_args) = if cfg!(target_arch = "arm") {
         (
             "arm-none-eabi-gcc",
-            "arm-none-eabi-ar",
             vec![
                 "-mcpu=cortex-m4",
                 "-mthumb",
@@ -89,7 +149,7 @@ fn compile_and_archive(
             ],
         )
     } else {
-        ("cc", "ar", vec![])
+        ("cc", vec![])
     };
 
     // Common compile arguments
@@ -100,6 +160,8 @@ fn compile_and_archive(
         obj_path.to_str().unwrap(),
         "-I",
         include_dir.to_str().unwrap(),
+        "-I",
+        "c_src/stub",
         "-std=c99",
         "-MMD",
         "-MP",
@@ -122,24 +184,7 @@ fn compile_and_archive(
     if !status.success() {
         return Err(BuildError::CompilationError(format!(
             "Failed to compile {}",
-            source_file
-        )));
-    }
-
-    // Create archive
-    let status = Command::new(archiver)
-        .args([
-            "rcs",
-            lib_path.to_str().unwrap(),
-            obj_path.to_str().unwrap(),
-        ])
-        .status()
-        .map_err(|e| BuildError::CompilationError(e.to_string()))?;
-
-    if !status.success() {
-        return Err(BuildError::CompilationError(format!(
-            "Failed to create archive {}",
-            lib_name
+            source_path.display()
         )));
     }
 
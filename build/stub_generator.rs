@@ -1,7 +1,8 @@
+use crate::bindings;
 use crate::error::{BuildError, Result};
 use bindgen::callbacks::ItemInfo;
-use bindgen::Builder;
-use std::collections::HashMap;
+use bindgen::{Builder, Formatter};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
@@ -10,8 +11,48 @@ struct CFunctionDecl {
     name: String,
     return_type: String,
     parameters: Vec<(String, String)>, // (type, name)
+    // The same signature spelled in Rust, for the Rust stub backend
+    rust_return_type: Option<String>,
+    rust_parameters: Vec<(String, String)>,
 }
 
+/// A set/get pair whose value is stored in an opaque object
+#[derive(Debug)]
+struct Property {
+    field: String,
+    value_type: String,
+    rust_type: String,
+    indexed: bool,
+}
+
+/// An opaque SDK type that the stubs give a real layout to
+#[derive(Debug)]
+struct StatefulObject {
+    struct_tag: String,
+    properties: Vec<Property>,
+}
+
+/// How a generated setter or getter reaches its field
+#[derive(Debug)]
+struct Accessor {
+    object_type: String,
+    field: String,
+    setter: bool,
+    index: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct StubState {
+    objects: HashMap<String, StatefulObject>,
+    object_order: Vec<String>,
+    accessors: HashMap<String, Accessor>,
+}
+
+/// Indexed properties are per subsweep
+const INDEXED_PROPERTY_CAPACITY: &str = "ACC_MAX_NUM_SUBSWEEPS";
+/// Plain properties alias subsweep 0 of their `subsweep_` counterpart, like in the SDK
+const SUBSWEEP_PREFIX: &str = "subsweep_";
+
 #[derive(Debug, Default)]
 struct FunctionCollector {
     _functions: Vec<CFunctionDecl>,
@@ -29,7 +70,10 @@ impl bindgen::callbacks::ParseCallbacks for FunctionCollector {
 
 pub struct StubGenerator {
     header_files: HashMap<String, Vec<String>>,
+    model_files: HashMap<String, Vec<String>>,
+    rust_model_files: HashMap<String, Vec<String>>,
     return_values: HashMap<String, String>,
+    property_defaults: HashMap<String, String>,
 }
 
 impl Default for StubGenerator {
@@ -71,6 +115,188 @@ impl Default for StubGenerator {
             "ACC_DETECTOR_DISTANCE_REFLECTOR_SHAPE_GENERIC".to_string(),
         );
 
+        // Values a freshly created config object reports, matching the SDK defaults
+        let mut property_defaults = HashMap::new();
+        for (property, value) in [
+            ("acc_config_subsweep_start_point", "80"),
+            ("acc_config_subsweep_num_points", "160"),
+            ("acc_config_subsweep_step_length", "1"),
+            ("acc_config_subsweep_profile", "ACC_CONFIG_PROFILE_3"),
+            ("acc_config_subsweep_hwaas", "8"),
+            ("acc_config_subsweep_receiver_gain", "16"),
+            ("acc_config_subsweep_enable_tx", "true"),
+            ("acc_config_subsweep_prf", "ACC_CONFIG_PRF_15_6_MHZ"),
+            ("acc_config_subsweep_phase_enhancement", "false"),
+            ("acc_config_subsweep_iq_imbalance_compensation", "false"),
+            ("acc_config_subsweep_enable_loopback", "false"),
+            ("acc_config_num_subsweeps", "1"),
+            ("acc_config_sweeps_per_frame", "1"),
+            ("acc_config_sweep_rate", "0.0f"),
+            ("acc_config_continuous_sweep_mode", "false"),
+            ("acc_config_frame_rate", "0.0f"),
+            (
+                "acc_config_inter_frame_idle_state",
+                "ACC_CONFIG_IDLE_STATE_DEEP_SLEEP",
+            ),
+            (
+                "acc_config_inter_sweep_idle_state",
+                "ACC_CONFIG_IDLE_STATE_READY",
+            ),
+            ("acc_config_double_buffering", "false"),
+            ("acc_detector_distance_config_start", "0.25f"),
+            ("acc_detector_distance_config_end", "3.0f"),
+            ("acc_detector_distance_config_max_step_length", "0"),
+            (
+                "acc_detector_distance_config_close_range_leakage_cancellation",
+                "false",
+            ),
+            ("acc_detector_distance_config_signal_quality", "15.0f"),
+            (
+                "acc_detector_distance_config_max_profile",
+                "ACC_CONFIG_PROFILE_5",
+            ),
+            (
+                "acc_detector_distance_config_threshold_method",
+                "ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_CFAR",
+            ),
+            (
+                "acc_detector_distance_config_peak_sorting",
+                "ACC_DETECTOR_DISTANCE_PEAK_SORTING_STRONGEST",
+            ),
+            (
+                "acc_detector_distance_config_num_frames_recorded_threshold",
+                "100",
+            ),
+            (
+                "acc_detector_distance_config_fixed_amplitude_threshold_value",
+                "100.0f",
+            ),
+            (
+                "acc_detector_distance_config_fixed_strength_threshold_value",
+                "0.0f",
+            ),
+            ("acc_detector_distance_config_threshold_sensitivity", "0.5f"),
+            (
+                "acc_detector_distance_config_reflector_shape",
+                "ACC_DETECTOR_DISTANCE_REFLECTOR_SHAPE_GENERIC",
+            ),
+            ("acc_detector_presence_config_start", "0.3f"),
+            ("acc_detector_presence_config_end", "2.5f"),
+            ("acc_detector_presence_config_step_length", "24"),
+            ("acc_detector_presence_config_auto_step_length", "true"),
+            (
+                "acc_detector_presence_config_profile",
+                "ACC_CONFIG_PROFILE_4",
+            ),
+            ("acc_detector_presence_config_auto_profile", "true"),
+            (
+                "acc_detector_presence_config_inter_frame_idle_state",
+                "ACC_CONFIG_IDLE_STATE_DEEP_SLEEP",
+            ),
+            ("acc_detector_presence_config_hwaas", "32"),
+            ("acc_detector_presence_config_sweeps_per_frame", "16"),
+            ("acc_detector_presence_config_frame_rate", "10.0f"),
+            (
+                "acc_detector_presence_config_frame_rate_app_driven",
+                "false",
+            ),
+            ("acc_detector_presence_config_sensor", "1"),
+            (
+                "acc_detector_presence_config_reset_filters_on_prepare",
+                "true",
+            ),
+            (
+                "acc_detector_presence_config_inter_frame_presence_timeout",
+                "3",
+            ),
+            ("acc_detector_presence_config_intra_detection", "true"),
+            (
+                "acc_detector_presence_config_intra_detection_threshold",
+                "1.3f",
+            ),
+            ("acc_detector_presence_config_inter_detection", "true"),
+            (
+                "acc_detector_presence_config_inter_detection_threshold",
+                "1.0f",
+            ),
+            (
+                "acc_detector_presence_config_inter_frame_deviation_time_const",
+                "0.5f",
+            ),
+            (
+                "acc_detector_presence_config_inter_frame_fast_cutoff",
+                "6.0f",
+            ),
+            (
+                "acc_detector_presence_config_inter_frame_slow_cutoff",
+                "0.2f",
+            ),
+            (
+                "acc_detector_presence_config_intra_frame_time_const",
+                "0.15f",
+            ),
+            (
+                "acc_detector_presence_config_intra_output_time_const",
+                "0.3f",
+            ),
+            (
+                "acc_detector_presence_config_inter_output_time_const",
+                "2.0f",
+            ),
+            ("acc_detector_presence_config_automatic_subsweeps", "false"),
+            ("acc_detector_presence_config_signal_quality", "15.0f"),
+            (
+                "acc_detector_presence_processing_config_frame_rate",
+                "10.0f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_frame_presence_timeout",
+                "3",
+            ),
+            (
+                "acc_detector_presence_processing_config_intra_detection",
+                "true",
+            ),
+            (
+                "acc_detector_presence_processing_config_intra_detection_threshold",
+                "1.3f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_detection",
+                "true",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_detection_threshold",
+                "1.0f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_frame_deviation_time_const",
+                "0.5f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_frame_fast_cutoff",
+                "6.0f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_frame_slow_cutoff",
+                "0.2f",
+            ),
+            (
+                "acc_detector_presence_processing_config_intra_frame_time_const",
+                "0.15f",
+            ),
+            (
+                "acc_detector_presence_processing_config_intra_output_time_const",
+                "0.3f",
+            ),
+            (
+                "acc_detector_presence_processing_config_inter_output_time_const",
+                "2.0f",
+            ),
+        ] {
+            property_defaults.insert(property.to_string(), value.to_string());
+        }
+
         let mut header_files = HashMap::new();
         header_files.insert(
             "acconeer_a121_stubs.c".to_string(),
@@ -106,7 +332,81 @@ impl Default for StubGenerator {
         if cfg!(feature = "presence") {
             header_files.insert(
                 "acc_detector_presence_a121_stubs.c".to_string(),
-                vec!["acc_detector_presence.h"]
+                vec![
+                    "acc_detector_presence.h",
+                    "acc_detector_presence_processing.h",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            );
+        }
+
+        // Hand written models replacing the generated stubs of the functions they define
+        let mut model_files = HashMap::new();
+        model_files.insert(
+            "acconeer_a121_stubs.c".to_string(),
+            vec![
+                "c_src/stub/acc_stub_assembly_test.c",
+                "c_src/stub/acc_stub_faults.c",
+                "c_src/stub/acc_stub_hal.c",
+                "c_src/stub/acc_stub_processing.c",
+                "c_src/stub/acc_stub_sensor.c",
+                "c_src/stub/acc_stub_trace.c",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        );
+
+        if cfg!(feature = "distance") {
+            model_files.insert(
+                "acc_detector_distance_a121_stubs.c".to_string(),
+                vec!["c_src/stub/acc_stub_distance.c".to_string()],
+            );
+        }
+
+        if cfg!(feature = "presence") {
+            model_files.insert(
+                "acc_detector_presence_a121_stubs.c".to_string(),
+                vec![
+                    "c_src/stub/acc_stub_presence.c",
+                    "c_src/stub/acc_stub_presence_processing.c",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            );
+        }
+
+        // The same models ported to Rust, for the `rust_stubs` backend
+        let mut rust_model_files = HashMap::new();
+        rust_model_files.insert(
+            "acconeer_a121_stubs.c".to_string(),
+            vec![
+                "src/stub/assembly_test.rs",
+                "src/stub/faults.rs",
+                "src/stub/hal.rs",
+                "src/stub/processing.rs",
+                "src/stub/sensor.rs",
+                "src/stub/trace.rs",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        );
+
+        if cfg!(feature = "distance") {
+            rust_model_files.insert(
+                "acc_detector_distance_a121_stubs.c".to_string(),
+                vec!["src/stub/distance.rs".to_string()],
+            );
+        }
+
+        if cfg!(feature = "presence") {
+            rust_model_files.insert(
+                "acc_detector_presence_a121_stubs.c".to_string(),
+                vec!["src/stub/presence.rs", "src/stub/presence_processing.rs"]
                     .into_iter()
                     .map(String::from)
                     .collect(),
@@ -115,48 +415,73 @@ impl Default for StubGenerator {
 
         Self {
             header_files,
+            model_files,
+            rust_model_files,
             return_values,
+            property_defaults,
         }
     }
 }
 
 impl StubGenerator {
+    /// Hand written sources compiled into the same archive as `stub_file`
+    pub fn model_sources(&self, stub_file: &str) -> &[String] {
+        self.model_files
+            .get(stub_file)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Rust sources of the models the Rust backend uses in place of `model_sources`
+    pub fn rust_model_sources(&self, stub_file: &str) -> &[String] {
+        self.rust_model_files
+            .get(stub_file)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Fails the build if a header the bindings are generated from has no stub source, as its
+    /// functions would be bound but missing at link time
+    pub fn check_header_coverage(&self, include_dir: &Path) -> Result<()> {
+        let covered: HashSet<&str> = self
+            .header_files
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+
+        let mut missing = Vec::new();
+        for path in bindings::bound_headers(include_dir)? {
+            let header = path.file_name().unwrap().to_str().unwrap().to_string();
+            if !covered.contains(header.as_str()) {
+                missing.push(header);
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            missing.sort();
+            Err(BuildError::StubGenerationFailed(format!(
+                "No stub source covers the bound headers: {}",
+                missing.join(", ")
+            )))
+        }
+    }
+
     pub fn generate_stubs(&self, include_dir: &Path, out_dir: &Path) -> Result<()> {
         for (stub_file, headers) in &self.header_files {
-            let mut functions = Vec::new();
-
-            // Parse all headers for this stub file
-            for header in headers {
-                let header_path = include_dir.join(header);
-                fs::read_to_string(&header_path).map_err(|e| {
-                    BuildError::StubGenerationFailed(format!(
-                        "Failed to read header {}: {}",
-                        header, e
-                    ))
-                })?;
+            let (functions, opaque_types) = self.parse_headers(include_dir, headers)?;
 
-                let collector = FunctionCollector::default();
-
-                let bindings = Builder::default()
-                    .header(header_path.to_str().unwrap())
-                    .parse_callbacks(Box::new(collector))
-                    .clang_arg("-I/usr/lib/arm-none-eabi/include")
-                    .clang_arg(format!("-I{}", include_dir.display()))
-                    .generate()
-                    .map_err(|e| {
-                        BuildError::StubGenerationFailed(format!(
-                            "Failed to generate bindings: {}",
-                            e
-                        ))
-                    })?;
-
-                // Extract function declarations by parsing the generated bindings text
-                let bindings_text = bindings.to_string();
-                functions.extend(self.extract_functions_from_text(&bindings_text)?);
+            // Functions a model implements only get the generated wrapper
+            let mut modeled = HashSet::new();
+            for model in self.model_sources(stub_file) {
+                modeled.extend(collect_defined_functions(&read_model(model)?));
             }
 
             // Generate the stub file
-            let stub_content = self.generate_stub_file(&functions, headers)?;
+            let stub_content =
+                self.generate_stub_file(&functions, headers, &opaque_types, &modeled)?;
 
             // Write the stub file
             let stub_path = out_dir.join(stub_file);
@@ -167,69 +492,162 @@ impl StubGenerator {
         Ok(())
     }
 
+    /// Generates the stubs of every library as `#[no_mangle]` Rust functions in a single
+    /// file, meant to be included by `src/stub/mod.rs`
+    pub fn generate_rust_stubs(&self, include_dir: &Path, out_file: &Path) -> Result<()> {
+        let mut stub_files: Vec<&String> = self.header_files.keys().collect();
+        stub_files.sort();
+
+        let mut content = String::from("// Generated by build/stub_generator.rs\n");
+        for stub_file in stub_files {
+            let (functions, opaque_types) =
+                self.parse_headers(include_dir, &self.header_files[stub_file])?;
+
+            let mut modeled = HashSet::new();
+            for model in self.rust_model_sources(stub_file) {
+                modeled.extend(collect_defined_rust_functions(&read_model(model)?));
+            }
+
+            content.push_str(&format!("\n// Stubs of {}\n", stub_file));
+            content.push_str(&self.generate_rust_stub_file(&functions, &opaque_types, &modeled));
+        }
+
+        fs::write(out_file, content).map_err(|e| {
+            BuildError::StubGenerationFailed(format!("Failed to write stub file: {}", e))
+        })
+    }
+
+    /// Collects the functions declared in `headers` and the opaque types they operate on
+    fn parse_headers(
+        &self,
+        include_dir: &Path,
+        headers: &[String],
+    ) -> Result<(Vec<CFunctionDecl>, HashMap<String, String>)> {
+        let mut functions = Vec::new();
+        let mut opaque_types = HashMap::new();
+
+        for header in headers {
+            let header_path = include_dir.join(header);
+            let header_text = fs::read_to_string(&header_path).map_err(|e| {
+                BuildError::StubGenerationFailed(format!("Failed to read header {}: {}", header, e))
+            })?;
+            opaque_types.extend(collect_opaque_types(&header_text));
+
+            let collector = FunctionCollector::default();
+
+            // Only emit the functions declared in this header, unformatted and
+            // without doc attributes so the token stream is easy to scan
+            let bindings = Builder::default()
+                .header(header_path.to_str().unwrap())
+                .allowlist_file(format!(".*/{}", header.replace('.', "\\.")))
+                .parse_callbacks(Box::new(collector))
+                .use_core()
+                .formatter(Formatter::None)
+                .generate_comments(false)
+                .layout_tests(false)
+                .clang_arg("-I/usr/lib/arm-none-eabi/include")
+                .clang_arg(format!("-I{}", include_dir.display()))
+                .generate()
+                .map_err(|e| {
+                    BuildError::StubGenerationFailed(format!("Failed to generate bindings: {}", e))
+                })?;
+
+            // Extract function declarations by parsing the generated bindings text
+            let bindings_text = bindings.to_string();
+            functions.extend(self.extract_functions_from_text(&bindings_text)?);
+        }
+
+        Ok((functions, opaque_types))
+    }
+
     fn extract_functions_from_text(&self, text: &str) -> Result<Vec<CFunctionDecl>> {
+        let tokens = tokenize(text);
         let mut functions = Vec::new();
 
-        // Split the text into lines and look for extern "C" function declarations
-        for line in text.lines() {
-            let line = line.trim();
-            if line.starts_with("extern") && line.contains("fn") {
-                if let Some(func) = self.parse_function_declaration(line) {
+        // Look for `fn name(params) -> ret;` items inside the extern blocks
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i] == "fn" {
+                if let Some((func, consumed)) = self.parse_function_declaration(&tokens[i + 1..]) {
                     functions.push(func);
+                    i += consumed;
                 }
             }
+            i += 1;
         }
 
         Ok(functions)
     }
 
-    fn parse_function_declaration(&self, line: &str) -> Option<CFunctionDecl> {
-        // Basic function declaration parsing
-        let line = line.trim_start_matches("extern \"C\" ").trim();
-        if !line.starts_with("fn ") {
+    /// Parses a bindgen function signature, starting at the function name.
+    ///
+    /// Returns the declaration and the number of tokens it spans.
+    fn parse_function_declaration(&self, tokens: &[String]) -> Option<(CFunctionDecl, usize)> {
+        let name = tokens.first()?.clone();
+        if tokens.get(1)? != "(" {
             return None;
         }
 
-        let line = line.trim_start_matches("fn ").trim_end_matches(';');
-
-        // Split name and parameters
-        let mut parts = line.splitn(2, '(');
-        let name = parts.next()?.trim().to_string();
-        let params_part = parts.next()?.trim_end_matches(')');
+        // Find the closing parenthesis of the parameter list
+        let mut depth = 0;
+        let mut close = None;
+        for (i, token) in tokens.iter().enumerate().skip(1) {
+            match token.as_str() {
+                "(" | "<" | "[" => depth += 1,
+                ")" | ">" | "]" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close = close?;
 
-        // Parse parameters
-        let parameters = if params_part.trim() == "void" {
-            Vec::new()
-        } else {
-            params_part
-                .split(',')
-                .filter_map(|param| {
-                    let mut parts = param.trim().rsplitn(2, ' ');
-                    let param_name = parts.next()?.to_string();
-                    let param_type = parts.next()?.to_string();
-                    Some((param_type, param_name))
-                })
-                .collect()
-        };
+        // Parse parameters, each being `name : type`
+        let mut parameters = Vec::new();
+        let mut rust_parameters = Vec::new();
+        for param in split_top_level(&tokens[2..close]) {
+            if param.len() < 3 || param[1] != ":" {
+                return None;
+            }
+            parameters.push((c_type_from_rust(&param[2..])?, param[0].clone()));
+            rust_parameters.push((rust_type_text(&param[2..]), param[0].clone()));
+        }
 
         // Parse return type
-        let return_type = if line.contains("->") {
-            line.split("->").nth(1)?.trim().to_string()
-        } else {
-            "void".to_string()
-        };
+        let end = close + tokens[close..].iter().position(|t| t == ";")?;
+        let (return_type, rust_return_type) =
+            if tokens.get(close + 1).map(String::as_str) == Some("->") {
+                let return_tokens = &tokens[close + 2..end];
+                (
+                    c_type_from_rust(return_tokens)?,
+                    Some(rust_type_text(return_tokens)),
+                )
+            } else {
+                ("void".to_string(), None)
+            };
 
-        Some(CFunctionDecl {
-            name,
-            return_type,
-            parameters,
-        })
+        Some((
+            CFunctionDecl {
+                name,
+                return_type,
+                parameters,
+                rust_return_type,
+                rust_parameters,
+            },
+            end,
+        ))
     }
 
     fn generate_stub_file(
         &self,
         functions: &[CFunctionDecl],
         headers: &[String],
+        opaque_types: &HashMap<String, String>,
+        modeled: &HashSet<String>,
     ) -> Result<String> {
         let mut content = String::new();
 
@@ -237,6 +655,7 @@ impl StubGenerator {
         for header in headers {
             content.push_str(&format!("#include \"{}\"\n", header));
         }
+        content.push_str("#include \"acc_stub_internal.h\"\n");
 
         // Add helper functions
         content.push_str(
@@ -245,8 +664,9 @@ impl StubGenerator {
 #include <complex.h>
 #include <string.h>
 #include <stdint.h>
+#include <stdlib.h>
 
-float fake_external_dependencies(char* foo, complex float iq) {
+static float fake_external_dependencies(char* foo, complex float iq) {
     char buff[42];
     memcpy(buff, foo, 1);
     memset(foo, 0, 1);
@@ -257,56 +677,834 @@ float fake_external_dependencies(char* foo, complex float iq) {
 "#,
         );
 
+        // Give the configuration objects a layout so setters can be read back
+        let state = self.collect_stub_state(functions, opaque_types);
+        for object_type in &state.object_order {
+            content.push('\n');
+            content.push_str(&self.generate_object_struct(&state.objects[object_type]));
+        }
+        content.push('\n');
+
         // Generate function stubs
         for func in functions {
-            content.push_str(&self.generate_function_stub(func));
+            content.push_str(&self.generate_function_stub(func, &state, modeled));
             content.push_str("\n\n");
         }
 
         Ok(content)
     }
 
-    fn generate_function_stub(&self, func: &CFunctionDecl) -> String {
-        let mut stub = format!("{} {}(", func.return_type, func.name);
+    /// Pairs up `<object>_<field>_set` / `<object>_<field>_get` functions operating on the
+    /// same opaque type into fields stored in that object.
+    fn collect_stub_state(
+        &self,
+        functions: &[CFunctionDecl],
+        opaque_types: &HashMap<String, String>,
+    ) -> StubState {
+        let mut state = StubState::default();
+        let mut pairs = Vec::new();
+
+        for setter in functions {
+            let Some(base) = setter.name.strip_suffix("_set") else {
+                continue;
+            };
+            let indexed = match setter.parameters.len() {
+                2 => false,
+                3 if setter.parameters[2].0 == "uint8_t" => true,
+                _ => continue,
+            };
+            let Some(object_type) = setter.parameters[0].0.strip_suffix(" *") else {
+                continue;
+            };
+            let Some(struct_tag) = opaque_types.get(object_type) else {
+                continue;
+            };
+            let Some(field) = base.strip_prefix(&object_prefix(object_type)) else {
+                continue;
+            };
+            let value_type = &setter.parameters[1].0;
+            let rust_type = &setter.rust_parameters[1].0;
+            let getter_name = format!("{}_get", base);
+            let Some(getter) = functions.iter().find(|f| {
+                f.name == getter_name
+                    && f.return_type == *value_type
+                    && f.parameters.len() == setter.parameters.len() - 1
+                    && f.parameters[0].0 == format!("const {} *", object_type)
+                    && (!indexed || f.parameters[1].0 == "uint8_t")
+            }) else {
+                continue;
+            };
 
-        // Add parameters
-        if func.parameters.is_empty() {
-            stub.push_str("void");
+            if !state.objects.contains_key(object_type) {
+                state.object_order.push(object_type.to_string());
+                state.objects.insert(
+                    object_type.to_string(),
+                    StatefulObject {
+                        struct_tag: struct_tag.clone(),
+                        properties: Vec::new(),
+                    },
+                );
+            }
+            state
+                .objects
+                .get_mut(object_type)
+                .unwrap()
+                .properties
+                .push(Property {
+                    field: field.to_string(),
+                    value_type: value_type.clone(),
+                    rust_type: rust_type.clone(),
+                    indexed,
+                });
+            pairs.push((object_type.to_string(), field.to_string(), setter, getter));
+        }
+
+        // Plain properties that also exist per subsweep are stored in subsweep 0
+        for object in state.objects.values_mut() {
+            let indexed_fields: Vec<(String, String)> = object
+                .properties
+                .iter()
+                .filter(|p| p.indexed)
+                .map(|p| (p.field.clone(), p.value_type.clone()))
+                .collect();
+            object.properties.retain(|p| {
+                p.indexed
+                    || !indexed_fields.contains(&(
+                        format!("{}{}", SUBSWEEP_PREFIX, p.field),
+                        p.value_type.clone(),
+                    ))
+            });
+        }
+
+        for (object_type, field, setter, getter) in pairs {
+            let stored = state.objects[&object_type]
+                .properties
+                .iter()
+                .any(|p| p.field == field);
+            let (field, setter_index, getter_index) = if !stored {
+                let field = format!("{}{}", SUBSWEEP_PREFIX, field);
+                (field, Some("0".to_string()), Some("0".to_string()))
+            } else if let (Some(set), Some(get)) =
+                (setter.parameters.get(2), getter.parameters.get(1))
+            {
+                (field, Some(set.1.clone()), Some(get.1.clone()))
+            } else {
+                (field, None, None)
+            };
+
+            state.accessors.insert(
+                setter.name.clone(),
+                Accessor {
+                    object_type: object_type.clone(),
+                    field: field.clone(),
+                    setter: true,
+                    index: setter_index,
+                },
+            );
+            state.accessors.insert(
+                getter.name.clone(),
+                Accessor {
+                    object_type,
+                    field,
+                    setter: false,
+                    index: getter_index,
+                },
+            );
+        }
+
+        state
+    }
+
+    fn generate_object_struct(&self, object: &StatefulObject) -> String {
+        let mut content = format!("struct {} {{\n", object.struct_tag);
+        for property in &object.properties {
+            if property.indexed {
+                content.push_str(&format!(
+                    "    {} {}[{}];\n",
+                    property.value_type, property.field, INDEXED_PROPERTY_CAPACITY
+                ));
+            } else {
+                content.push_str(&format!(
+                    "    {} {};\n",
+                    property.value_type, property.field
+                ));
+            }
+        }
+        content.push_str("};\n");
+        content
+    }
+
+    /// Emits the exported function, which records the call and forwards to either a
+    /// generated `stub_<name>` body or the model's `model_<name>`
+    fn generate_function_stub(
+        &self,
+        func: &CFunctionDecl,
+        state: &StubState,
+        modeled: &HashSet<String>,
+    ) -> String {
+        let mut stub = String::new();
+
+        let implementation = if modeled.contains(&func.name) {
+            let implementation = format!("model_{}", func.name);
+            stub.push_str(&format!("{};\n\n", c_signature(func, &implementation)));
+            implementation
         } else {
-            for (i, (param_type, param_name)) in func.parameters.iter().enumerate() {
-                if i > 0 {
-                    stub.push_str(", ");
-                }
-                stub.push_str(&format!("{} {}", param_type, param_name));
+            let implementation = format!("stub_{}", func.name);
+            stub.push_str(&format!(
+                "static {} {{\n",
+                c_signature(func, &implementation)
+            ));
+
+            // Add fake dependencies call for create functions
+            if func.name.contains("create") {
+                stub.push_str("    char dummy[] = \"dummy\";\n");
+                stub.push_str("    fake_external_dependencies(dummy, 1.0 + 2.0*I);\n");
+            }
+
+            stub.push_str(&self.generate_function_body(func, state));
+            stub.push_str("}\n\n");
+            implementation
+        };
+
+        stub.push_str(&format!("{} {{\n", c_signature(func, &func.name)));
+        stub.push_str(&format!(
+            "    uint32_t trace = acc_stub_trace_begin(\"{}\");\n",
+            func.name
+        ));
+        for (param_type, param_name) in &func.parameters {
+            if let Some(kind) = trace_kind(param_type) {
+                stub.push_str(&format!(
+                    "    acc_stub_trace_{}(trace, {});\n",
+                    kind, param_name
+                ));
             }
         }
-        stub.push_str(") {\n");
 
-        // Add parameter void casts
-        for (_, param_name) in &func.parameters {
-            stub.push_str(&format!("    (void) {};\n", param_name));
+        let arguments: Vec<&str> = func.parameters.iter().map(|(_, n)| n.as_str()).collect();
+        let call = format!("{}({})", implementation, arguments.join(", "));
+        if func.return_type == "void" {
+            stub.push_str(&format!("    {};\n", call));
+        } else {
+            stub.push_str(&format!("    {} ret = {};\n", func.return_type, call));
+            if let Some(kind) = trace_kind(&func.return_type) {
+                stub.push_str(&format!(
+                    "    acc_stub_trace_result_{}(trace, ret);\n",
+                    kind
+                ));
+            }
+            stub.push_str("    return ret;\n");
+        }
+
+        stub.push('}');
+        stub
+    }
+
+    fn generate_function_body(&self, func: &CFunctionDecl, state: &StubState) -> String {
+        if let Some(accessor) = state.accessors.get(&func.name) {
+            return self.generate_accessor_body(func, accessor);
         }
 
-        // Add fake dependencies call for create functions
-        if func.name.contains("create") {
-            stub.push_str("    fake_external_dependencies(\"dummy\", 1.0 + 2.0*I);\n");
+        let object_type = func.return_type.strip_suffix(" *");
+        if let Some(object) = object_type.and_then(|t| state.objects.get(t)) {
+            if func.name.ends_with("_create") && func.parameters.is_empty() {
+                return self.generate_create_body(object_type.unwrap(), object);
+            }
+        }
+
+        if let [(param_type, param_name)] = func.parameters.as_slice() {
+            let object_type = param_type.strip_suffix(" *").unwrap_or_default();
+            if func.name.ends_with("_destroy") && state.objects.contains_key(object_type) {
+                return format!("    acc_stub_free({});\n", param_name);
+            }
+        }
+
+        let mut body = String::new();
+
+        // Add parameter void casts
+        for (_, param_name) in &func.parameters {
+            body.push_str(&format!("    (void) {};\n", param_name));
         }
 
         // Add return value
         if func.return_type != "void" {
-            if let Some(return_value) = self.return_values.get(&func.return_type) {
-                stub.push_str(&format!("    return {};\n", return_value));
-            } else if func.return_type.contains('*') {
-                stub.push_str("    return NULL;\n");
+            body.push_str(&format!(
+                "    return {};\n",
+                self.default_value(&func.return_type)
+            ));
+        }
+
+        body
+    }
+
+    fn generate_accessor_body(&self, func: &CFunctionDecl, accessor: &Accessor) -> String {
+        let object = &func.parameters[0].1;
+        let mut guard = format!("{} == NULL", object);
+        let mut place = format!("{}->{}", object, accessor.field);
+        if let Some(index) = &accessor.index {
+            if index != "0" {
+                guard.push_str(&format!(" || {} >= {}", index, INDEXED_PROPERTY_CAPACITY));
+            }
+            place.push_str(&format!("[{}]", index));
+        }
+
+        if accessor.setter {
+            format!(
+                "    if ({}) {{\n        return;\n    }}\n    {} = {};\n",
+                guard, place, func.parameters[1].1
+            )
+        } else {
+            let default = self
+                .property_default(&accessor.object_type, &accessor.field)
+                .unwrap_or_else(|| self.default_value(&func.return_type));
+            format!(
+                "    if ({}) {{\n        return {};\n    }}\n    return {};\n",
+                guard, default, place
+            )
+        }
+    }
+
+    fn generate_create_body(&self, object_type: &str, object: &StatefulObject) -> String {
+        let mut body = format!(
+            "    {} *object = acc_stub_alloc(sizeof(*object));\n    if (object == NULL) {{\n        return NULL;\n    }}\n",
+            object_type
+        );
+
+        for property in &object.properties {
+            let Some(default) = self.property_default(object_type, &property.field) else {
+                continue;
+            };
+            if property.indexed {
+                body.push_str(&format!(
+                    "    for (uint8_t i = 0; i < {}; i++) {{\n        object->{}[i] = {};\n    }}\n",
+                    INDEXED_PROPERTY_CAPACITY, property.field, default
+                ));
             } else {
+                body.push_str(&format!("    object->{} = {};\n", property.field, default));
+            }
+        }
+
+        body.push_str("    return object;\n");
+        body
+    }
+
+    fn property_default(&self, object_type: &str, field: &str) -> Option<String> {
+        self.property_defaults
+            .get(&format!("{}{}", object_prefix(object_type), field))
+            .cloned()
+    }
+
+    fn default_value(&self, return_type: &str) -> String {
+        if let Some(return_value) = self.return_values.get(return_type) {
+            return_value.clone()
+        } else if return_type.contains('*') {
+            "NULL".to_string()
+        } else {
+            format!("({}){{0}}", return_type)
+        }
+    }
+
+    fn generate_rust_stub_file(
+        &self,
+        functions: &[CFunctionDecl],
+        opaque_types: &HashMap<String, String>,
+        modeled: &HashSet<String>,
+    ) -> String {
+        let mut content = String::new();
+
+        let state = self.collect_stub_state(functions, opaque_types);
+        for object_type in &state.object_order {
+            content.push('\n');
+            content.push_str(&self.generate_rust_object_struct(&state.objects[object_type]));
+        }
+
+        for func in functions {
+            content.push('\n');
+            content.push_str(&self.generate_rust_function_stub(func, &state, modeled));
+        }
+
+        content
+    }
+
+    fn generate_rust_object_struct(&self, object: &StatefulObject) -> String {
+        let mut content = format!(
+            "#[derive(Default)]\nstruct {} {{\n",
+            rust_struct_name(&object.struct_tag)
+        );
+        for property in &object.properties {
+            if property.indexed {
+                content.push_str(&format!(
+                    "    {}: [{}; {} as usize],\n",
+                    property.field, property.rust_type, INDEXED_PROPERTY_CAPACITY
+                ));
+            } else {
+                content.push_str(&format!(
+                    "    {}: {},\n",
+                    property.field, property.rust_type
+                ));
+            }
+        }
+        content.push_str("}\n");
+        content
+    }
+
+    /// Rust counterpart of `generate_function_stub`, exporting the function under its SDK name
+    fn generate_rust_function_stub(
+        &self,
+        func: &CFunctionDecl,
+        state: &StubState,
+        modeled: &HashSet<String>,
+    ) -> String {
+        let parameters = func
+            .rust_parameters
+            .iter()
+            .map(|(param_type, param_name)| format!("{}: {}", param_name, param_type))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let return_type = func
+            .rust_return_type
+            .as_ref()
+            .map(|t| format!(" -> {}", t))
+            .unwrap_or_default();
+        let mut stub = String::new();
+
+        let implementation = if modeled.contains(&func.name) {
+            format!("model_{}", func.name)
+        } else {
+            let implementation = format!("stub_{}", func.name);
+            stub.push_str(&format!(
+                "unsafe fn {}({}){} {{\n",
+                implementation, parameters, return_type
+            ));
+            stub.push_str(&self.generate_rust_function_body(func, state));
+            stub.push_str("}\n\n");
+            implementation
+        };
+
+        let recorders: Vec<String> = func
+            .parameters
+            .iter()
+            .filter_map(|(param_type, param_name)| {
+                let kind = trace_kind(param_type)?;
+                Some(format!(
+                    "    trace::{}(trace, {});\n",
+                    kind,
+                    rust_trace_value(kind, param_name)
+                ))
+            })
+            .collect();
+        let result_kind = func
+            .rust_return_type
+            .as_ref()
+            .and(trace_kind(&func.return_type));
+
+        stub.push_str("#[no_mangle]\n");
+        stub.push_str(&format!(
+            "pub unsafe extern \"C\" fn {}({}){} {{\n",
+            func.name, parameters, return_type
+        ));
+        let begin = format!("trace::begin(c\"{}\")", func.name);
+        if recorders.is_empty() && result_kind.is_none() {
+            stub.push_str(&format!("    {};\n", begin));
+        } else {
+            stub.push_str(&format!("    let trace = {};\n", begin));
+        }
+        for recorder in recorders {
+            stub.push_str(&recorder);
+        }
+
+        let arguments: Vec<&str> = func
+            .rust_parameters
+            .iter()
+            .map(|(_, n)| n.as_str())
+            .collect();
+        let call = format!("{}({})", implementation, arguments.join(", "));
+        match (&func.rust_return_type, result_kind) {
+            (None, _) => stub.push_str(&format!("    {};\n", call)),
+            (Some(_), Some(kind)) => {
+                stub.push_str(&format!("    let ret = {};\n", call));
                 stub.push_str(&format!(
-                    "    {} result = {{}};\n    return result;\n",
-                    func.return_type
+                    "    trace::result_{}(trace, {});\n",
+                    kind,
+                    rust_trace_value(kind, "ret")
                 ));
+                stub.push_str("    ret\n");
             }
+            (Some(_), None) => stub.push_str(&format!("    {}\n", call)),
         }
 
-        stub.push('}');
+        stub.push_str("}\n");
         stub
     }
+
+    fn generate_rust_function_body(&self, func: &CFunctionDecl, state: &StubState) -> String {
+        if let Some(accessor) = state.accessors.get(&func.name) {
+            return self.generate_rust_accessor_body(func, accessor, state);
+        }
+
+        let object_type = func.return_type.strip_suffix(" *");
+        if let Some(object) = object_type.and_then(|t| state.objects.get(t)) {
+            if func.name.ends_with("_create") && func.parameters.is_empty() {
+                return self.generate_rust_create_body(object_type.unwrap(), object);
+            }
+        }
+
+        if let [(param_type, param_name)] = func.parameters.as_slice() {
+            let object_type = param_type.strip_suffix(" *").unwrap_or_default();
+            if let Some(object) = state.objects.get(object_type) {
+                if func.name.ends_with("_destroy") {
+                    return format!(
+                        "    hal::free({}.cast::<{}>());\n",
+                        param_name,
+                        rust_struct_name(&object.struct_tag)
+                    );
+                }
+            }
+        }
+
+        let mut body = String::new();
+
+        // Mark parameters as used
+        for (_, param_name) in &func.rust_parameters {
+            body.push_str(&format!("    let _ = {};\n", param_name));
+        }
+
+        // Add return value
+        if func.return_type != "void" {
+            body.push_str(&format!(
+                "    {}\n",
+                rust_value(&self.default_value(&func.return_type), &func.return_type)
+            ));
+        }
+
+        body
+    }
+
+    fn generate_rust_accessor_body(
+        &self,
+        func: &CFunctionDecl,
+        accessor: &Accessor,
+        state: &StubState,
+    ) -> String {
+        let object = &func.rust_parameters[0].1;
+        let struct_name = rust_struct_name(&state.objects[&accessor.object_type].struct_tag);
+        let mut guard = format!("{}.is_null()", object);
+        let mut place = format!("(*{}.cast::<{}>()).{}", object, struct_name, accessor.field);
+        if let Some(index) = &accessor.index {
+            if index == "0" {
+                place.push_str("[0]");
+            } else {
+                guard.push_str(&format!(
+                    " || u32::from({}) >= {}",
+                    index, INDEXED_PROPERTY_CAPACITY
+                ));
+                place.push_str(&format!("[{} as usize]", index));
+            }
+        }
+
+        if accessor.setter {
+            format!(
+                "    if {} {{\n        return;\n    }}\n    {} = {};\n",
+                guard, place, func.rust_parameters[1].1
+            )
+        } else {
+            let default = self
+                .property_default(&accessor.object_type, &accessor.field)
+                .unwrap_or_else(|| self.default_value(&func.return_type));
+            format!(
+                "    if {} {{\n        return {};\n    }}\n    {}\n",
+                guard,
+                rust_value(&default, &func.return_type),
+                place
+            )
+        }
+    }
+
+    fn generate_rust_create_body(&self, object_type: &str, object: &StatefulObject) -> String {
+        let struct_name = rust_struct_name(&object.struct_tag);
+        let mut fields = String::new();
+        let mut all_defaulted = true;
+        for property in &object.properties {
+            let Some(default) = self.property_default(object_type, &property.field) else {
+                all_defaulted = false;
+                continue;
+            };
+            let value = rust_value(&default, &property.value_type);
+            if property.indexed {
+                fields.push_str(&format!(
+                    "        {}: [{}; {} as usize],\n",
+                    property.field, value, INDEXED_PROPERTY_CAPACITY
+                ));
+            } else {
+                fields.push_str(&format!("        {}: {},\n", property.field, value));
+            }
+        }
+
+        let object = if fields.is_empty() {
+            format!("{}::default()", struct_name)
+        } else if all_defaulted {
+            format!("{} {{\n{}    }}", struct_name, fields)
+        } else {
+            format!(
+                "{} {{\n{}        ..Default::default()\n    }}",
+                struct_name, fields
+            )
+        };
+        format!("    hal::alloc({}).cast()\n", object)
+    }
+}
+
+/// Name prefix shared by the functions operating on an opaque type,
+/// e.g. `acc_config_` for `acc_config_t`
+fn object_prefix(object_type: &str) -> String {
+    format!("{}_", object_type.trim_end_matches("_t"))
+}
+
+/// Finds `typedef struct tag name;` declarations, mapping name to tag
+fn collect_opaque_types(header_text: &str) -> HashMap<String, String> {
+    let mut types = HashMap::new();
+    for line in header_text.lines() {
+        let words: Vec<&str> = line
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect();
+        if let ["typedef", "struct", tag, name] = words.as_slice() {
+            types.insert(name.to_string(), tag.to_string());
+        }
+    }
+    types
+}
+
+fn read_model(path: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| {
+        BuildError::StubGenerationFailed(format!("Failed to read model {}: {}", path, e))
+    })
+}
+
+/// Finds the SDK functions a model implements, i.e. its non-static `model_<name>`
+/// definitions starting at column 0
+fn collect_defined_functions(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter(|line| {
+            !line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '/' || c == '}')
+                && !line.starts_with("static")
+                && !line.trim_end().ends_with(';')
+        })
+        .filter_map(|line| {
+            let (declaration, _) = line.split_once('(')?;
+            let name = declaration
+                .split_whitespace()
+                .last()?
+                .trim_start_matches('*');
+            name.strip_prefix("model_").map(String::from)
+        })
+        .collect()
+}
+
+/// Finds the SDK functions a Rust model implements, i.e. its top level `model_<name>` functions
+fn collect_defined_rust_functions(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter(|line| line.starts_with("pub"))
+        .filter_map(|line| {
+            let (_, rest) = line.split_once("fn model_")?;
+            let name = rest
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()?;
+            Some(name.to_string())
+        })
+        .collect()
+}
+
+/// Formats a C prototype for `func` under another name
+fn c_signature(func: &CFunctionDecl, name: &str) -> String {
+    let parameters = if func.parameters.is_empty() {
+        "void".to_string()
+    } else {
+        func.parameters
+            .iter()
+            .map(|(param_type, param_name)| format!("{} {}", param_type, param_name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!("{} {}({})", func.return_type, name, parameters)
+}
+
+/// Name of the Rust struct giving an opaque type its layout, e.g. `AccConfig` for `acc_config`
+fn rust_struct_name(struct_tag: &str) -> String {
+    struct_tag
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Converts a C value from the stub tables to a Rust expression of the same type
+fn rust_value(c_value: &str, c_type: &str) -> String {
+    if c_value == "NULL" && c_type.starts_with("const") {
+        "core::ptr::null()".to_string()
+    } else if c_value == "NULL" {
+        "core::ptr::null_mut()".to_string()
+    } else if c_value.starts_with('(') {
+        // Compound literal of a zeroed value
+        "core::mem::zeroed()".to_string()
+    } else if c_value.starts_with(|c: char| c.is_ascii_uppercase()) {
+        // bindgen prefixes enum constants with their type
+        format!("{}_{}", c_type, c_value)
+    } else if let Some(number) = c_value
+        .strip_suffix('f')
+        .filter(|n| n.parse::<f32>().is_ok())
+    {
+        number.to_string()
+    } else {
+        c_value.to_string()
+    }
+}
+
+/// Converts a Rust value to what the `trace` recorder of `kind` takes
+fn rust_trace_value(kind: &str, value: &str) -> String {
+    match kind {
+        "pointer" => value.to_string(),
+        "float" => format!("{} as f64", value),
+        _ => format!("{} as i64", value),
+    }
+}
+
+/// Which `acc_stub_trace_*` recorder a value of this C type goes through
+fn trace_kind(c_type: &str) -> Option<&'static str> {
+    match c_type {
+        _ if c_type.ends_with('*') => Some("pointer"),
+        "float" | "double" => Some("float"),
+        "void" => None,
+        // Integers, bool and the SDK enums
+        _ if !c_type.starts_with("struct") => Some("int"),
+        _ => None,
+    }
+}
+
+/// Splits Rust source into identifiers, literals and punctuation
+fn tokenize(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if c == '"' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(chars[start..i.min(chars.len())].iter().collect());
+        } else if (c == ':' || c == '-')
+            && chars.get(i + 1) == Some(&if c == ':' { ':' } else { '>' })
+        {
+            tokens.push(chars[i..i + 2].iter().collect());
+            i += 2;
+        } else {
+            tokens.push(c.to_string());
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Splits a parameter list on commas that are not nested in brackets
+fn split_top_level(tokens: &[String]) -> Vec<&[String]> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "(" | "<" | "[" => depth += 1,
+            ")" | ">" | "]" => depth -= 1,
+            "," if depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+/// Converts a bindgen Rust type back to its C spelling
+fn c_type_from_rust(tokens: &[String]) -> Option<String> {
+    if tokens.first()? == "*" {
+        let mutable = tokens.get(1)? == "mut";
+        let inner = c_type_from_rust(&tokens[2..])?;
+        return Some(if inner.ends_with('*') {
+            format!("{}{}", inner, if mutable { "*" } else { " const *" })
+        } else if mutable {
+            format!("{} *", inner)
+        } else {
+            format!("const {} *", inner)
+        });
+    }
+
+    // Function pointers and generics have no use in the stubbed API
+    if tokens.iter().any(|t| t == "<" || t == "fn") {
+        return None;
+    }
+
+    let name = tokens.last()?;
+    let c_type = match name.as_str() {
+        "u8" => "uint8_t",
+        "i8" => "int8_t",
+        "u16" => "uint16_t",
+        "i16" => "int16_t",
+        "u32" => "uint32_t",
+        "i32" => "int32_t",
+        "u64" => "uint64_t",
+        "i64" => "int64_t",
+        "usize" => "size_t",
+        "f32" => "float",
+        "f64" => "double",
+        "c_char" => "char",
+        "c_void" => "void",
+        "c_int" => "int",
+        "c_uint" => "unsigned int",
+        other => other,
+    };
+    Some(c_type.to_string())
+}
+
+/// Joins the tokens of a bindgen Rust type back into source text
+fn rust_type_text(tokens: &[String]) -> String {
+    let mut text = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let spaced = match i.checked_sub(1).map(|p| tokens[p].as_str()) {
+            None | Some("*") | Some("::") => false,
+            Some("mut") | Some("const") => true,
+            Some(_) => token != "::",
+        };
+        if spaced {
+            text.push(' ');
+        }
+        text.push_str(token);
+    }
+    text
 }
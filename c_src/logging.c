@@ -1,20 +1,371 @@
 #include "../rss/include/acc_definitions_common.h"
 #include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#include "logging.h"
+
+#ifdef A121_SYS_LOG_BUNDLED_PRINTF
+// Formatting without the toolchain's libc, see printf.h
+#include "printf.h"
+#define log_vsnprintf a121_vsnprintf
+#define log_snprintf a121_snprintf
+#else
 #include <stdio.h>
+#define log_vsnprintf vsnprintf
+#define log_snprintf snprintf
+#endif
+
+// Size of the buffer a log line is formatted into, including the NUL. Set through the
+// A121_SYS_LOG_BUFFER_SIZE environment variable when building the crate.
+#ifndef A121_SYS_LOG_BUFFER_SIZE
+#define A121_SYS_LOG_BUFFER_SIZE (256U)
+#endif
+
+// Flags passed with each line, must match src/logging.rs
+#define LOG_FLAG_TRUNCATED (1U)
+#define LOG_FLAG_CONTINUED (2U)
+
+// Defined by the crate, checks the module log filter before anything is formatted
+extern bool a121_sys_log_enabled(acc_log_level_t level, const char *module);
+
+#if defined(A121_SYS_LOG_DEFMT) || defined(A121_SYS_LOG_CHUNKS)
+
+// Longest single conversion, e.g. a float or a padded integer, longer ones are cut
+#define CONVERSION_LENGTH (32U)
+// Longest conversion spec, "%-+ #0" flags, width, precision and length modifier
+#define SPEC_LENGTH (24U)
+// Largest width or precision, numbers are cut at CONVERSION_LENGTH anyway, and strings are padded
+// or cut to at most this many characters. Reading the digits stops here, so they can not overflow.
+#define COUNT_LIMIT (0xFFFF)
+
+struct c_log_args {
+    const char *format;
+    va_list args;
+};
+
+// Local strlen, strchr and memcpy, so that with bundled_printf the log wrapper needs nothing from
+// the toolchain's libc
+
+static size_t text_length(const char *text) {
+    size_t length = 0;
+    while (text[length] != '\0') {
+        length++;
+    }
+    return length;
+}
+
+// First c in text, or the terminating NUL
+static const char *find_char(const char *text, char c) {
+    while (*text != '\0' && *text != c) {
+        text++;
+    }
+    return text;
+}
+
+static bool is_one_of(char c, const char *set) {
+    return c != '\0' && *find_char(set, c) != '\0';
+}
+
+static void copy_text(char *destination, const char *source, size_t length) {
+    for (size_t i = 0; i < length; i++) {
+        destination[i] = source[i];
+    }
+}
+
+#endif
+
+#if defined(A121_SYS_LOG_DEFMT)
+
+// Defined by the crate, writes the message to defmt by calling back into
+// c_log_write_segments
+extern void a121_sys_log_defmt(acc_log_level_t level, const char *module, struct c_log_args *args);
+
+void c_log_stub(acc_log_level_t level, const char *module, const char *format, ...) {
+    if (!a121_sys_log_enabled(level, module)) {
+        return;
+    }
+
+    struct c_log_args args;
+    args.format = format;
+    va_start(args.args, format);
+    a121_sys_log_defmt(level, module, &args);
+    va_end(args.args);
+}
+
+#else
+
+// Defined by the crate, hands the line to the installed log sink
+extern void a121_sys_log(acc_log_level_t level, const char *module, const char *message, uint32_t flags);
+
+#if defined(A121_SYS_LOG_CHUNKS)
 
-extern void rust_log(uint32_t level, const char *message);
+struct chunk_writer {
+    acc_log_level_t level;
+    const char *module;
+    size_t length;
+    char buffer[A121_SYS_LOG_BUFFER_SIZE];
+};
+
+static void write_chunk(void *context, const char *segment, size_t length) {
+    struct chunk_writer *writer = context;
+    while (length > 0) {
+        if (writer->length == sizeof(writer->buffer) - 1U) {
+            // Only sent once more text follows, so the last chunk is never empty
+            writer->buffer[writer->length] = '\0';
+            a121_sys_log(writer->level, writer->module, writer->buffer, LOG_FLAG_CONTINUED);
+            writer->length = 0;
+        }
+
+        size_t space = sizeof(writer->buffer) - 1U - writer->length;
+        size_t part = length < space ? length : space;
+        copy_text(&writer->buffer[writer->length], segment, part);
+        writer->length += part;
+        segment += part;
+        length -= part;
+    }
+}
+
+void c_log_stub(acc_log_level_t level, const char *module, const char *format, ...) {
+    if (!a121_sys_log_enabled(level, module)) {
+        return;
+    }
+
+    struct chunk_writer writer;
+    writer.level = level;
+    writer.module = module;
+    writer.length = 0;
+
+    struct c_log_args args;
+    args.format = format;
+    va_start(args.args, format);
+    c_log_write_segments(&args, write_chunk, &writer);
+    va_end(args.args);
+
+    writer.buffer[writer.length] = '\0';
+    a121_sys_log(level, module, writer.buffer, 0U);
+}
+
+#else
 
 void c_log_stub(acc_log_level_t level, const char *module, const char *format, ...) {
-    char message_buffer[128] = {0}; // Half the size for the message
-    char log_buffer[256] = {0};     // Full size for the final log string
+    if (!a121_sys_log_enabled(level, module)) {
+        return;
+    }
+
+    char message_buffer[A121_SYS_LOG_BUFFER_SIZE] = {0}; // The module is passed on separately
 
     va_list args;
     va_start(args, format);
-    vsnprintf(message_buffer, sizeof(message_buffer), format, args);
+    int length = log_vsnprintf(message_buffer, sizeof(message_buffer), format, args);
     va_end(args);
 
-    // Safely format the final log string
-    snprintf(log_buffer, sizeof(log_buffer), "%s: %s", module, message_buffer);
+    uint32_t flags = length >= (int)sizeof(message_buffer) ? LOG_FLAG_TRUNCATED : 0U;
+    a121_sys_log(level, module, message_buffer, flags);
+}
+
+#endif
+#endif
+
+#if defined(A121_SYS_LOG_DEFMT) || defined(A121_SYS_LOG_CHUNKS)
 
-    rust_log(level, log_buffer); // Call the Rust function
+static void write_padding(c_log_segment_t write, void *context, int width) {
+    static const char spaces[] = "        ";
+    while (width > 0) {
+        size_t length = (size_t)width < sizeof(spaces) - 1 ? (size_t)width : sizeof(spaces) - 1;
+        write(context, spaces, length);
+        width -= (int)length;
+    }
 }
+
+// Reads the digits of a width or precision, clamped to COUNT_LIMIT
+static const char *read_count(const char *cursor, int *count) {
+    *count = 0;
+    while (*cursor >= '0' && *cursor <= '9') {
+        if (*count <= COUNT_LIMIT) {
+            *count = *count * 10 + (*cursor - '0');
+        }
+        cursor++;
+    }
+    if (*count > COUNT_LIMIT) {
+        *count = COUNT_LIMIT;
+    }
+    return cursor;
+}
+
+static const char *write_conversion(c_log_segment_t write, void *context, const char *spec_start,
+                                    struct c_log_args *args) {
+    char spec[SPEC_LENGTH];
+    char value[CONVERSION_LENGTH];
+    size_t spec_length = 0;
+    const char *cursor = spec_start + 1;
+    bool left_align = false;
+    int width = -1;
+    int precision = -1;
+
+    spec[spec_length++] = '%';
+    while (is_one_of(*cursor, "-+ #0")) {
+        left_align = left_align || *cursor == '-';
+        if (spec_length < SPEC_LENGTH - 8U) {
+            spec[spec_length++] = *cursor;
+        }
+        cursor++;
+    }
+    if (*cursor == '*') {
+        width = va_arg(args->args, int);
+        if (width < 0) {
+            left_align = true;
+            // Not negated before the clamp, INT_MIN has no positive counterpart
+            width = width < -COUNT_LIMIT ? COUNT_LIMIT : -width;
+        } else if (width > COUNT_LIMIT) {
+            width = COUNT_LIMIT;
+        }
+        cursor++;
+    } else if (*cursor >= '0' && *cursor <= '9') {
+        cursor = read_count(cursor, &width);
+    }
+    if (*cursor == '.') {
+        cursor++;
+        precision = 0;
+        if (*cursor == '*') {
+            precision = va_arg(args->args, int);
+            if (precision > COUNT_LIMIT) {
+                precision = COUNT_LIMIT;
+            }
+            cursor++;
+        } else {
+            cursor = read_count(cursor, &precision);
+        }
+    }
+    if (left_align) {
+        // Repeated flags are allowed, this covers a negative width from the arguments
+        spec[spec_length++] = '-';
+    }
+    if (width < 0) {
+        width = 0;
+    }
+
+    const char *length_start = cursor;
+    while (is_one_of(*cursor, "hlLjzt")) {
+        cursor++;
+    }
+    size_t length_modifier = (size_t)(cursor - length_start);
+    if (*cursor == '\0') {
+        // Cut off spec at the end of the format, write it as it is
+        write(context, spec_start, (size_t)(cursor - spec_start));
+        return cursor;
+    }
+
+    // L is only known in front of a float conversion, whose long double is formatted as a double
+    bool is_long_double = length_modifier == 1U && length_start[0] == 'L';
+    bool has_long_double = length_start[0] == 'L' || (length_modifier == 2U && length_start[1] == 'L');
+    if (length_modifier > 2U || (has_long_double && !(is_long_double && is_one_of(*cursor, "fFeEgG")))) {
+        // The size of the argument is unknown, so the remaining arguments can not be found anymore
+        write(context, spec_start, (size_t)(cursor + 1 - spec_start));
+        return cursor + text_length(cursor);
+    }
+
+    // Width and precision are passed as arguments, so they need no room in the spec. A negative
+    // precision counts as none.
+    spec[spec_length++] = '*';
+    if (*cursor != 'c' && *cursor != 'p') {
+        spec[spec_length++] = '.';
+        spec[spec_length++] = '*';
+    }
+    if (!is_long_double) {
+        copy_text(&spec[spec_length], length_start, length_modifier);
+        spec_length += length_modifier;
+    }
+    spec[spec_length++] = *cursor;
+    spec[spec_length] = '\0';
+
+    bool is_long = length_modifier == 1U && length_start[0] == 'l';
+    bool is_long_long = length_modifier == 2U && length_start[0] == 'l';
+    bool is_size = length_modifier == 1U && (length_start[0] == 'z' || length_start[0] == 't');
+    bool is_max = length_modifier == 1U && length_start[0] == 'j';
+    int formatted = 0;
+
+    switch (*cursor) {
+    case '%':
+        write(context, "%", 1);
+        return cursor + 1;
+    case 's': {
+        // Strings are written from where they are, without a copy
+        const char *string = va_arg(args->args, const char *);
+        if (string == NULL) {
+            string = "(null)";
+        }
+        size_t string_length = text_length(string);
+        if (precision >= 0 && (size_t)precision < string_length) {
+            string_length = (size_t)precision;
+        }
+        int padding = width > (int)string_length ? width - (int)string_length : 0;
+        if (!left_align) {
+            write_padding(write, context, padding);
+        }
+        write(context, string, string_length);
+        if (left_align) {
+            write_padding(write, context, padding);
+        }
+        return cursor + 1;
+    }
+    case 'c':
+        formatted = log_snprintf(value, sizeof(value), spec, width, va_arg(args->args, int));
+        break;
+    case 'd':
+    case 'i':
+    case 'u':
+    case 'x':
+    case 'X':
+    case 'o':
+        if (is_long_long) {
+            formatted = log_snprintf(value, sizeof(value), spec, width, precision, va_arg(args->args, long long));
+        } else if (is_long) {
+            formatted = log_snprintf(value, sizeof(value), spec, width, precision, va_arg(args->args, long));
+        } else if (is_size) {
+            formatted = log_snprintf(value, sizeof(value), spec, width, precision, va_arg(args->args, size_t));
+        } else if (is_max) {
+            formatted = log_snprintf(value, sizeof(value), spec, width, precision, va_arg(args->args, intmax_t));
+        } else {
+            formatted = log_snprintf(value, sizeof(value), spec, width, precision, va_arg(args->args, int));
+        }
+        break;
+    case 'f':
+    case 'F':
+    case 'e':
+    case 'E':
+    case 'g':
+    case 'G': {
+        double number = is_long_double ? (double)va_arg(args->args, long double) : va_arg(args->args, double);
+        formatted = log_snprintf(value, sizeof(value), spec, width, precision, number);
+        break;
+    }
+    case 'p':
+        formatted = log_snprintf(value, sizeof(value), spec, width, va_arg(args->args, void *));
+        break;
+    default:
+        // Unknown conversion, the remaining arguments can not be found anymore
+        write(context, spec_start, (size_t)(cursor + 1 - spec_start));
+        return cursor + text_length(cursor);
+    }
+
+    if (formatted > 0) {
+        write(context, value, (size_t)formatted < sizeof(value) ? (size_t)formatted : sizeof(value) - 1U);
+    }
+    return cursor + 1;
+}
+
+void c_log_write_segments(struct c_log_args *args, c_log_segment_t write, void *context) {
+    const char *cursor = args->format;
+    while (*cursor != '\0') {
+        const char *literal_end = find_char(cursor, '%');
+        if (literal_end != cursor) {
+            // Text between conversions is written straight from the format string
+            write(context, cursor, (size_t)(literal_end - cursor));
+        }
+        cursor = *literal_end == '%' ? write_conversion(write, context, literal_end, args) : literal_end;
+    }
+}
+
+#endif
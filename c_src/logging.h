@@ -2,8 +2,22 @@
 #define LOGGING_H
 
 #include "../rss/include/acc_definitions_common.h"
+#include <stddef.h>
 
 void c_log_stub(acc_log_level_t level, const char *module, const char *format,
                 ...);
 
+#if defined(A121_SYS_LOG_DEFMT) || defined(A121_SYS_LOG_CHUNKS)
+struct c_log_args;
+
+// Receives one piece of a log message, not NUL terminated
+typedef void (*c_log_segment_t)(void *context, const char *segment,
+                                size_t length);
+
+// Formats the message of a c_log_stub call piece by piece, without staging the
+// whole line
+void c_log_write_segments(struct c_log_args *args, c_log_segment_t write,
+                          void *context);
+#endif
+
 #endif // LOGGING_H
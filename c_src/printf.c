@@ -0,0 +1,610 @@
+#include <stdbool.h>
+#include <stdint.h>
+
+#include "printf.h"
+
+// Fractional digits computed by %f, further requested digits are written as zeros
+#define MAX_FRACTION_DIGITS (17)
+// Largest value %f writes in full, larger values are written as with %e
+#define MAX_FIXED_VALUE (1e18)
+// Longest number without padding, the precision is capped to fit it
+#define NUMBER_LENGTH (64U)
+#define MAX_PRECISION (32)
+// Largest width or precision, far past any log line. Larger ones are clamped, so reading their
+// digits or negating a width argument can not overflow.
+#define MAX_COUNT (0xFFFF)
+
+#define FLAG_LEFT (1U << 0)
+#define FLAG_PLUS (1U << 1)
+#define FLAG_SPACE (1U << 2)
+#define FLAG_ALTERNATE (1U << 3)
+#define FLAG_ZERO (1U << 4)
+#define FLAG_UPPER (1U << 5)
+
+typedef enum {
+    LENGTH_DEFAULT,
+    LENGTH_CHAR,
+    LENGTH_SHORT,
+    LENGTH_LONG,
+    LENGTH_LONG_LONG,
+    LENGTH_MAX,
+    LENGTH_SIZE,
+    LENGTH_PTRDIFF,
+    LENGTH_LONG_DOUBLE,
+} length_modifier_t;
+
+typedef struct {
+    char *buffer;
+    size_t size;
+    size_t length;
+} output_t;
+
+static const double powers_of_10[MAX_FRACTION_DIGITS + 1] = {1e0,  1e1,  1e2,  1e3,  1e4,  1e5,  1e6,  1e7,  1e8,
+                                                              1e9,  1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16, 1e17};
+
+static void put(output_t *out, char c) {
+    // Keeps counting past the end, the return value is the length of the full output
+    if (out->length + 1U < out->size) {
+        out->buffer[out->length] = c;
+    }
+    out->length++;
+}
+
+static void put_repeated(output_t *out, char c, size_t count) {
+    while (count-- > 0) {
+        put(out, c);
+    }
+}
+
+static void put_string(output_t *out, const char *string, size_t length) {
+    for (size_t i = 0; i < length; i++) {
+        put(out, string[i]);
+    }
+}
+
+static size_t string_length(const char *string, size_t max_length) {
+    size_t length = 0;
+    while (length < max_length && string[length] != '\0') {
+        length++;
+    }
+    return length;
+}
+
+// Writes the prefix (sign or 0x) and body, padded to width with spaces or, after the prefix, zeros
+static void put_padded(output_t *out, const char *prefix, const char *body, size_t body_length, unsigned flags,
+                       size_t width) {
+    size_t prefix_length = string_length(prefix, 2U);
+    size_t length = prefix_length + body_length;
+    size_t padding = width > length ? width - length : 0;
+
+    if ((flags & (FLAG_LEFT | FLAG_ZERO)) == 0) {
+        put_repeated(out, ' ', padding);
+    }
+    put_string(out, prefix, prefix_length);
+    if ((flags & FLAG_LEFT) == 0 && (flags & FLAG_ZERO) != 0) {
+        put_repeated(out, '0', padding);
+    }
+    put_string(out, body, body_length);
+    if ((flags & FLAG_LEFT) != 0) {
+        put_repeated(out, ' ', padding);
+    }
+}
+
+// Reads the digits of a width or precision, clamped to MAX_COUNT
+static const char *parse_count(const char *format, int *count) {
+    *count = 0;
+    while (*format >= '0' && *format <= '9') {
+        if (*count <= MAX_COUNT) {
+            *count = *count * 10 + (*format - '0');
+        }
+        format++;
+    }
+    if (*count > MAX_COUNT) {
+        *count = MAX_COUNT;
+    }
+    return format;
+}
+
+static void sign_prefix(char *prefix, bool negative, unsigned flags) {
+    if (negative) {
+        *prefix++ = '-';
+    } else if ((flags & FLAG_PLUS) != 0) {
+        *prefix++ = '+';
+    } else if ((flags & FLAG_SPACE) != 0) {
+        *prefix++ = ' ';
+    }
+    *prefix = '\0';
+}
+
+static void put_integer(output_t *out, uintmax_t value, bool negative, unsigned base, unsigned flags, size_t width,
+                        int precision) {
+    const char *symbols = (flags & FLAG_UPPER) != 0 ? "0123456789ABCDEF" : "0123456789abcdef";
+    char digits[24];
+    char body[NUMBER_LENGTH];
+    char prefix[3];
+    size_t count = 0;
+    bool zero = value == 0;
+
+    while (value != 0) {
+        digits[count++] = symbols[value % base];
+        value /= base;
+    }
+    if (zero && precision != 0) {
+        digits[count++] = '0';
+    }
+
+    size_t min_digits = precision < 0 ? 1U : (size_t)precision;
+    if (min_digits > NUMBER_LENGTH) {
+        min_digits = NUMBER_LENGTH;
+    }
+    size_t zeros = min_digits > count ? min_digits - count : 0;
+    if (base == 8U && (flags & FLAG_ALTERNATE) != 0 && zeros == 0 && (count == 0 || digits[count - 1] != '0')) {
+        // The alternate form of octal starts with a 0
+        zeros = 1;
+    }
+
+    sign_prefix(prefix, negative, flags);
+    if (base == 16U && (flags & FLAG_ALTERNATE) != 0 && !zero) {
+        prefix[0] = '0';
+        prefix[1] = (flags & FLAG_UPPER) != 0 ? 'X' : 'x';
+        prefix[2] = '\0';
+    }
+    if (precision >= 0) {
+        flags &= ~FLAG_ZERO;
+    }
+
+    size_t length = 0;
+    while (zeros-- > 0) {
+        body[length++] = '0';
+    }
+    while (count > 0) {
+        body[length++] = digits[--count];
+    }
+    put_padded(out, prefix, body, length, flags, width);
+}
+
+// Splits value into a high part of 26 bits and the rest, so products of parts are exact
+static void split(double value, double *high, double *low) {
+    double scaled = value * 134217729.0; // 2^27 + 1
+    *high = scaled - (scaled - value);
+    *low = value - *high;
+}
+
+// What rounding took from the exact a * b to give product, by Dekker's product
+static double product_error(double a, double b, double product) {
+    double a_high, a_low, b_high, b_low;
+    split(a, &a_high, &a_low);
+    split(b, &b_high, &b_low);
+    return ((a_high * b_high - product) + a_high * b_low + a_low * b_high) + a_low * b_low;
+}
+
+// Writes a value below MAX_FIXED_VALUE with the given number of fractional digits, rounding to
+// nearest from the exact value and half to even only on exact ties, as glibc does
+static size_t format_fixed(char *body, double value, int precision, bool alternate) {
+    int computed = precision > MAX_FRACTION_DIGITS ? MAX_FRACTION_DIGITS : precision;
+    uint64_t whole = (uint64_t)value;
+    double fraction_part = value - (double)whole;
+    double scaled = fraction_part * powers_of_10[computed];
+    // The scaling can round a value just off a tie onto it, e.g. 0.15 to 1.5
+    double error = product_error(fraction_part, powers_of_10[computed], scaled);
+    uint64_t fraction = (uint64_t)scaled;
+    double remainder = scaled - (double)fraction;
+    uint64_t last_digit = computed == 0 ? whole : fraction;
+    bool round_up = remainder > 0.5 || (remainder == 0.5 && (error > 0.0 || (error == 0.0 && (last_digit & 1U) != 0)));
+
+    if (round_up && computed == 0) {
+        whole++;
+    } else if (round_up) {
+        fraction++;
+        if ((double)fraction >= powers_of_10[computed]) {
+            fraction = 0;
+            whole++;
+        }
+    }
+
+    char digits[20];
+    size_t count = 0;
+    do {
+        digits[count++] = (char)('0' + whole % 10U);
+        whole /= 10U;
+    } while (whole != 0);
+
+    size_t length = 0;
+    while (count > 0) {
+        body[length++] = digits[--count];
+    }
+    if (precision > 0 || alternate) {
+        body[length++] = '.';
+    }
+    for (int i = computed - 1; i >= 0; i--) {
+        body[length + (size_t)i] = (char)('0' + fraction % 10U);
+        fraction /= 10U;
+    }
+    length += (size_t)computed;
+    for (int i = computed; i < precision; i++) {
+        body[length++] = '0';
+    }
+    return length;
+}
+
+static size_t format_exponential(char *body, double value, int precision, bool alternate, bool upper,
+                                 int *exponent_out) {
+    int exponent = 0;
+    if (value != 0.0) {
+        // Large steps first, so few roundings add up
+        while (value >= 1e16) {
+            value /= 1e16;
+            exponent += 16;
+        }
+        while (value >= 10.0) {
+            value /= 10.0;
+            exponent++;
+        }
+        while (value < 1e-16) {
+            value *= 1e16;
+            exponent -= 16;
+        }
+        while (value < 1.0) {
+            value *= 10.0;
+            exponent--;
+        }
+    }
+
+    size_t length = format_fixed(body, value, precision, alternate);
+    if (length >= 2U && body[0] == '1' && body[1] == '0') {
+        // The mantissa rounded up to 10
+        value /= 10.0;
+        exponent++;
+        length = format_fixed(body, value, precision, alternate);
+    }
+
+    body[length++] = upper ? 'E' : 'e';
+    body[length++] = exponent < 0 ? '-' : '+';
+    unsigned magnitude = (unsigned)(exponent < 0 ? -exponent : exponent);
+    if (magnitude >= 100U) {
+        body[length++] = (char)('0' + magnitude / 100U);
+    }
+    body[length++] = (char)('0' + magnitude / 10U % 10U);
+    body[length++] = (char)('0' + magnitude % 10U);
+
+    *exponent_out = exponent;
+    return length;
+}
+
+// Drops trailing zeros of the fraction, and the point if nothing is left of it, as %g does
+static size_t strip_fraction_zeros(char *body, size_t length) {
+    size_t mantissa_end = 0;
+    bool point = false;
+    while (mantissa_end < length && body[mantissa_end] != 'e' && body[mantissa_end] != 'E') {
+        point = point || body[mantissa_end] == '.';
+        mantissa_end++;
+    }
+    if (!point) {
+        return length;
+    }
+
+    size_t end = mantissa_end;
+    while (body[end - 1] == '0') {
+        end--;
+    }
+    if (body[end - 1] == '.') {
+        end--;
+    }
+    for (size_t i = mantissa_end; i < length; i++) {
+        body[end + i - mantissa_end] = body[i];
+    }
+    return length - (mantissa_end - end);
+}
+
+static void put_float(output_t *out, double value, char conversion, unsigned flags, size_t width, int precision) {
+    union {
+        double value;
+        uint64_t bits;
+    } representation = {value};
+    bool negative = (representation.bits >> 63) != 0;
+    bool upper = conversion == 'F' || conversion == 'E' || conversion == 'G';
+    bool alternate = (flags & FLAG_ALTERNATE) != 0;
+    char body[NUMBER_LENGTH];
+    char prefix[2];
+    size_t length;
+    int exponent;
+
+    sign_prefix(prefix, negative, flags);
+    if (negative) {
+        value = -value;
+    }
+    if (value != value || value - value != 0.0) {
+        // NaN, or infinity where the difference is NaN
+        const char *text = value != value ? (upper ? "NAN" : "nan") : (upper ? "INF" : "inf");
+        put_padded(out, prefix, text, 3U, flags & ~FLAG_ZERO, width);
+        return;
+    }
+
+    if (precision < 0) {
+        precision = 6;
+    } else if (precision > MAX_PRECISION) {
+        precision = MAX_PRECISION;
+    }
+
+    switch (conversion) {
+    case 'f':
+    case 'F':
+        if (value < MAX_FIXED_VALUE) {
+            length = format_fixed(body, value, precision, alternate);
+        } else {
+            length = format_exponential(body, value, precision, alternate, upper, &exponent);
+        }
+        break;
+    case 'e':
+    case 'E':
+        length = format_exponential(body, value, precision, alternate, upper, &exponent);
+        break;
+    default: {
+        // %g uses %e for exponents below -4 or from the precision on, with the precision as
+        // significant digits
+        int significant = precision == 0 ? 1 : precision;
+        exponent = 0;
+        if (value != 0.0) {
+            (void)format_exponential(body, value, significant - 1, false, upper, &exponent);
+        }
+        if (exponent >= -4 && exponent < significant && value < MAX_FIXED_VALUE) {
+            length = format_fixed(body, value, significant - 1 - exponent, alternate);
+        } else {
+            length = format_exponential(body, value, significant - 1, alternate, upper, &exponent);
+        }
+        if (!alternate) {
+            length = strip_fraction_zeros(body, length);
+        }
+        break;
+    }
+    }
+
+    put_padded(out, prefix, body, length, flags, width);
+}
+
+static uintmax_t unsigned_argument(va_list *args, length_modifier_t length) {
+    switch (length) {
+    case LENGTH_CHAR:
+        return (unsigned char)va_arg(*args, unsigned int);
+    case LENGTH_SHORT:
+        return (unsigned short)va_arg(*args, unsigned int);
+    case LENGTH_LONG:
+        return va_arg(*args, unsigned long);
+    case LENGTH_LONG_LONG:
+        return va_arg(*args, unsigned long long);
+    case LENGTH_MAX:
+        return va_arg(*args, uintmax_t);
+    case LENGTH_SIZE:
+    case LENGTH_PTRDIFF:
+        return va_arg(*args, size_t);
+    default:
+        return va_arg(*args, unsigned int);
+    }
+}
+
+static intmax_t signed_argument(va_list *args, length_modifier_t length) {
+    switch (length) {
+    case LENGTH_CHAR:
+        return (signed char)va_arg(*args, int);
+    case LENGTH_SHORT:
+        return (short)va_arg(*args, int);
+    case LENGTH_LONG:
+        return va_arg(*args, long);
+    case LENGTH_LONG_LONG:
+        return va_arg(*args, long long);
+    case LENGTH_MAX:
+        return va_arg(*args, intmax_t);
+    case LENGTH_SIZE:
+    case LENGTH_PTRDIFF:
+        return va_arg(*args, ptrdiff_t);
+    default:
+        return va_arg(*args, int);
+    }
+}
+
+static const char *parse_length(const char *format, length_modifier_t *length) {
+    switch (*format) {
+    case 'h':
+        if (format[1] == 'h') {
+            *length = LENGTH_CHAR;
+            return format + 2;
+        }
+        *length = LENGTH_SHORT;
+        return format + 1;
+    case 'l':
+        if (format[1] == 'l') {
+            *length = LENGTH_LONG_LONG;
+            return format + 2;
+        }
+        *length = LENGTH_LONG;
+        return format + 1;
+    case 'j':
+        *length = LENGTH_MAX;
+        return format + 1;
+    case 'z':
+        *length = LENGTH_SIZE;
+        return format + 1;
+    case 't':
+        *length = LENGTH_PTRDIFF;
+        return format + 1;
+    case 'L':
+        *length = LENGTH_LONG_DOUBLE;
+        return format + 1;
+    default:
+        *length = LENGTH_DEFAULT;
+        return format;
+    }
+}
+
+static void vformat(output_t *out, const char *format, va_list *args) {
+    while (*format != '\0') {
+        if (*format != '%') {
+            put(out, *format++);
+            continue;
+        }
+        format++;
+
+        unsigned flags = 0;
+        for (bool more = true; more;) {
+            switch (*format) {
+            case '-':
+                flags |= FLAG_LEFT;
+                break;
+            case '+':
+                flags |= FLAG_PLUS;
+                break;
+            case ' ':
+                flags |= FLAG_SPACE;
+                break;
+            case '#':
+                flags |= FLAG_ALTERNATE;
+                break;
+            case '0':
+                flags |= FLAG_ZERO;
+                break;
+            default:
+                more = false;
+                continue;
+            }
+            format++;
+        }
+
+        int width_count = 0;
+        if (*format == '*') {
+            width_count = va_arg(*args, int);
+            if (width_count < 0) {
+                flags |= FLAG_LEFT;
+                // Not negated before the clamp, INT_MIN has no positive counterpart
+                width_count = width_count < -MAX_COUNT ? MAX_COUNT : -width_count;
+            } else if (width_count > MAX_COUNT) {
+                width_count = MAX_COUNT;
+            }
+            format++;
+        } else {
+            format = parse_count(format, &width_count);
+        }
+        size_t width = (size_t)width_count;
+
+        int precision = -1;
+        if (*format == '.') {
+            format++;
+            precision = 0;
+            if (*format == '*') {
+                precision = va_arg(*args, int);
+                if (precision < 0) {
+                    precision = -1;
+                } else if (precision > MAX_COUNT) {
+                    precision = MAX_COUNT;
+                }
+                format++;
+            } else {
+                format = parse_count(format, &precision);
+            }
+        }
+
+        length_modifier_t length;
+        format = parse_length(format, &length);
+        if ((flags & FLAG_LEFT) != 0) {
+            flags &= ~FLAG_ZERO;
+        }
+
+        char conversion = *format;
+        if (conversion == '\0') {
+            break;
+        }
+        format++;
+
+        switch (conversion) {
+        case 'd':
+        case 'i': {
+            intmax_t value = signed_argument(args, length);
+            uintmax_t magnitude = value < 0 ? (uintmax_t)0 - (uintmax_t)value : (uintmax_t)value;
+            put_integer(out, magnitude, value < 0, 10U, flags, width, precision);
+            break;
+        }
+        case 'u':
+            put_integer(out, unsigned_argument(args, length), false, 10U, flags & ~(FLAG_PLUS | FLAG_SPACE), width,
+                        precision);
+            break;
+        case 'o':
+            put_integer(out, unsigned_argument(args, length), false, 8U, flags & ~(FLAG_PLUS | FLAG_SPACE), width,
+                        precision);
+            break;
+        case 'X':
+            flags |= FLAG_UPPER;
+            // fall through
+        case 'x':
+            put_integer(out, unsigned_argument(args, length), false, 16U, flags & ~(FLAG_PLUS | FLAG_SPACE), width,
+                        precision);
+            break;
+        case 'f':
+        case 'F':
+        case 'e':
+        case 'E':
+        case 'g':
+        case 'G': {
+            double value = length == LENGTH_LONG_DOUBLE ? (double)va_arg(*args, long double) : va_arg(*args, double);
+            put_float(out, value, conversion, flags, width, precision);
+            break;
+        }
+        case 'c': {
+            char c = (char)va_arg(*args, int);
+            put_padded(out, "", &c, 1U, flags & ~FLAG_ZERO, width);
+            break;
+        }
+        case 's': {
+            const char *string = va_arg(*args, const char *);
+            if (string == NULL) {
+                string = "(null)";
+            }
+            size_t max_length = precision < 0 ? SIZE_MAX : (size_t)precision;
+            put_padded(out, "", string, string_length(string, max_length), flags & ~FLAG_ZERO, width);
+            break;
+        }
+        case 'p': {
+            void *pointer = va_arg(*args, void *);
+            if (pointer == NULL) {
+                put_padded(out, "", "(nil)", 5U, flags & ~FLAG_ZERO, width);
+            } else {
+                put_integer(out, (uintptr_t)pointer, false, 16U, (flags | FLAG_ALTERNATE) & ~FLAG_UPPER, width,
+                            precision);
+            }
+            break;
+        }
+        case 'n':
+            // Writing through the arguments is not supported, the pointer is skipped
+            (void)va_arg(*args, int *);
+            break;
+        case '%':
+            put(out, '%');
+            break;
+        default:
+            put(out, '%');
+            put(out, conversion);
+            break;
+        }
+    }
+}
+
+int a121_vsnprintf(char *buffer, size_t size, const char *format, va_list args) {
+    output_t out = {buffer, size, 0};
+    va_list copy;
+
+    // Copied so the arguments can be passed around by pointer, whatever va_list is
+    va_copy(copy, args);
+    vformat(&out, format, &copy);
+    va_end(copy);
+
+    if (size > 0) {
+        buffer[out.length < size ? out.length : size - 1U] = '\0';
+    }
+    return (int)out.length;
+}
+
+int a121_snprintf(char *buffer, size_t size, const char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    int length = a121_vsnprintf(buffer, size, format, args);
+    va_end(args);
+    return length;
+}
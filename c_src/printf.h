@@ -0,0 +1,17 @@
+#ifndef PRINTF_H
+#define PRINTF_H
+
+#include <stdarg.h>
+#include <stddef.h>
+
+// Self-contained snprintf used by the log wrapper with the bundled_printf
+// feature, so logging needs no libc formatting. Follows C99 for the flags,
+// width, precision, length modifiers and the d i u o x X c s p f F e E g G %
+// conversions, except that %f computes at most 17 fractional digits and
+// switches to %e above 1e18. Returns the length of the full output, like
+// snprintf.
+int a121_vsnprintf(char *buffer, size_t size, const char *format,
+                   va_list args);
+int a121_snprintf(char *buffer, size_t size, const char *format, ...);
+
+#endif // PRINTF_H
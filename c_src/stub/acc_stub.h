@@ -0,0 +1,198 @@
+#ifndef ACC_STUB_H
+#define ACC_STUB_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#include "acc_definitions_common.h"
+#include "acc_rss_a121.h"
+
+// Control interface of the stub library, only available with the `stub_library` feature.
+// None of these functions are thread safe, tests driving the stubs should not run concurrently.
+//
+// Like the real library, the stubs need acc_rss_hal_register to be given a valid HAL first:
+// handles are allocated through its mem_alloc/mem_free, errors are reported through its log
+// function, and creating anything before registration returns NULL. A HAL with a
+// max_spi_transfer_size below ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED or a missing function is rejected.
+//
+// The stubbed sensor drives the HAL's transfer function, or optimization.transfer16 when set, with
+// emulated register traffic: create, calibrate, prepare, measure and read each make burst accesses,
+// split into transfers no larger than max_spi_transfer_size or the buffer given to the call. This
+// exercises an SPI HAL's chunking and DMA paths on the host, though the registers are not the real
+// A121 register map.
+//
+// Config objects keep whatever their setters were given, so the getters round-trip, and fresh ones
+// report the SDK defaults. The sensor synthesizes IQ frames from a scene of point reflectors, laid
+// out according to the prepared config, and acc_processing_execute points the result frame at the
+// data read into the buffer. Buffer sizes are derived from the stored config, the frame data plus
+// ACC_STUB_BUFFER_OVERHEAD bytes, and preparing with a smaller buffer fails like on hardware.
+//
+// With presence, acc_detector_presence_process reports whatever the timeline scripted through
+// acc_stub_presence_timeline_add says for the current frame. The frame based
+// acc_detector_presence_processing_* API instead scores the frames it is given, with simplified
+// intra- and inter-frame filters driven by the processing config.
+//
+// acc_rss_assembly_test_execute steps through the enabled tests, asking for an enable pin toggle
+// and an interrupt wait on the way like the real test, and acc_rss_assembly_test_get_results
+// reports one result per test that ran.
+
+/**
+ * Forget the HAL given to acc_rss_hal_register, so the stubs behave as before registration.
+ * Handles allocated through the HAL must be destroyed first.
+ */
+void acc_stub_hal_unregister(void);
+
+/** Maximum number of point reflectors in the synthetic scene */
+#define ACC_STUB_SCENE_MAX_REFLECTORS (16U)
+
+/** Temperature reported by the stubbed sensor until changed */
+#define ACC_STUB_DEFAULT_TEMPERATURE (25)
+
+/**
+ * Remove all reflectors and restore the default noise level and temperature.
+ */
+void acc_stub_scene_clear(void);
+
+/**
+ * Add a point reflector to the scene seen by every stubbed sensor.
+ *
+ * @param distance_m Distance to the reflector at the first frame after prepare
+ * @param amplitude Peak amplitude of the reflection, in IQ units
+ * @param radial_velocity_m_s Radial velocity, positive when moving away from the sensor
+ * @return False if the scene is full
+ */
+bool acc_stub_scene_add_reflector(float distance_m, float amplitude, float radial_velocity_m_s);
+
+/**
+ * Add uniformly distributed noise of the given amplitude to every IQ component.
+ * The noise generator is deterministic and restarts on acc_stub_scene_clear.
+ */
+void acc_stub_scene_set_noise(float amplitude);
+
+/**
+ * Set the temperature reported in processing results.
+ */
+void acc_stub_scene_set_temperature(int16_t temperature);
+
+/**
+ * Bytes added on top of the frame data to every buffer size the stubs report,
+ * e.g. by acc_rss_get_buffer_size.
+ */
+#define ACC_STUB_BUFFER_OVERHEAD (256U)
+
+/** Maximum number of frame ranges with raised result flags */
+#define ACC_STUB_MAX_FRAME_FAULTS (16U)
+
+/** Flags that can be raised in acc_processing_result_t */
+#define ACC_STUB_FLAG_DATA_SATURATED (1U << 0)
+#define ACC_STUB_FLAG_FRAME_DELAYED (1U << 1)
+#define ACC_STUB_FLAG_CALIBRATION_NEEDED (1U << 2)
+
+/**
+ * Remove all injected faults.
+ */
+void acc_stub_faults_clear(void);
+
+/**
+ * Make acc_sensor_create return NULL.
+ */
+void acc_stub_fault_sensor_create(bool fail);
+
+/**
+ * Control the outcome of acc_sensor_calibrate.
+ *
+ * @param calls_until_complete Number of calls before cal_complete is set, counted per sensor
+ * @param fail Make every call return false
+ */
+void acc_stub_fault_calibration(uint32_t calls_until_complete, bool fail);
+
+/**
+ * Make acc_sensor_connected report the given sensor id as disconnected.
+ * Only ids below 32 can be disconnected.
+ */
+void acc_stub_fault_sensor_disconnected(acc_sensor_id_t sensor_id, bool disconnected);
+
+/**
+ * Raise flags in the processing result of a range of frames.
+ *
+ * Frames are counted per sensor, from 0 at the first acc_sensor_read after prepare.
+ *
+ * @param first_frame First frame of the range
+ * @param last_frame Last frame of the range, inclusive
+ * @param flags Combination of ACC_STUB_FLAG_* values
+ * @return False if too many ranges were added or the range is empty
+ */
+bool acc_stub_fault_frame_flags(uint32_t first_frame, uint32_t last_frame, uint32_t flags);
+
+/**
+ * Make an assembly test report failure. The interrupt test also fails when
+ * acc_rss_assembly_test_execute is told that waiting for the interrupt timed out,
+ * and the read and communication tests fail on a disconnected sensor.
+ */
+void acc_stub_fault_assembly_test(acc_rss_assembly_test_test_id_t test_id, bool fail);
+
+/** Number of calls kept in the trace buffer */
+#define ACC_STUB_TRACE_CAPACITY (256U)
+
+/** Maximum number of recorded arguments per call */
+#define ACC_STUB_TRACE_MAX_ARGS (9U)
+
+/** Kind of a recorded argument or return value */
+typedef enum {
+    ACC_STUB_TRACE_KIND_NONE,
+    ACC_STUB_TRACE_KIND_INT,
+    ACC_STUB_TRACE_KIND_FLOAT,
+    ACC_STUB_TRACE_KIND_POINTER,
+} acc_stub_trace_kind_t;
+
+/**
+ * A recorded argument or return value.
+ *
+ * Integers, bools and enums are stored in integer, pointers are stored as their address in integer,
+ * float values are stored in real.
+ */
+typedef struct {
+    acc_stub_trace_kind_t kind;
+    int64_t integer;
+    double real;
+} acc_stub_trace_value_t;
+
+/** A recorded call to a stubbed SDK function */
+typedef struct {
+    const char *function;
+    uint32_t num_args;
+    acc_stub_trace_value_t args[ACC_STUB_TRACE_MAX_ARGS];
+    acc_stub_trace_value_t result;
+} acc_stub_trace_entry_t;
+
+/**
+ * Start or stop recording calls to the stubbed SDK functions. Recording is off by default.
+ *
+ * Calls made by the stubs themselves, e.g. acc_sensor_prepare called from the presence detector, are recorded too.
+ */
+void acc_stub_trace_enable(bool enable);
+
+/**
+ * Remove all recorded calls and reset the dropped call counter.
+ */
+void acc_stub_trace_clear(void);
+
+/**
+ * Get the number of recorded calls.
+ */
+uint32_t acc_stub_trace_length(void);
+
+/**
+ * Get a recorded call, in call order.
+ *
+ * @param index Index of the call, below acc_stub_trace_length()
+ * @return The recorded call or NULL if the index is out of range
+ */
+const acc_stub_trace_entry_t *acc_stub_trace_get(uint32_t index);
+
+/**
+ * Get the number of calls not recorded because the trace buffer was full.
+ */
+uint32_t acc_stub_trace_dropped(void);
+
+#endif // ACC_STUB_H
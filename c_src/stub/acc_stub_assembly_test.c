@@ -0,0 +1,152 @@
+#include "acc_rss_a121.h"
+#include "acc_stub_internal.h"
+
+// One more than the last test id
+#define NUM_TESTS (ACC_RSS_ASSEMBLY_TEST_ID_SENSOR_CALIBRATION + 1)
+
+static const char *const test_names[NUM_TESTS] = {
+    "basic_read",
+    "communication",
+    "enable_pin",
+    "interrupt",
+    "clock_and_supply",
+    "sensor_calibration",
+};
+
+struct acc_rss_assembly_test {
+    acc_sensor_id_t sensor_id;
+    bool enabled[NUM_TESTS];
+    // Test run by the next call to execute, NUM_TESTS once every test has run
+    uint32_t current_test;
+    // COMPLETE was returned, the next call to execute starts over
+    bool complete;
+    // The current test handed the pin toggle or interrupt wait to the application
+    bool waiting;
+    acc_rss_assembly_test_result_t results[NUM_TESTS];
+    uint16_t num_results;
+};
+
+acc_rss_assembly_test_t *model_acc_rss_assembly_test_create(acc_sensor_id_t sensor_id, void *buffer,
+                                                            uint32_t buffer_size) {
+    if (buffer == NULL || buffer_size < ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE) {
+        return NULL;
+    }
+
+    acc_rss_assembly_test_t *assembly_test = acc_stub_alloc(sizeof(*assembly_test));
+    if (assembly_test == NULL) {
+        return NULL;
+    }
+
+    assembly_test->sensor_id = sensor_id;
+    acc_rss_assembly_test_enable_all_tests(assembly_test);
+    return assembly_test;
+}
+
+void model_acc_rss_assembly_test_destroy(acc_rss_assembly_test_t *assembly_test) {
+    acc_stub_free(assembly_test);
+}
+
+void model_acc_rss_assembly_test_enable_all_tests(acc_rss_assembly_test_t *assembly_test) {
+    if (assembly_test == NULL) {
+        return;
+    }
+
+    for (uint32_t i = 0; i < NUM_TESTS; i++) {
+        assembly_test->enabled[i] = true;
+    }
+}
+
+void model_acc_rss_assembly_test_disable_all_tests(acc_rss_assembly_test_t *assembly_test) {
+    if (assembly_test == NULL) {
+        return;
+    }
+
+    for (uint32_t i = 0; i < NUM_TESTS; i++) {
+        assembly_test->enabled[i] = false;
+    }
+}
+
+void model_acc_rss_assembly_test_enable(acc_rss_assembly_test_t *assembly_test,
+                                        acc_rss_assembly_test_test_id_t test_id) {
+    if (assembly_test != NULL && (uint32_t)test_id < NUM_TESTS) {
+        assembly_test->enabled[test_id] = true;
+    }
+}
+
+void model_acc_rss_assembly_test_disable(acc_rss_assembly_test_t *assembly_test,
+                                         acc_rss_assembly_test_test_id_t test_id) {
+    if (assembly_test != NULL && (uint32_t)test_id < NUM_TESTS) {
+        assembly_test->enabled[test_id] = false;
+    }
+}
+
+static void finish_test(acc_rss_assembly_test_t *assembly_test, bool passed) {
+    acc_rss_assembly_test_test_id_t test_id = (acc_rss_assembly_test_test_id_t)assembly_test->current_test;
+    if (acc_stub_assembly_test_fails(test_id)) {
+        passed = false;
+    }
+
+    acc_rss_assembly_test_result_t *result = &assembly_test->results[assembly_test->num_results];
+    result->test_name = test_names[test_id];
+    result->test_result = passed;
+    assembly_test->num_results++;
+    assembly_test->current_test++;
+    assembly_test->waiting = false;
+}
+
+acc_rss_test_state_t model_acc_rss_assembly_test_execute(acc_rss_assembly_test_t *assembly_test,
+                                                         acc_rss_test_integration_status_t integration_status) {
+    if (assembly_test == NULL) {
+        return ACC_RSS_TEST_STATE_COMPLETE;
+    }
+
+    if (assembly_test->complete) {
+        assembly_test->complete = false;
+        assembly_test->current_test = 0;
+        assembly_test->num_results = 0;
+    }
+
+    while (assembly_test->current_test < NUM_TESTS && !assembly_test->enabled[assembly_test->current_test]) {
+        assembly_test->current_test++;
+    }
+    if (assembly_test->current_test >= NUM_TESTS) {
+        assembly_test->complete = true;
+        return ACC_RSS_TEST_STATE_COMPLETE;
+    }
+
+    bool connected = !acc_stub_sensor_disconnected(assembly_test->sensor_id);
+    switch (assembly_test->current_test) {
+    case ACC_RSS_ASSEMBLY_TEST_ID_BASIC_READ:
+    case ACC_RSS_ASSEMBLY_TEST_ID_COMMUNICATION:
+        finish_test(assembly_test, connected);
+        break;
+    case ACC_RSS_ASSEMBLY_TEST_ID_ENABLE_PIN:
+        if (!assembly_test->waiting) {
+            assembly_test->waiting = true;
+            return ACC_RSS_TEST_STATE_TOGGLE_ENABLE_PIN;
+        }
+        finish_test(assembly_test, true);
+        break;
+    case ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT:
+        if (!assembly_test->waiting) {
+            assembly_test->waiting = true;
+            return ACC_RSS_TEST_STATE_WAIT_FOR_INTERRUPT;
+        }
+        finish_test(assembly_test, integration_status == ACC_RSS_TEST_INTEGRATION_STATUS_OK);
+        break;
+    default:
+        finish_test(assembly_test, true);
+        break;
+    }
+
+    return ACC_RSS_TEST_STATE_ONGOING;
+}
+
+const acc_rss_assembly_test_result_t *model_acc_rss_assembly_test_get_results(
+    const acc_rss_assembly_test_t *assembly_test, uint16_t *nbr_of_test_results) {
+    if (nbr_of_test_results != NULL) {
+        *nbr_of_test_results = assembly_test != NULL ? assembly_test->num_results : 0;
+    }
+
+    return assembly_test != NULL ? assembly_test->results : NULL;
+}
@@ -0,0 +1,179 @@
+#include <string.h>
+
+#include "acc_detector_distance.h"
+#include "acc_processing.h"
+#include "acc_stub_internal.h"
+
+struct acc_detector_distance_handle {
+    uint16_t num_points;
+    // The sensor config the detector would derive from its own config
+    acc_config_t *sensor_config;
+};
+
+/** Step lengths below 24 the RSS accepts, the divisors of 24 */
+static const uint16_t valid_step_lengths[] = {1, 2, 3, 4, 6, 8, 12};
+
+/** Step length the detector measures with for a profile, about a quarter of the pulse width */
+static uint16_t profile_step_length(acc_config_profile_t profile) {
+    switch (profile) {
+    case ACC_CONFIG_PROFILE_1:
+        return 1;
+    case ACC_CONFIG_PROFILE_2:
+        return 2;
+    case ACC_CONFIG_PROFILE_3:
+        return 6;
+    default:
+        return 12;
+    }
+}
+
+/** Longest step length the RSS accepts that is not above `limit`, which is at least 1 */
+static uint16_t valid_step_length_up_to(uint16_t limit) {
+    uint16_t step_length = valid_step_lengths[0];
+    for (size_t i = 0; i < sizeof(valid_step_lengths) / sizeof(valid_step_lengths[0]); i++) {
+        if (valid_step_lengths[i] <= limit) {
+            step_length = valid_step_lengths[i];
+        }
+    }
+    return step_length;
+}
+
+static uint32_t required_buffer_size(const acc_detector_distance_handle_t *handle) {
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(handle->sensor_config, &layout);
+    return acc_stub_buffer_size(&layout);
+}
+
+/** A threshold per point */
+static uint32_t required_cal_result_static_size(const acc_detector_distance_handle_t *handle) {
+    return handle->num_points * sizeof(float);
+}
+
+acc_detector_distance_handle_t *model_acc_detector_distance_create(const acc_detector_distance_config_t *config) {
+    if (config == NULL) {
+        return NULL;
+    }
+
+    acc_detector_distance_handle_t *handle = acc_stub_alloc(sizeof(*handle));
+    if (handle == NULL) {
+        return NULL;
+    }
+
+    acc_config_profile_t profile = acc_detector_distance_config_max_profile_get(config);
+    uint16_t step_length = profile_step_length(profile);
+    uint16_t max_step_length = acc_detector_distance_config_max_step_length_get(config);
+    if (max_step_length > 0 && max_step_length < step_length) {
+        step_length = valid_step_length_up_to(max_step_length);
+    }
+    int32_t start_point = acc_processing_meter_to_points(acc_detector_distance_config_start_get(config));
+    int32_t end_point = acc_processing_meter_to_points(acc_detector_distance_config_end_get(config));
+    int32_t num_points = end_point >= start_point ? (end_point - start_point) / step_length + 1 : 1;
+    if (num_points > UINT16_MAX) {
+        acc_stub_log(ACC_LOG_LEVEL_ERROR, "distance", "Range of %ld points does not fit a sensor config",
+                     (long)num_points);
+        acc_stub_free(handle);
+        return NULL;
+    }
+    handle->num_points = (uint16_t)num_points;
+
+    handle->sensor_config = acc_config_create();
+    if (handle->sensor_config == NULL) {
+        acc_detector_distance_destroy(handle);
+        return NULL;
+    }
+
+    acc_config_start_point_set(handle->sensor_config, start_point);
+    acc_config_num_points_set(handle->sensor_config, handle->num_points);
+    acc_config_step_length_set(handle->sensor_config, step_length);
+    acc_config_profile_set(handle->sensor_config, profile);
+    return handle;
+}
+
+void model_acc_detector_distance_destroy(acc_detector_distance_handle_t *handle) {
+    if (handle == NULL) {
+        return;
+    }
+
+    acc_config_destroy(handle->sensor_config);
+    acc_stub_free(handle);
+}
+
+bool model_acc_detector_distance_get_sizes(const acc_detector_distance_handle_t *handle, uint32_t *buffer_size,
+                                           uint32_t *detector_cal_result_static_size) {
+    if (handle == NULL || buffer_size == NULL || detector_cal_result_static_size == NULL) {
+        return false;
+    }
+
+    *buffer_size = required_buffer_size(handle);
+    *detector_cal_result_static_size = required_cal_result_static_size(handle);
+    return true;
+}
+
+bool model_acc_detector_distance_calibrate(acc_sensor_t *sensor, acc_detector_distance_handle_t *handle,
+                                           const acc_cal_result_t *sensor_cal_result, void *buffer,
+                                           uint32_t buffer_size, uint8_t *detector_cal_result_static,
+                                           uint32_t detector_cal_result_static_size,
+                                           acc_detector_cal_result_dynamic_t *detector_cal_result_dynamic,
+                                           bool *calibration_complete) {
+    if (sensor == NULL || handle == NULL || buffer == NULL || detector_cal_result_static == NULL ||
+        detector_cal_result_dynamic == NULL || calibration_complete == NULL ||
+        !acc_sensor_validate_calibration(sensor_cal_result)) {
+        return false;
+    }
+    if (buffer_size < required_buffer_size(handle) ||
+        detector_cal_result_static_size < required_cal_result_static_size(handle)) {
+        return false;
+    }
+
+    memset(detector_cal_result_static, 0, required_cal_result_static_size(handle));
+    memset(detector_cal_result_dynamic, 0, sizeof(*detector_cal_result_dynamic));
+    *calibration_complete = true;
+    return true;
+}
+
+bool model_acc_detector_distance_update_calibration(acc_sensor_t *sensor, acc_detector_distance_handle_t *handle,
+                                                    const acc_cal_result_t *sensor_cal_result, void *buffer,
+                                                    uint32_t buffer_size,
+                                                    acc_detector_cal_result_dynamic_t *detector_cal_result_dynamic,
+                                                    bool *calibration_complete) {
+    if (sensor == NULL || handle == NULL || buffer == NULL || detector_cal_result_dynamic == NULL ||
+        calibration_complete == NULL || !acc_sensor_validate_calibration(sensor_cal_result)) {
+        return false;
+    }
+    if (buffer_size < required_buffer_size(handle)) {
+        return false;
+    }
+
+    memset(detector_cal_result_dynamic, 0, sizeof(*detector_cal_result_dynamic));
+    *calibration_complete = true;
+    return true;
+}
+
+bool model_acc_detector_distance_prepare(const acc_detector_distance_handle_t *handle,
+                                         const acc_detector_distance_config_t *config, acc_sensor_t *sensor,
+                                         const acc_cal_result_t *sensor_cal_result, void *buffer,
+                                         uint32_t buffer_size) {
+    if (handle == NULL || config == NULL) {
+        return false;
+    }
+
+    return acc_sensor_prepare(sensor, handle->sensor_config, sensor_cal_result, buffer, buffer_size);
+}
+
+void model_acc_detector_distance_config_log(const acc_detector_distance_handle_t *handle,
+                                            const acc_detector_distance_config_t *config) {
+    if (config == NULL) {
+        return;
+    }
+
+    // Read in order, the evaluation order of arguments is unspecified
+    float start = acc_detector_distance_config_start_get(config);
+    float end = acc_detector_distance_config_end_get(config);
+    acc_config_profile_t max_profile = acc_detector_distance_config_max_profile_get(config);
+    uint16_t max_step_length = acc_detector_distance_config_max_step_length_get(config);
+    acc_stub_log(ACC_LOG_LEVEL_INFO, "distance", "start: %.3f m, end: %.3f m, max_profile: %u, max_step_length: %u",
+                 start, end, (unsigned)max_profile, (unsigned)max_step_length);
+    if (handle != NULL) {
+        acc_stub_log(ACC_LOG_LEVEL_INFO, "distance", "num_points: %u", (unsigned)handle->num_points);
+    }
+}
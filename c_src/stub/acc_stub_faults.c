@@ -0,0 +1,100 @@
+#include "acc_stub.h"
+#include "acc_stub_internal.h"
+
+typedef struct {
+    uint32_t first_frame;
+    uint32_t last_frame;
+    uint32_t flags;
+} frame_fault_t;
+
+static bool sensor_create_fails = false;
+static uint32_t calibration_calls = 1;
+static bool calibration_fails = false;
+static uint32_t disconnected_sensors = 0;
+static frame_fault_t frame_faults[ACC_STUB_MAX_FRAME_FAULTS];
+static uint32_t num_frame_faults = 0;
+static uint32_t failing_assembly_tests = 0;
+
+void acc_stub_faults_clear(void) {
+    sensor_create_fails = false;
+    calibration_calls = 1;
+    calibration_fails = false;
+    disconnected_sensors = 0;
+    num_frame_faults = 0;
+    failing_assembly_tests = 0;
+}
+
+void acc_stub_fault_sensor_create(bool fail) {
+    sensor_create_fails = fail;
+}
+
+void acc_stub_fault_calibration(uint32_t calls_until_complete, bool fail) {
+    calibration_calls = calls_until_complete > 0 ? calls_until_complete : 1;
+    calibration_fails = fail;
+}
+
+void acc_stub_fault_sensor_disconnected(acc_sensor_id_t sensor_id, bool disconnected) {
+    if (sensor_id >= 32) {
+        return;
+    }
+
+    if (disconnected) {
+        disconnected_sensors |= 1U << sensor_id;
+    } else {
+        disconnected_sensors &= ~(1U << sensor_id);
+    }
+}
+
+bool acc_stub_fault_frame_flags(uint32_t first_frame, uint32_t last_frame, uint32_t flags) {
+    if (num_frame_faults >= ACC_STUB_MAX_FRAME_FAULTS || last_frame < first_frame) {
+        return false;
+    }
+
+    frame_faults[num_frame_faults].first_frame = first_frame;
+    frame_faults[num_frame_faults].last_frame = last_frame;
+    frame_faults[num_frame_faults].flags = flags;
+    num_frame_faults++;
+    return true;
+}
+
+void acc_stub_fault_assembly_test(acc_rss_assembly_test_test_id_t test_id, bool fail) {
+    if ((uint32_t)test_id >= 32) {
+        return;
+    }
+
+    if (fail) {
+        failing_assembly_tests |= 1U << test_id;
+    } else {
+        failing_assembly_tests &= ~(1U << test_id);
+    }
+}
+
+bool acc_stub_sensor_create_fails(void) {
+    return sensor_create_fails;
+}
+
+bool acc_stub_sensor_disconnected(acc_sensor_id_t sensor_id) {
+    return sensor_id < 32 && (disconnected_sensors & (1U << sensor_id)) != 0;
+}
+
+uint32_t acc_stub_calibration_calls(void) {
+    return calibration_calls;
+}
+
+bool acc_stub_calibration_fails(void) {
+    return calibration_fails;
+}
+
+uint32_t acc_stub_frame_flags(uint32_t frame_index) {
+    uint32_t flags = 0;
+    for (uint32_t i = 0; i < num_frame_faults; i++) {
+        if (frame_index >= frame_faults[i].first_frame && frame_index <= frame_faults[i].last_frame) {
+            flags |= frame_faults[i].flags;
+        }
+    }
+    return flags;
+}
+
+bool acc_stub_assembly_test_fails(acc_rss_assembly_test_test_id_t test_id) {
+    return (uint32_t)test_id < 32 && (failing_assembly_tests & (1U << test_id)) != 0;
+}
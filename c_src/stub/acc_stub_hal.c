@@ -0,0 +1,114 @@
+#include <stdarg.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "acc_hal_definitions_a121.h"
+#include "acc_rss_a121.h"
+#include "acc_stub.h"
+#include "acc_stub_internal.h"
+
+// Longest log line handed to the HAL, longer lines are cut
+#define LOG_LINE_LENGTH (128U)
+
+// Every emulated SPI transfer starts with a header word holding the register address,
+// with the top bit set for writes
+#define SPI_WRITE_FLAG (0x8000U)
+#define SPI_ADDRESS_MASK (0x7FFFU)
+
+static acc_hal_a121_t registered_hal;
+static bool hal_registered = false;
+
+bool model_acc_rss_hal_register(const acc_hal_a121_t *hal) {
+    if (hal == NULL || hal->mem_alloc == NULL || hal->mem_free == NULL || hal->transfer == NULL ||
+        hal->log == NULL) {
+        return false;
+    }
+    if (hal->max_spi_transfer_size < ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED) {
+        hal->log(ACC_LOG_LEVEL_ERROR, "rss", "max_spi_transfer_size %u is below the required %u",
+                 (unsigned)hal->max_spi_transfer_size, ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED);
+        return false;
+    }
+
+    registered_hal = *hal;
+    hal_registered = true;
+    return true;
+}
+
+void acc_stub_hal_unregister(void) {
+    hal_registered = false;
+}
+
+bool acc_stub_hal_registered(void) {
+    return hal_registered;
+}
+
+void *acc_stub_alloc(size_t size) {
+    if (!hal_registered) {
+        return NULL;
+    }
+
+    void *memory = registered_hal.mem_alloc(size);
+    if (memory == NULL) {
+        acc_stub_log(ACC_LOG_LEVEL_ERROR, "mem", "Out of memory, %u bytes requested", (unsigned)size);
+        return NULL;
+    }
+
+    memset(memory, 0, size);
+    return memory;
+}
+
+void acc_stub_free(void *memory) {
+    if (memory != NULL && hal_registered) {
+        registered_hal.mem_free(memory);
+    }
+}
+
+void acc_stub_log(acc_log_level_t level, const char *module, const char *format, ...) {
+    if (!hal_registered) {
+        return;
+    }
+
+    char line[LOG_LINE_LENGTH];
+    va_list args;
+    va_start(args, format);
+    vsnprintf(line, sizeof(line), format, args);
+    va_end(args);
+
+    registered_hal.log(level, module, "%s", line);
+}
+
+void acc_stub_spi_burst(acc_sensor_id_t sensor_id, uint16_t address, bool write, uint32_t num_words, void *buffer,
+                        uint32_t buffer_size) {
+    // Used when the caller has no buffer, sized to what every HAL has to handle
+    uint32_t scratch[ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED / sizeof(uint32_t)];
+
+    if (!hal_registered || num_words == 0) {
+        return;
+    }
+    if (buffer == NULL || buffer_size < sizeof(scratch)) {
+        buffer = scratch;
+        buffer_size = sizeof(scratch);
+    }
+
+    uint32_t transfer_size = registered_hal.max_spi_transfer_size < buffer_size ? registered_hal.max_spi_transfer_size
+                                                                                : buffer_size;
+    uint32_t max_payload = transfer_size / sizeof(uint16_t) - 1;
+    uint16_t *words = buffer;
+    while (num_words > 0) {
+        uint32_t payload = num_words < max_payload ? num_words : max_payload;
+        words[0] = (uint16_t)((write ? SPI_WRITE_FLAG : 0U) | (address & SPI_ADDRESS_MASK));
+        for (uint32_t i = 1; i <= payload; i++) {
+            // Written registers get a recognizable value, read ones are filled in by the sensor
+            words[i] = write ? (uint16_t)(address + i - 1) : 0U;
+        }
+
+        if (registered_hal.optimization.transfer16 != NULL) {
+            registered_hal.optimization.transfer16(sensor_id, words, payload + 1);
+        } else {
+            registered_hal.transfer(sensor_id, (uint8_t *)words, (payload + 1) * sizeof(uint16_t));
+        }
+
+        address = (uint16_t)(address + payload);
+        num_words -= payload;
+    }
+}
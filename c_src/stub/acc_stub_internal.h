@@ -0,0 +1,97 @@
+#ifndef ACC_STUB_INTERNAL_H
+#define ACC_STUB_INTERNAL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#include "acc_config.h"
+#include "acc_config_subsweep.h"
+#include "acc_definitions_common.h"
+#include "acc_rss_a121.h"
+
+// Shared between the hand written stub models, not part of the control interface
+
+bool acc_stub_hal_registered(void);
+
+/** Zeroed memory from the registered HAL, NULL before acc_rss_hal_register */
+void *acc_stub_alloc(size_t size);
+
+void acc_stub_free(void *memory);
+
+/** Formats a log line for the registered HAL, dropped before acc_rss_hal_register */
+void acc_stub_log(acc_log_level_t level, const char *module, const char *format, ...);
+
+/**
+ * Emulates a burst access of num_words consecutive 16-bit registers through the registered HAL,
+ * using transfer16 when set. The access is split into transfers fitting both the buffer and
+ * max_spi_transfer_size, each starting with a header word. A NULL buffer means a small internal one.
+ */
+void acc_stub_spi_burst(acc_sensor_id_t sensor_id, uint16_t address, bool write, uint32_t num_words, void *buffer,
+                        uint32_t buffer_size);
+
+/** The parts of a sensor config that shape the produced frames */
+typedef struct {
+    uint8_t num_subsweeps;
+    int32_t start_point[ACC_MAX_NUM_SUBSWEEPS];
+    uint16_t num_points[ACC_MAX_NUM_SUBSWEEPS];
+    uint16_t step_length[ACC_MAX_NUM_SUBSWEEPS];
+    uint16_t hwaas[ACC_MAX_NUM_SUBSWEEPS];
+    acc_config_profile_t profile[ACC_MAX_NUM_SUBSWEEPS];
+    uint16_t sweeps_per_frame;
+    float sweep_rate;
+    float frame_rate;
+    bool continuous_sweep_mode;
+    acc_config_idle_state_t inter_sweep_idle_state;
+} acc_stub_frame_layout_t;
+
+void acc_stub_frame_layout_from_config(const acc_config_t *config, acc_stub_frame_layout_t *layout);
+
+uint16_t acc_stub_sweep_length(const acc_stub_frame_layout_t *layout);
+
+uint32_t acc_stub_frame_length(const acc_stub_frame_layout_t *layout);
+
+float acc_stub_max_sweep_rate(const acc_stub_frame_layout_t *layout);
+
+bool acc_stub_high_speed_mode(const acc_stub_frame_layout_t *layout);
+
+/** Working memory needed to measure with the layout, as reported by acc_rss_get_buffer_size */
+uint32_t acc_stub_buffer_size(const acc_stub_frame_layout_t *layout);
+
+void acc_stub_scene_synthesize(const acc_stub_frame_layout_t *layout, uint32_t frame_index,
+                               acc_int16_complex_t *frame);
+
+int16_t acc_stub_scene_temperature(void);
+
+bool acc_stub_sensor_create_fails(void);
+
+bool acc_stub_sensor_disconnected(acc_sensor_id_t sensor_id);
+
+uint32_t acc_stub_calibration_calls(void);
+
+bool acc_stub_calibration_fails(void);
+
+uint32_t acc_stub_frame_flags(uint32_t frame_index);
+
+uint32_t acc_stub_sensor_last_frame_index(void);
+
+bool acc_stub_assembly_test_fails(acc_rss_assembly_test_test_id_t test_id);
+
+// Call trace recorders used by the generated exported functions.
+// acc_stub_trace_begin returns an invalid handle when tracing is off or the buffer is full,
+// the other recorders ignore it.
+uint32_t acc_stub_trace_begin(const char *function);
+
+void acc_stub_trace_int(uint32_t trace, int64_t value);
+
+void acc_stub_trace_float(uint32_t trace, double value);
+
+void acc_stub_trace_pointer(uint32_t trace, const void *value);
+
+void acc_stub_trace_result_int(uint32_t trace, int64_t value);
+
+void acc_stub_trace_result_float(uint32_t trace, double value);
+
+void acc_stub_trace_result_pointer(uint32_t trace, const void *value);
+
+#endif // ACC_STUB_INTERNAL_H
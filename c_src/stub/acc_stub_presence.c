@@ -0,0 +1,235 @@
+#include <math.h>
+#include <string.h>
+
+#include "acc_detector_presence.h"
+#include "acc_processing.h"
+#include "acc_stub_internal.h"
+#include "acc_stub_presence.h"
+
+// Spread of the synthesized depthwise score peak
+#define DEPTHWISE_PEAK_WIDTH_M (0.1f)
+
+struct acc_detector_presence_handle {
+    float start_m;
+    float step_length_m;
+    uint16_t num_points;
+    bool reset_filters_on_prepare;
+    acc_config_t *sensor_config;
+    acc_processing_t *processing;
+    acc_processing_metadata_t processing_metadata;
+    uint32_t frame_index;
+    float *depthwise_intra_presence_scores;
+    float *depthwise_inter_presence_scores;
+};
+
+static acc_stub_presence_segment_t segments[ACC_STUB_PRESENCE_MAX_SEGMENTS];
+static uint32_t num_segments = 0;
+
+void acc_stub_presence_timeline_clear(void) {
+    num_segments = 0;
+}
+
+bool acc_stub_presence_timeline_add(const acc_stub_presence_segment_t *segment) {
+    if (segment == NULL || num_segments >= ACC_STUB_PRESENCE_MAX_SEGMENTS ||
+        segment->last_frame < segment->first_frame) {
+        return false;
+    }
+
+    segments[num_segments] = *segment;
+    num_segments++;
+    return true;
+}
+
+static const acc_stub_presence_segment_t *find_segment(uint32_t frame_index) {
+    for (uint32_t i = num_segments; i > 0; i--) {
+        const acc_stub_presence_segment_t *segment = &segments[i - 1];
+        if (frame_index >= segment->first_frame && frame_index <= segment->last_frame) {
+            return segment;
+        }
+    }
+    return NULL;
+}
+
+static void fill_depthwise(const struct acc_detector_presence_handle *handle, float *scores, float score,
+                           const float *scripted, uint32_t scripted_length, float distance) {
+    for (uint16_t i = 0; i < handle->num_points; i++) {
+        if (scripted != NULL) {
+            scores[i] = i < scripted_length ? scripted[i] : 0.0f;
+        } else {
+            float offset = (handle->start_m + i * handle->step_length_m - distance) / DEPTHWISE_PEAK_WIDTH_M;
+            scores[i] = score * expf(-0.5f * offset * offset);
+        }
+    }
+}
+
+acc_detector_presence_handle_t *model_acc_detector_presence_create(acc_detector_presence_config_t *presence_config,
+                                                                   acc_detector_presence_metadata_t *metadata) {
+    if (presence_config == NULL) {
+        return NULL;
+    }
+
+    acc_detector_presence_handle_t *handle = acc_stub_alloc(sizeof(*handle));
+    if (handle == NULL) {
+        return NULL;
+    }
+
+    float start_m = acc_detector_presence_config_start_get(presence_config);
+    float end_m = acc_detector_presence_config_end_get(presence_config);
+    uint16_t step_length = acc_detector_presence_config_step_length_get(presence_config);
+    if (step_length == 0) {
+        step_length = 1;
+    }
+    int32_t start_point = acc_processing_meter_to_points(start_m);
+    int32_t end_point = acc_processing_meter_to_points(end_m);
+    int32_t num_points = end_point >= start_point ? (end_point - start_point) / step_length + 1 : 1;
+
+    handle->start_m = acc_processing_points_to_meter(start_point);
+    handle->step_length_m = acc_processing_points_to_meter(step_length);
+    handle->num_points = (uint16_t)num_points;
+    handle->reset_filters_on_prepare = acc_detector_presence_config_reset_filters_on_prepare_get(presence_config);
+
+    // The sensor config the detector would derive from its own config
+    handle->sensor_config = acc_config_create();
+    handle->depthwise_intra_presence_scores = acc_stub_alloc(handle->num_points * sizeof(float));
+    handle->depthwise_inter_presence_scores = acc_stub_alloc(handle->num_points * sizeof(float));
+    if (handle->sensor_config == NULL || handle->depthwise_intra_presence_scores == NULL ||
+        handle->depthwise_inter_presence_scores == NULL) {
+        acc_detector_presence_destroy(handle);
+        return NULL;
+    }
+
+    acc_config_start_point_set(handle->sensor_config, start_point);
+    acc_config_num_points_set(handle->sensor_config, handle->num_points);
+    acc_config_step_length_set(handle->sensor_config, step_length);
+    acc_config_profile_set(handle->sensor_config, acc_detector_presence_config_profile_get(presence_config));
+    acc_config_hwaas_set(handle->sensor_config, acc_detector_presence_config_hwaas_get(presence_config));
+    acc_config_sweeps_per_frame_set(handle->sensor_config,
+                                    acc_detector_presence_config_sweeps_per_frame_get(presence_config));
+    if (!acc_detector_presence_config_frame_rate_app_driven_get(presence_config)) {
+        acc_config_frame_rate_set(handle->sensor_config, acc_detector_presence_config_frame_rate_get(presence_config));
+    }
+    acc_config_inter_frame_idle_state_set(handle->sensor_config,
+                                          acc_detector_presence_config_inter_frame_idle_state_get(presence_config));
+
+    handle->processing = acc_processing_create(handle->sensor_config, &handle->processing_metadata);
+    if (handle->processing == NULL) {
+        acc_detector_presence_destroy(handle);
+        return NULL;
+    }
+
+    if (metadata != NULL) {
+        metadata->start_m = handle->start_m;
+        metadata->end_m = handle->start_m + (handle->num_points - 1) * handle->step_length_m;
+        metadata->step_length_m = handle->step_length_m;
+        metadata->num_points = handle->num_points;
+        metadata->profile = acc_config_profile_get(handle->sensor_config);
+        metadata->sensor_config = handle->sensor_config;
+        metadata->processing_metadata = &handle->processing_metadata;
+    }
+
+    return handle;
+}
+
+/** Frame data of the sensor config plus the depthwise score arrays of the result */
+static uint32_t required_buffer_size(const acc_detector_presence_handle_t *handle) {
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(handle->sensor_config, &layout);
+    return acc_stub_buffer_size(&layout) + 2U * handle->num_points * sizeof(float);
+}
+
+bool model_acc_detector_presence_get_buffer_size(const acc_detector_presence_handle_t *presence_handle,
+                                                 uint32_t *buffer_size) {
+    if (presence_handle == NULL || buffer_size == NULL) {
+        return false;
+    }
+
+    *buffer_size = required_buffer_size(presence_handle);
+    return true;
+}
+
+void model_acc_detector_presence_destroy(acc_detector_presence_handle_t *presence_handle) {
+    if (presence_handle == NULL) {
+        return;
+    }
+
+    acc_processing_destroy(presence_handle->processing);
+    acc_config_destroy(presence_handle->sensor_config);
+    acc_stub_free(presence_handle->depthwise_intra_presence_scores);
+    acc_stub_free(presence_handle->depthwise_inter_presence_scores);
+    acc_stub_free(presence_handle);
+}
+
+bool model_acc_detector_presence_prepare(const acc_detector_presence_handle_t *presence_handle,
+                                         acc_detector_presence_config_t *presence_config, acc_sensor_t *sensor,
+                                         const acc_cal_result_t *cal_result, void *buffer, uint32_t buffer_size) {
+    // The handle was allocated mutable, the SDK signature only promises not to reconfigure it
+    acc_detector_presence_handle_t *handle = (acc_detector_presence_handle_t *)presence_handle;
+
+    if (handle == NULL || presence_config == NULL || buffer_size < required_buffer_size(handle)) {
+        return false;
+    }
+    if (!acc_sensor_prepare(sensor, handle->sensor_config, cal_result, buffer, buffer_size)) {
+        return false;
+    }
+
+    if (handle->reset_filters_on_prepare) {
+        handle->frame_index = 0;
+    }
+    return true;
+}
+
+bool model_acc_detector_presence_process(acc_detector_presence_handle_t *presence_handle, void *buffer,
+                                         acc_detector_presence_result_t *result) {
+    if (presence_handle == NULL || buffer == NULL || result == NULL) {
+        return false;
+    }
+
+    memset(result, 0, sizeof(*result));
+    acc_processing_execute(presence_handle->processing, buffer, &result->processing_result);
+
+    const acc_stub_presence_segment_t *segment = find_segment(presence_handle->frame_index);
+    if (segment != NULL) {
+        result->presence_detected = segment->presence_detected;
+        result->intra_presence_score = segment->intra_presence_score;
+        result->inter_presence_score = segment->inter_presence_score;
+        result->presence_distance = segment->presence_distance;
+        fill_depthwise(presence_handle, presence_handle->depthwise_intra_presence_scores,
+                       segment->intra_presence_score, segment->depthwise_intra_presence_scores,
+                       segment->depthwise_presence_scores_length, segment->presence_distance);
+        fill_depthwise(presence_handle, presence_handle->depthwise_inter_presence_scores,
+                       segment->inter_presence_score, segment->depthwise_inter_presence_scores,
+                       segment->depthwise_presence_scores_length, segment->presence_distance);
+    } else {
+        memset(presence_handle->depthwise_intra_presence_scores, 0, presence_handle->num_points * sizeof(float));
+        memset(presence_handle->depthwise_inter_presence_scores, 0, presence_handle->num_points * sizeof(float));
+    }
+
+    result->depthwise_intra_presence_scores = presence_handle->depthwise_intra_presence_scores;
+    result->depthwise_inter_presence_scores = presence_handle->depthwise_inter_presence_scores;
+    result->depthwise_presence_scores_length = presence_handle->num_points;
+    presence_handle->frame_index++;
+    return true;
+}
+
+float model_acc_detector_presence_get_distance_m(const acc_detector_presence_handle_t *presence_handle,
+                                                 uint16_t point_idx) {
+    if (presence_handle == NULL) {
+        return 0.0f;
+    }
+
+    return presence_handle->start_m + point_idx * presence_handle->step_length_m;
+}
+
+void model_acc_detector_presence_config_log(acc_detector_presence_config_t *presence_config) {
+    if (presence_config == NULL) {
+        return;
+    }
+
+    // Read in order, the evaluation order of arguments is unspecified
+    float start = acc_detector_presence_config_start_get(presence_config);
+    float end = acc_detector_presence_config_end_get(presence_config);
+    uint16_t step_length = acc_detector_presence_config_step_length_get(presence_config);
+    float frame_rate = acc_detector_presence_config_frame_rate_get(presence_config);
+    acc_stub_log(ACC_LOG_LEVEL_INFO, "presence", "start: %.3f m, end: %.3f m, step_length: %u, frame_rate: %.3f Hz",
+                 start, end, (unsigned)step_length, frame_rate);
+}
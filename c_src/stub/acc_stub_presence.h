@@ -0,0 +1,50 @@
+#ifndef ACC_STUB_PRESENCE_H
+#define ACC_STUB_PRESENCE_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+// Control interface of the stubbed presence detector, only available with the
+// `stub_library` and `presence` features.
+
+/** Maximum number of segments in the presence timeline */
+#define ACC_STUB_PRESENCE_MAX_SEGMENTS (32U)
+
+/**
+ * What the presence detector reports for a range of frames.
+ *
+ * Frames are counted per detector handle, from 0 at the first
+ * acc_detector_presence_process call after create or prepare.
+ */
+typedef struct {
+    /** First frame of the segment */
+    uint32_t first_frame;
+    /** Last frame of the segment, inclusive */
+    uint32_t last_frame;
+    bool presence_detected;
+    float intra_presence_score;
+    float inter_presence_score;
+    float presence_distance;
+    /**
+     * Depthwise scores, copied into the result up to the detector's num_points.
+     * When NULL, a peak of the segment score centered on presence_distance is reported.
+     * The arrays are not copied and must outlive the timeline.
+     */
+    const float *depthwise_intra_presence_scores;
+    const float *depthwise_inter_presence_scores;
+    uint32_t depthwise_presence_scores_length;
+} acc_stub_presence_segment_t;
+
+/**
+ * Remove all segments. Frames outside every segment report no presence and zero scores.
+ */
+void acc_stub_presence_timeline_clear(void);
+
+/**
+ * Append a segment to the timeline. Where segments overlap, the one added last wins.
+ *
+ * @return False if the timeline is full or the frame range is empty
+ */
+bool acc_stub_presence_timeline_add(const acc_stub_presence_segment_t *segment);
+
+#endif // ACC_STUB_PRESENCE_H
@@ -0,0 +1,276 @@
+#include <math.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "acc_detector_presence_processing.h"
+#include "acc_processing.h"
+#include "acc_stub_internal.h"
+
+// Working memory asked for per point, the filter state is kept in the handle instead
+#define BUFFER_BYTES_PER_POINT (8U * sizeof(float))
+// Smallest noise level the scores are normalized by, the IQ data is integer
+#define MIN_NOISE_LEVEL (1.0f)
+#define PI (3.14159265f)
+
+struct acc_detector_presence_processing_handle {
+    acc_stub_frame_layout_t layout;
+    uint16_t sweep_length;
+    float frame_rate;
+    bool intra_detection;
+    float intra_detection_threshold;
+    bool inter_detection;
+    float inter_detection_threshold;
+    float inter_frame_deviation_time_const;
+    float inter_frame_fast_cutoff;
+    float inter_frame_slow_cutoff;
+    float intra_frame_time_const;
+    float intra_output_time_const;
+    float inter_output_time_const;
+    bool filters_initialized;
+    float intra_presence_score;
+    float inter_presence_score;
+    // Per point filter state, sweep_length long
+    float *fast_real;
+    float *fast_imag;
+    float *slow_real;
+    float *slow_imag;
+    float *depthwise_intra_presence_scores;
+    float *depthwise_inter_presence_scores;
+};
+
+/** Smoothing factor of a first order low pass filter with the given time constant */
+static float time_const_alpha(float time_const, float frame_rate) {
+    if (time_const <= 0.0f || frame_rate <= 0.0f) {
+        return 0.0f;
+    }
+    return expf(-1.0f / (time_const * frame_rate));
+}
+
+/** Smoothing factor of a first order low pass filter with the given cutoff frequency */
+static float cutoff_alpha(float cutoff, float frame_rate) {
+    if (cutoff <= 0.0f || frame_rate <= 0.0f) {
+        return 1.0f;
+    }
+    // No filtering above the Nyquist limit
+    if (cutoff >= frame_rate / 2.0f) {
+        return 0.0f;
+    }
+    return expf(-2.0f * PI * cutoff / frame_rate);
+}
+
+static float low_pass(float previous, float value, float alpha) {
+    return alpha * previous + (1.0f - alpha) * value;
+}
+
+static int compare_floats(const void *a, const void *b) {
+    float left = *(const float *)a;
+    float right = *(const float *)b;
+    return (left > right) - (left < right);
+}
+
+/** Distance of a point of the sweep, walking the subsweeps it is made of */
+static float point_distance_m(const acc_stub_frame_layout_t *layout, uint16_t point) {
+    for (uint8_t i = 0; i < layout->num_subsweeps; i++) {
+        if (point < layout->num_points[i]) {
+            return acc_processing_points_to_meter(layout->start_point[i] + (int32_t)point * layout->step_length[i]);
+        }
+        point -= layout->num_points[i];
+    }
+    return 0.0f;
+}
+
+uint32_t model_acc_detector_presence_processing_get_buffer_size(const acc_config_t *sensor_config) {
+    if (sensor_config == NULL) {
+        return 0;
+    }
+
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(sensor_config, &layout);
+    return acc_stub_sweep_length(&layout) * BUFFER_BYTES_PER_POINT;
+}
+
+acc_detector_presence_processing_handle_t *model_acc_detector_presence_processing_create(
+    const acc_detector_presence_processing_config_t *processing_config, const acc_config_t *sensor_config) {
+    if (processing_config == NULL || sensor_config == NULL) {
+        return NULL;
+    }
+
+    acc_detector_presence_processing_handle_t *handle = acc_stub_alloc(sizeof(*handle));
+    if (handle == NULL) {
+        return NULL;
+    }
+
+    acc_stub_frame_layout_from_config(sensor_config, &handle->layout);
+    handle->sweep_length = acc_stub_sweep_length(&handle->layout);
+    handle->frame_rate = acc_detector_presence_processing_config_frame_rate_get(processing_config);
+    handle->intra_detection = acc_detector_presence_processing_config_intra_detection_get(processing_config);
+    handle->intra_detection_threshold =
+        acc_detector_presence_processing_config_intra_detection_threshold_get(processing_config);
+    handle->inter_detection = acc_detector_presence_processing_config_inter_detection_get(processing_config);
+    handle->inter_detection_threshold =
+        acc_detector_presence_processing_config_inter_detection_threshold_get(processing_config);
+    handle->inter_frame_deviation_time_const =
+        acc_detector_presence_processing_config_inter_frame_deviation_time_const_get(processing_config);
+    handle->inter_frame_fast_cutoff =
+        acc_detector_presence_processing_config_inter_frame_fast_cutoff_get(processing_config);
+    handle->inter_frame_slow_cutoff =
+        acc_detector_presence_processing_config_inter_frame_slow_cutoff_get(processing_config);
+    handle->intra_frame_time_const =
+        acc_detector_presence_processing_config_intra_frame_time_const_get(processing_config);
+    handle->intra_output_time_const =
+        acc_detector_presence_processing_config_intra_output_time_const_get(processing_config);
+    handle->inter_output_time_const =
+        acc_detector_presence_processing_config_inter_output_time_const_get(processing_config);
+
+    handle->fast_real = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    handle->fast_imag = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    handle->slow_real = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    handle->slow_imag = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    handle->depthwise_intra_presence_scores = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    handle->depthwise_inter_presence_scores = acc_stub_alloc(handle->sweep_length * sizeof(float));
+    if (handle->sweep_length == 0 || handle->fast_real == NULL || handle->fast_imag == NULL ||
+        handle->slow_real == NULL || handle->slow_imag == NULL || handle->depthwise_intra_presence_scores == NULL ||
+        handle->depthwise_inter_presence_scores == NULL) {
+        acc_detector_presence_processing_destroy(handle);
+        return NULL;
+    }
+
+    return handle;
+}
+
+void model_acc_detector_presence_processing_destroy(acc_detector_presence_processing_handle_t *processing_handle) {
+    if (processing_handle == NULL) {
+        return;
+    }
+
+    acc_stub_free(processing_handle->fast_real);
+    acc_stub_free(processing_handle->fast_imag);
+    acc_stub_free(processing_handle->slow_real);
+    acc_stub_free(processing_handle->slow_imag);
+    acc_stub_free(processing_handle->depthwise_intra_presence_scores);
+    acc_stub_free(processing_handle->depthwise_inter_presence_scores);
+    acc_stub_free(processing_handle);
+}
+
+bool model_acc_detector_presence_processing_reset(acc_detector_presence_processing_handle_t *processing_handle) {
+    if (processing_handle == NULL) {
+        return false;
+    }
+
+    processing_handle->filters_initialized = false;
+    processing_handle->intra_presence_score = 0.0f;
+    processing_handle->inter_presence_score = 0.0f;
+    memset(processing_handle->depthwise_intra_presence_scores, 0, processing_handle->sweep_length * sizeof(float));
+    memset(processing_handle->depthwise_inter_presence_scores, 0, processing_handle->sweep_length * sizeof(float));
+    return true;
+}
+
+bool model_acc_detector_presence_processing_process(acc_detector_presence_processing_handle_t *handle, void *buffer,
+                                                    const acc_int16_complex_t *frame,
+                                                    acc_detector_presence_processing_result_t *presence_result) {
+    if (handle == NULL || buffer == NULL || frame == NULL || presence_result == NULL) {
+        return false;
+    }
+
+    uint16_t sweeps = handle->layout.sweeps_per_frame > 0 ? handle->layout.sweeps_per_frame : 1;
+    float intra_frame_alpha = time_const_alpha(handle->intra_frame_time_const, handle->frame_rate);
+    float deviation_alpha = time_const_alpha(handle->inter_frame_deviation_time_const, handle->frame_rate);
+    float fast_alpha = cutoff_alpha(handle->inter_frame_fast_cutoff, handle->frame_rate);
+    float slow_alpha = cutoff_alpha(handle->inter_frame_slow_cutoff, handle->frame_rate);
+
+    // The mean sweep goes to the working buffer, followed by the spread of the sweeps around it
+    float *mean_real = buffer;
+    float *mean_imag = mean_real + handle->sweep_length;
+    float *spread = mean_imag + handle->sweep_length;
+    float *sorted_spread = spread + handle->sweep_length;
+
+    for (uint16_t point = 0; point < handle->sweep_length; point++) {
+        float real = 0.0f;
+        float imag = 0.0f;
+        for (uint16_t sweep = 0; sweep < sweeps; sweep++) {
+            const acc_int16_complex_t *sample = &frame[(uint32_t)sweep * handle->sweep_length + point];
+            real += sample->real;
+            imag += sample->imag;
+        }
+        mean_real[point] = real / sweeps;
+        mean_imag[point] = imag / sweeps;
+
+        float variance = 0.0f;
+        for (uint16_t sweep = 0; sweep < sweeps; sweep++) {
+            const acc_int16_complex_t *sample = &frame[(uint32_t)sweep * handle->sweep_length + point];
+            float delta_real = sample->real - mean_real[point];
+            float delta_imag = sample->imag - mean_imag[point];
+            variance += delta_real * delta_real + delta_imag * delta_imag;
+        }
+        spread[point] = sqrtf(variance / sweeps);
+    }
+
+    // Presence only covers part of the range, the median spread tells the noise level
+    memcpy(sorted_spread, spread, handle->sweep_length * sizeof(float));
+    qsort(sorted_spread, handle->sweep_length, sizeof(float), compare_floats);
+    float noise_level = sorted_spread[handle->sweep_length / 2];
+    if (noise_level < MIN_NOISE_LEVEL) {
+        noise_level = MIN_NOISE_LEVEL;
+    }
+
+    if (!handle->filters_initialized) {
+        memcpy(handle->fast_real, mean_real, handle->sweep_length * sizeof(float));
+        memcpy(handle->fast_imag, mean_imag, handle->sweep_length * sizeof(float));
+        memcpy(handle->slow_real, mean_real, handle->sweep_length * sizeof(float));
+        memcpy(handle->slow_imag, mean_imag, handle->sweep_length * sizeof(float));
+        handle->filters_initialized = true;
+    }
+
+    uint16_t intra_peak = 0;
+    uint16_t inter_peak = 0;
+    for (uint16_t point = 0; point < handle->sweep_length; point++) {
+        handle->fast_real[point] = low_pass(handle->fast_real[point], mean_real[point], fast_alpha);
+        handle->fast_imag[point] = low_pass(handle->fast_imag[point], mean_imag[point], fast_alpha);
+        handle->slow_real[point] = low_pass(handle->slow_real[point], mean_real[point], slow_alpha);
+        handle->slow_imag[point] = low_pass(handle->slow_imag[point], mean_imag[point], slow_alpha);
+
+        // Averaging the sweeps lowers the noise of the mean sweep
+        float deviation = hypotf(handle->fast_real[point] - handle->slow_real[point],
+                                 handle->fast_imag[point] - handle->slow_imag[point]) *
+                          sqrtf(sweeps) / noise_level;
+
+        // Both scores count noise levels above the noise itself
+
+        handle->depthwise_intra_presence_scores[point] =
+            low_pass(handle->depthwise_intra_presence_scores[point], spread[point] / noise_level - 1.0f,
+                     intra_frame_alpha);
+        handle->depthwise_inter_presence_scores[point] =
+            low_pass(handle->depthwise_inter_presence_scores[point], deviation - 1.0f, deviation_alpha);
+
+        if (handle->depthwise_intra_presence_scores[point] > handle->depthwise_intra_presence_scores[intra_peak]) {
+            intra_peak = point;
+        }
+        if (handle->depthwise_inter_presence_scores[point] > handle->depthwise_inter_presence_scores[inter_peak]) {
+            inter_peak = point;
+        }
+    }
+
+    handle->intra_presence_score =
+        low_pass(handle->intra_presence_score, handle->depthwise_intra_presence_scores[intra_peak],
+                 time_const_alpha(handle->intra_output_time_const, handle->frame_rate));
+    handle->inter_presence_score =
+        low_pass(handle->inter_presence_score, handle->depthwise_inter_presence_scores[inter_peak],
+                 time_const_alpha(handle->inter_output_time_const, handle->frame_rate));
+
+    bool intra_detected = handle->intra_detection && handle->intra_presence_score > handle->intra_detection_threshold;
+    bool inter_detected = handle->inter_detection && handle->inter_presence_score > handle->inter_detection_threshold;
+
+    memset(presence_result, 0, sizeof(*presence_result));
+    presence_result->presence_detected = intra_detected || inter_detected;
+    presence_result->intra_presence_score = handle->intra_presence_score;
+    presence_result->inter_presence_score = handle->inter_presence_score;
+    if (presence_result->presence_detected) {
+        // Fast motion is located more precisely by the intra-frame part
+        presence_result->presence_distance =
+            point_distance_m(&handle->layout, intra_detected ? intra_peak : inter_peak);
+    }
+    presence_result->depthwise_intra_presence_scores = handle->depthwise_intra_presence_scores;
+    presence_result->depthwise_inter_presence_scores = handle->depthwise_inter_presence_scores;
+    presence_result->depthwise_presence_scores_length = handle->sweep_length;
+    return true;
+}
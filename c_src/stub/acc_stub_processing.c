@@ -0,0 +1,277 @@
+#include <math.h>
+
+#include "acc_processing.h"
+#include "acc_rss_a121.h"
+#include "acc_stub.h"
+#include "acc_stub_internal.h"
+
+// Distance between two A121 points
+#define POINT_SPACING_M (0.0025f)
+// Wavelength of the 60.5 GHz carrier
+#define WAVELENGTH_M (0.004955f)
+// Rough per-point measurement time and per-subsweep overhead used for the max sweep rate
+#define SAMPLE_TIME_S (0.5e-6f)
+#define SUBSWEEP_OVERHEAD_S (20e-6f)
+#define PI (3.14159265f)
+
+typedef struct {
+    float distance_m;
+    float amplitude;
+    float radial_velocity_m_s;
+} reflector_t;
+
+struct acc_processing_handle {
+    acc_stub_frame_layout_t layout;
+};
+
+static reflector_t reflectors[ACC_STUB_SCENE_MAX_REFLECTORS];
+static uint32_t num_reflectors = 0;
+static float noise_amplitude = 0.0f;
+static uint32_t noise_state = 1;
+static int16_t temperature = ACC_STUB_DEFAULT_TEMPERATURE;
+
+void acc_stub_scene_clear(void) {
+    num_reflectors = 0;
+    noise_amplitude = 0.0f;
+    noise_state = 1;
+    temperature = ACC_STUB_DEFAULT_TEMPERATURE;
+}
+
+bool acc_stub_scene_add_reflector(float distance_m, float amplitude, float radial_velocity_m_s) {
+    if (num_reflectors >= ACC_STUB_SCENE_MAX_REFLECTORS) {
+        return false;
+    }
+
+    reflectors[num_reflectors].distance_m = distance_m;
+    reflectors[num_reflectors].amplitude = amplitude;
+    reflectors[num_reflectors].radial_velocity_m_s = radial_velocity_m_s;
+    num_reflectors++;
+    return true;
+}
+
+void acc_stub_scene_set_noise(float amplitude) {
+    noise_amplitude = amplitude;
+}
+
+void acc_stub_scene_set_temperature(int16_t new_temperature) {
+    temperature = new_temperature;
+}
+
+int16_t acc_stub_scene_temperature(void) {
+    return temperature;
+}
+
+void acc_stub_frame_layout_from_config(const acc_config_t *config, acc_stub_frame_layout_t *layout) {
+    layout->num_subsweeps = acc_config_num_subsweeps_get(config);
+    if (layout->num_subsweeps > ACC_MAX_NUM_SUBSWEEPS) {
+        layout->num_subsweeps = ACC_MAX_NUM_SUBSWEEPS;
+    }
+
+    for (uint8_t i = 0; i < layout->num_subsweeps; i++) {
+        layout->start_point[i] = acc_config_subsweep_start_point_get(config, i);
+        layout->num_points[i] = acc_config_subsweep_num_points_get(config, i);
+        layout->step_length[i] = acc_config_subsweep_step_length_get(config, i);
+        layout->hwaas[i] = acc_config_subsweep_hwaas_get(config, i);
+        layout->profile[i] = acc_config_subsweep_profile_get(config, i);
+    }
+
+    layout->sweeps_per_frame = acc_config_sweeps_per_frame_get(config);
+    layout->sweep_rate = acc_config_sweep_rate_get(config);
+    layout->frame_rate = acc_config_frame_rate_get(config);
+    layout->continuous_sweep_mode = acc_config_continuous_sweep_mode_get(config);
+    layout->inter_sweep_idle_state = acc_config_inter_sweep_idle_state_get(config);
+}
+
+uint16_t acc_stub_sweep_length(const acc_stub_frame_layout_t *layout) {
+    uint16_t length = 0;
+    for (uint8_t i = 0; i < layout->num_subsweeps; i++) {
+        length += layout->num_points[i];
+    }
+    return length;
+}
+
+uint32_t acc_stub_frame_length(const acc_stub_frame_layout_t *layout) {
+    return (uint32_t)acc_stub_sweep_length(layout) * layout->sweeps_per_frame;
+}
+
+bool acc_stub_high_speed_mode(const acc_stub_frame_layout_t *layout) {
+    return !layout->continuous_sweep_mode &&
+           layout->inter_sweep_idle_state == ACC_CONFIG_IDLE_STATE_READY &&
+           layout->num_subsweeps == 1 && layout->profile[0] >= ACC_CONFIG_PROFILE_3;
+}
+
+uint32_t acc_stub_buffer_size(const acc_stub_frame_layout_t *layout) {
+    return acc_stub_frame_length(layout) * sizeof(acc_int16_complex_t) + ACC_STUB_BUFFER_OVERHEAD;
+}
+
+float acc_stub_max_sweep_rate(const acc_stub_frame_layout_t *layout) {
+    float sweep_time = 0.0f;
+    for (uint8_t i = 0; i < layout->num_subsweeps; i++) {
+        sweep_time += SUBSWEEP_OVERHEAD_S + SAMPLE_TIME_S * layout->num_points[i] * layout->hwaas[i];
+    }
+    if (acc_stub_high_speed_mode(layout)) {
+        sweep_time *= 0.5f;
+    }
+    return sweep_time > 0.0f ? 1.0f / sweep_time : 0.0f;
+}
+
+static float profile_pulse_width_m(acc_config_profile_t profile) {
+    switch (profile) {
+    case ACC_CONFIG_PROFILE_1:
+        return 0.015f;
+    case ACC_CONFIG_PROFILE_2:
+        return 0.025f;
+    case ACC_CONFIG_PROFILE_3:
+        return 0.05f;
+    case ACC_CONFIG_PROFILE_4:
+        return 0.07f;
+    default:
+        return 0.12f;
+    }
+}
+
+static float next_noise(void) {
+    // xorshift32, mapped to [-1, 1]
+    noise_state ^= noise_state << 13;
+    noise_state ^= noise_state >> 17;
+    noise_state ^= noise_state << 5;
+    return (float)noise_state / 2147483648.0f - 1.0f;
+}
+
+static int16_t saturate(float value) {
+    if (value > INT16_MAX) {
+        return INT16_MAX;
+    }
+    if (value < INT16_MIN) {
+        return INT16_MIN;
+    }
+    return (int16_t)lroundf(value);
+}
+
+void acc_stub_scene_synthesize(const acc_stub_frame_layout_t *layout, uint32_t frame_index,
+                               acc_int16_complex_t *frame) {
+    uint16_t sweep_length = acc_stub_sweep_length(layout);
+    float sweep_rate = layout->sweep_rate > 0.0f ? layout->sweep_rate : acc_stub_max_sweep_rate(layout);
+    float sweep_period = sweep_rate > 0.0f ? 1.0f / sweep_rate : 0.0f;
+    float frame_period = layout->frame_rate > 0.0f ? 1.0f / layout->frame_rate
+                                                   : sweep_period * layout->sweeps_per_frame;
+
+    for (uint16_t sweep = 0; sweep < layout->sweeps_per_frame; sweep++) {
+        float time = frame_index * frame_period + sweep * sweep_period;
+        acc_int16_complex_t *sweep_data = &frame[(uint32_t)sweep * sweep_length];
+
+        for (uint8_t subsweep = 0; subsweep < layout->num_subsweeps; subsweep++) {
+            float width = profile_pulse_width_m(layout->profile[subsweep]);
+
+            for (uint16_t point = 0; point < layout->num_points[subsweep]; point++) {
+                float distance = (layout->start_point[subsweep] + (int32_t)point * layout->step_length[subsweep]) *
+                                 POINT_SPACING_M;
+                float real = 0.0f;
+                float imag = 0.0f;
+
+                for (uint32_t i = 0; i < num_reflectors; i++) {
+                    float range = reflectors[i].distance_m + reflectors[i].radial_velocity_m_s * time;
+                    float offset = (distance - range) / width;
+                    float envelope = reflectors[i].amplitude * expf(-0.5f * offset * offset);
+                    float phase = -4.0f * PI * range / WAVELENGTH_M;
+                    real += envelope * cosf(phase);
+                    imag += envelope * sinf(phase);
+                }
+
+                if (noise_amplitude > 0.0f) {
+                    real += noise_amplitude * next_noise();
+                    imag += noise_amplitude * next_noise();
+                }
+
+                sweep_data->real = saturate(real);
+                sweep_data->imag = saturate(imag);
+                sweep_data++;
+            }
+        }
+    }
+}
+
+acc_processing_t *model_acc_processing_create(const acc_config_t *config,
+                                              acc_processing_metadata_t *processing_metadata) {
+    if (config == NULL) {
+        return NULL;
+    }
+
+    acc_processing_t *handle = acc_stub_alloc(sizeof(*handle));
+    if (handle == NULL) {
+        return NULL;
+    }
+    acc_stub_frame_layout_from_config(config, &handle->layout);
+
+    if (processing_metadata != NULL) {
+        uint16_t offset = 0;
+        for (uint8_t i = 0; i < ACC_MAX_NUM_SUBSWEEPS; i++) {
+            uint16_t length = i < handle->layout.num_subsweeps ? handle->layout.num_points[i] : 0;
+            processing_metadata->subsweep_data_offset[i] = offset;
+            processing_metadata->subsweep_data_length[i] = length;
+            offset += length;
+        }
+        processing_metadata->sweep_data_length = acc_stub_sweep_length(&handle->layout);
+        processing_metadata->frame_data_length = (uint16_t)acc_stub_frame_length(&handle->layout);
+        processing_metadata->max_sweep_rate = acc_stub_max_sweep_rate(&handle->layout);
+        processing_metadata->high_speed_mode = acc_stub_high_speed_mode(&handle->layout);
+    }
+
+    return handle;
+}
+
+void model_acc_processing_execute(acc_processing_t *handle, void *buffer, acc_processing_result_t *result) {
+    if (handle == NULL || result == NULL) {
+        return;
+    }
+
+    // The stubbed sensor reads frames already in processed layout
+    uint32_t flags = acc_stub_frame_flags(acc_stub_sensor_last_frame_index());
+    result->data_saturated = (flags & ACC_STUB_FLAG_DATA_SATURATED) != 0;
+    result->frame_delayed = (flags & ACC_STUB_FLAG_FRAME_DELAYED) != 0;
+    result->calibration_needed = (flags & ACC_STUB_FLAG_CALIBRATION_NEEDED) != 0;
+    result->temperature = temperature;
+    result->frame = buffer;
+}
+
+void model_acc_processing_destroy(acc_processing_t *handle) {
+    acc_stub_free(handle);
+}
+
+float model_acc_processing_points_to_meter(int32_t points) {
+    return points * POINT_SPACING_M;
+}
+
+int32_t model_acc_processing_meter_to_points(float length) {
+    return (int32_t)lroundf(length / POINT_SPACING_M);
+}
+
+bool model_acc_rss_get_buffer_size(const acc_config_t *config, uint32_t *buffer_size) {
+    if (config == NULL || buffer_size == NULL) {
+        return false;
+    }
+
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(config, &layout);
+    *buffer_size = acc_stub_buffer_size(&layout);
+    return true;
+}
+
+void model_acc_config_log(const acc_config_t *config) {
+    if (config == NULL) {
+        return;
+    }
+
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(config, &layout);
+    acc_stub_log(ACC_LOG_LEVEL_INFO, "config", "sweeps_per_frame: %u, num_subsweeps: %u",
+                 (unsigned)layout.sweeps_per_frame, (unsigned)layout.num_subsweeps);
+    acc_stub_log(ACC_LOG_LEVEL_INFO, "config", "sweep_rate: %.3f Hz, frame_rate: %.3f Hz", layout.sweep_rate,
+                 layout.frame_rate);
+    for (uint8_t i = 0; i < layout.num_subsweeps; i++) {
+        acc_stub_log(ACC_LOG_LEVEL_INFO, "config",
+                     "subsweep %u: start_point %d, num_points %u, step_length %u, profile %u, hwaas %u", (unsigned)i,
+                     (int)layout.start_point[i], (unsigned)layout.num_points[i], (unsigned)layout.step_length[i],
+                     (unsigned)layout.profile[i], (unsigned)layout.hwaas[i]);
+    }
+}
@@ -0,0 +1,161 @@
+#include "acc_sensor.h"
+#include "acc_stub_internal.h"
+
+// Marks calibration results produced by the stub
+#define CAL_RESULT_MAGIC (0xACC0CA1BU)
+#define CAL_RESULT_WORDS (ACC_CAL_RESULT_DATA_SIZE / 4)
+
+// Registers of the emulated SPI traffic, not the real A121 register map
+#define REG_CHIP_ID (0x0000U)
+#define REG_COMMAND (0x0001U)
+#define REG_CONFIG (0x0100U)
+#define REG_CAL_RESULT (0x0400U)
+#define REG_FRAME (0x1000U)
+
+// Registers written by prepare
+#define CONFIG_WORDS_COMMON (16U)
+#define CONFIG_WORDS_PER_SUBSWEEP (8U)
+
+struct acc_sensor {
+    acc_sensor_id_t sensor_id;
+    uint32_t calibration_calls;
+    bool prepared;
+    bool measured;
+    acc_stub_frame_layout_t layout;
+    uint32_t frame_index;
+};
+
+static uint32_t last_frame_index = 0;
+
+uint32_t acc_stub_sensor_last_frame_index(void) {
+    return last_frame_index;
+}
+
+acc_sensor_t *model_acc_sensor_create(acc_sensor_id_t sensor_id) {
+    if (acc_stub_sensor_create_fails()) {
+        acc_stub_log(ACC_LOG_LEVEL_ERROR, "sensor", "Sensor %u could not be created", (unsigned)sensor_id);
+        return NULL;
+    }
+
+    acc_sensor_t *sensor = acc_stub_alloc(sizeof(*sensor));
+    if (sensor == NULL) {
+        return NULL;
+    }
+
+    sensor->sensor_id = sensor_id;
+    acc_stub_spi_burst(sensor_id, REG_CHIP_ID, false, 1, NULL, 0);
+    return sensor;
+}
+
+void model_acc_sensor_destroy(acc_sensor_t *sensor) {
+    acc_stub_free(sensor);
+}
+
+bool model_acc_sensor_calibrate(acc_sensor_t *sensor, bool *cal_complete, acc_cal_result_t *cal_result, void *buffer,
+                                uint32_t buffer_size) {
+    if (sensor == NULL || cal_complete == NULL || cal_result == NULL) {
+        return false;
+    }
+    if (acc_stub_calibration_fails()) {
+        acc_stub_log(ACC_LOG_LEVEL_ERROR, "sensor", "Calibration of sensor %u failed", (unsigned)sensor->sensor_id);
+        return false;
+    }
+
+    acc_stub_spi_burst(sensor->sensor_id, REG_COMMAND, true, 1, buffer, buffer_size);
+    sensor->calibration_calls++;
+    *cal_complete = sensor->calibration_calls >= acc_stub_calibration_calls();
+    if (!*cal_complete) {
+        return true;
+    }
+
+    sensor->calibration_calls = 0;
+    acc_stub_spi_burst(sensor->sensor_id, REG_CAL_RESULT, false, ACC_CAL_RESULT_DATA_SIZE / sizeof(uint16_t), buffer,
+                       buffer_size);
+    cal_result->data[0] = CAL_RESULT_MAGIC;
+    cal_result->data[1] = (uint16_t)acc_stub_scene_temperature();
+    for (uint32_t i = 2; i < CAL_RESULT_WORDS; i++) {
+        cal_result->data[i] = sensor->sensor_id * 0x9E3779B9U + i;
+    }
+    return true;
+}
+
+bool model_acc_sensor_get_cal_info(const acc_cal_result_t *cal_result, acc_cal_info_t *cal_info) {
+    if (cal_info == NULL || !acc_sensor_validate_calibration(cal_result)) {
+        return false;
+    }
+
+    cal_info->temperature = (int16_t)(uint16_t)cal_result->data[1];
+    return true;
+}
+
+bool model_acc_sensor_validate_calibration(const acc_cal_result_t *cal_result) {
+    return cal_result != NULL && cal_result->data[0] == CAL_RESULT_MAGIC;
+}
+
+bool model_acc_sensor_connected(acc_sensor_id_t sensor_id) {
+    return acc_stub_hal_registered() && !acc_stub_sensor_disconnected(sensor_id);
+}
+
+bool model_acc_sensor_prepare(acc_sensor_t *sensor, const acc_config_t *config, const acc_cal_result_t *cal_result,
+                              void *buffer, uint32_t buffer_size) {
+    if (sensor == NULL || config == NULL || cal_result == NULL || buffer == NULL) {
+        return false;
+    }
+
+    acc_stub_frame_layout_t layout;
+    acc_stub_frame_layout_from_config(config, &layout);
+    if (buffer_size < acc_stub_buffer_size(&layout)) {
+        acc_stub_log(ACC_LOG_LEVEL_ERROR, "sensor", "Buffer of %u bytes is too small, %u bytes needed",
+                     (unsigned)buffer_size, (unsigned)acc_stub_buffer_size(&layout));
+        return false;
+    }
+
+    acc_stub_spi_burst(sensor->sensor_id, REG_CONFIG, true,
+                       CONFIG_WORDS_COMMON + CONFIG_WORDS_PER_SUBSWEEP * layout.num_subsweeps, buffer, buffer_size);
+    sensor->layout = layout;
+    sensor->prepared = true;
+    sensor->measured = false;
+    sensor->frame_index = 0;
+    return true;
+}
+
+bool model_acc_sensor_measure(acc_sensor_t *sensor) {
+    if (sensor == NULL || !sensor->prepared) {
+        return false;
+    }
+
+    acc_stub_spi_burst(sensor->sensor_id, REG_COMMAND, true, 1, NULL, 0);
+    sensor->measured = true;
+    return true;
+}
+
+bool model_acc_sensor_read(const acc_sensor_t *sensor, void *buffer, uint32_t buffer_size) {
+    // The handle was allocated mutable, the SDK signature only promises not to reconfigure it
+    acc_sensor_t *state = (acc_sensor_t *)sensor;
+
+    if (state == NULL || buffer == NULL || !state->measured) {
+        return false;
+    }
+    if (buffer_size < acc_stub_frame_length(&state->layout) * sizeof(acc_int16_complex_t)) {
+        return false;
+    }
+
+    // Each IQ sample is two words
+    acc_stub_spi_burst(state->sensor_id, REG_FRAME, false, acc_stub_frame_length(&state->layout) * 2U, buffer,
+                       buffer_size);
+    acc_stub_scene_synthesize(&state->layout, state->frame_index, buffer);
+    last_frame_index = state->frame_index;
+    state->measured = false;
+    state->frame_index++;
+    return true;
+}
+
+void model_acc_sensor_status(const acc_sensor_t *sensor) {
+    if (sensor == NULL) {
+        return;
+    }
+
+    const char *state = sensor->measured ? "measured" : sensor->prepared ? "prepared" : "created";
+    acc_stub_log(ACC_LOG_LEVEL_INFO, "sensor", "sensor_id: %u, state: %s, frames read: %u",
+                 (unsigned)sensor->sensor_id, state, (unsigned)sensor->frame_index);
+}
@@ -0,0 +1,100 @@
+#include <stddef.h>
+#include <stdint.h>
+
+#include "acc_stub.h"
+#include "acc_stub_internal.h"
+
+#define INVALID_TRACE UINT32_MAX
+
+static bool trace_enabled = false;
+static acc_stub_trace_entry_t trace_entries[ACC_STUB_TRACE_CAPACITY];
+static uint32_t trace_length = 0;
+static uint32_t trace_dropped = 0;
+
+void acc_stub_trace_enable(bool enable) {
+    trace_enabled = enable;
+}
+
+void acc_stub_trace_clear(void) {
+    trace_length = 0;
+    trace_dropped = 0;
+}
+
+uint32_t acc_stub_trace_length(void) {
+    return trace_length;
+}
+
+const acc_stub_trace_entry_t *acc_stub_trace_get(uint32_t index) {
+    if (index >= trace_length) {
+        return NULL;
+    }
+
+    return &trace_entries[index];
+}
+
+uint32_t acc_stub_trace_dropped(void) {
+    return trace_dropped;
+}
+
+uint32_t acc_stub_trace_begin(const char *function) {
+    if (!trace_enabled) {
+        return INVALID_TRACE;
+    }
+
+    if (trace_length >= ACC_STUB_TRACE_CAPACITY) {
+        trace_dropped++;
+        return INVALID_TRACE;
+    }
+
+    acc_stub_trace_entry_t *entry = &trace_entries[trace_length];
+    entry->function = function;
+    entry->num_args = 0;
+    entry->result = (acc_stub_trace_value_t){.kind = ACC_STUB_TRACE_KIND_NONE};
+
+    return trace_length++;
+}
+
+static void add_arg(uint32_t trace, acc_stub_trace_value_t value) {
+    if (trace >= trace_length) {
+        return;
+    }
+
+    acc_stub_trace_entry_t *entry = &trace_entries[trace];
+    if (entry->num_args < ACC_STUB_TRACE_MAX_ARGS) {
+        entry->args[entry->num_args++] = value;
+    }
+}
+
+static void set_result(uint32_t trace, acc_stub_trace_value_t value) {
+    if (trace >= trace_length) {
+        return;
+    }
+
+    trace_entries[trace].result = value;
+}
+
+void acc_stub_trace_int(uint32_t trace, int64_t value) {
+    add_arg(trace, (acc_stub_trace_value_t){.kind = ACC_STUB_TRACE_KIND_INT, .integer = value});
+}
+
+void acc_stub_trace_float(uint32_t trace, double value) {
+    add_arg(trace, (acc_stub_trace_value_t){.kind = ACC_STUB_TRACE_KIND_FLOAT, .real = value});
+}
+
+void acc_stub_trace_pointer(uint32_t trace, const void *value) {
+    acc_stub_trace_value_t pointer = {.kind = ACC_STUB_TRACE_KIND_POINTER, .integer = (int64_t)(uintptr_t)value};
+    add_arg(trace, pointer);
+}
+
+void acc_stub_trace_result_int(uint32_t trace, int64_t value) {
+    set_result(trace, (acc_stub_trace_value_t){.kind = ACC_STUB_TRACE_KIND_INT, .integer = value});
+}
+
+void acc_stub_trace_result_float(uint32_t trace, double value) {
+    set_result(trace, (acc_stub_trace_value_t){.kind = ACC_STUB_TRACE_KIND_FLOAT, .real = value});
+}
+
+void acc_stub_trace_result_pointer(uint32_t trace, const void *value) {
+    acc_stub_trace_value_t pointer = {.kind = ACC_STUB_TRACE_KIND_POINTER, .integer = (int64_t)(uintptr_t)value};
+    set_result(trace, pointer);
+}
@@ -0,0 +1,460 @@
+//! Calibration results serialized for storage across reboots, e.g. in flash
+//!
+//! [`SensorCalibration`] and, with `distance`, [`DistanceCalibration`] turn calibration results
+//! into blobs, so a reboot does not need a new calibration. Loading checks the CRC, rejects blobs
+//! from another RSS version with [`CalibrationError::VersionMismatch`], checks sensor
+//! calibrations with `acc_sensor_validate_calibration` and against the stored temperature, and
+//! distance results against the size the detector needs. Recalibrate instead when the stored
+//! temperature is far from the current one.
+//!
+//! A blob holds a 16 byte header, the calibration result and a CRC-32 of everything before it,
+//! all little endian:
+//!
+//! | Offset | Size | Content                                               |
+//! |--------|------|-------------------------------------------------------|
+//! | 0      | 4    | `b"ACAL"`                                             |
+//! | 4      | 1    | Format version, 1                                     |
+//! | 5      | 1    | Kind, 0 for a sensor and 1 for a distance calibration |
+//! | 6      | 2    | Temperature from `acc_sensor_get_cal_info`            |
+//! | 8      | 4    | RSS version from `acc_version_get_hex`                |
+//! | 12     | 4    | Payload length `n`                                    |
+//! | 16     | n    | Payload                                               |
+//! | 16 + n | 4    | CRC-32 (IEEE) of bytes 0 to 16 + n                    |
+//!
+//! Loading rejects blobs written by another RSS version, as the SDK does not keep calibration
+//! results compatible between versions. Bytes after the CRC are ignored, so a blob can be read
+//! back from a larger flash page.
+
+use crate::*;
+use core::fmt;
+
+const MAGIC: [u8; 4] = *b"ACAL";
+const FORMAT_VERSION: u8 = 1;
+const KIND_SENSOR: u8 = 0;
+#[cfg(feature = "distance")]
+const KIND_DISTANCE: u8 = 1;
+const HEADER_SIZE: usize = 16;
+const CRC_SIZE: usize = 4;
+const CAL_RESULT_WORDS: usize = (ACC_CAL_RESULT_DATA_SIZE / 4) as usize;
+
+/// Why a calibration could not be saved or loaded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// The output buffer is shorter than the blob
+    BufferTooSmall {
+        needed: usize,
+    },
+    /// The payload is longer than the 32 bit length in the header can describe
+    PayloadTooLarge,
+    /// The bytes end before the length given in the header
+    Truncated,
+    /// The bytes do not start with a calibration blob, e.g. erased flash
+    InvalidMagic,
+    UnsupportedFormat(u8),
+    /// A sensor calibration was loaded as a distance calibration or the other way round
+    WrongKind,
+    CrcMismatch,
+    /// The blob was written by another RSS version, calibrate again
+    VersionMismatch {
+        stored: u32,
+        current: u32,
+    },
+    /// `acc_sensor_validate_calibration` or `acc_sensor_get_cal_info` rejected the result, the
+    /// stored temperature is not the one of the result, or the payload is not of the size of the
+    /// result
+    InvalidCalibration,
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::BufferTooSmall { needed } => {
+                write!(f, "Buffer too small, {} bytes needed", needed)
+            }
+            CalibrationError::PayloadTooLarge => write!(f, "Calibration payload too large"),
+            CalibrationError::Truncated => write!(f, "Calibration blob is truncated"),
+            CalibrationError::InvalidMagic => write!(f, "No calibration blob found"),
+            CalibrationError::UnsupportedFormat(format) => {
+                write!(f, "Unsupported calibration blob format {}", format)
+            }
+            CalibrationError::WrongKind => write!(f, "Calibration blob is of another kind"),
+            CalibrationError::CrcMismatch => write!(f, "Calibration blob CRC mismatch"),
+            CalibrationError::VersionMismatch { stored, current } => write!(
+                f,
+                "Calibration from RSS version {:#010x}, running {:#010x}",
+                stored, current
+            ),
+            CalibrationError::InvalidCalibration => write!(f, "Invalid calibration result"),
+        }
+    }
+}
+
+impl core::error::Error for CalibrationError {}
+
+/// CRC-32 as used by Ethernet and zlib, computed bitwise to keep the table out of flash
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn rss_version() -> u32 {
+    unsafe { acc_version_get_hex() }
+}
+
+/// Writes a blob of `kind` made with RSS `version` around `payload` into `out`, returning its
+/// length
+fn write_blob(
+    kind: u8,
+    version: u32,
+    temperature: i16,
+    payload: &[u8],
+    out: &mut [u8],
+) -> Result<usize, CalibrationError> {
+    let payload_len =
+        u32::try_from(payload.len()).map_err(|_| CalibrationError::PayloadTooLarge)?;
+    let size = payload
+        .len()
+        .checked_add(HEADER_SIZE + CRC_SIZE)
+        .ok_or(CalibrationError::PayloadTooLarge)?;
+    let Some(out) = out.get_mut(..size) else {
+        return Err(CalibrationError::BufferTooSmall { needed: size });
+    };
+
+    out[..4].copy_from_slice(&MAGIC);
+    out[4] = FORMAT_VERSION;
+    out[5] = kind;
+    out[6..8].copy_from_slice(&temperature.to_le_bytes());
+    out[8..12].copy_from_slice(&version.to_le_bytes());
+    out[12..16].copy_from_slice(&payload_len.to_le_bytes());
+    out[HEADER_SIZE..size - CRC_SIZE].copy_from_slice(payload);
+    let crc = crc32(&out[..size - CRC_SIZE]);
+    out[size - CRC_SIZE..].copy_from_slice(&crc.to_le_bytes());
+    Ok(size)
+}
+
+/// Checks a blob of `kind` made with RSS `version` and returns its temperature and payload
+fn read_blob(kind: u8, version: u32, bytes: &[u8]) -> Result<(i16, &[u8]), CalibrationError> {
+    let header = bytes
+        .get(..HEADER_SIZE)
+        .ok_or(CalibrationError::Truncated)?;
+    if header[..4] != MAGIC {
+        return Err(CalibrationError::InvalidMagic);
+    }
+    if header[4] != FORMAT_VERSION {
+        return Err(CalibrationError::UnsupportedFormat(header[4]));
+    }
+    let payload_len = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+    let size = usize::try_from(payload_len)
+        .ok()
+        .and_then(|len| len.checked_add(HEADER_SIZE + CRC_SIZE))
+        .ok_or(CalibrationError::Truncated)?;
+    let blob = bytes.get(..size).ok_or(CalibrationError::Truncated)?;
+    let (data, crc) = blob.split_at(size - CRC_SIZE);
+    if crc32(data) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+        return Err(CalibrationError::CrcMismatch);
+    }
+
+    if header[5] != kind {
+        return Err(CalibrationError::WrongKind);
+    }
+    let stored = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    if stored != version {
+        return Err(CalibrationError::VersionMismatch {
+            stored,
+            current: version,
+        });
+    }
+    let temperature = i16::from_le_bytes([header[6], header[7]]);
+    Ok((temperature, &data[HEADER_SIZE..]))
+}
+
+/// A sensor calibration result checked with `acc_sensor_validate_calibration`, with the
+/// temperature it was made at
+///
+/// ```no_run
+/// use a121_sys::{Sensor, SensorCalibration};
+///
+/// # fn wait_for_interrupt() -> bool { true }
+/// # fn flash_write(_: &[u8]) {}
+/// # fn flash_read() -> &'static [u8] { &[] }
+/// let mut buffer = [0u64; 2048];
+/// let sensor = Sensor::new(1)?;
+/// let sensor = match SensorCalibration::from_bytes(flash_read()) {
+///     Ok(calibration) => sensor.with_calibration(&calibration),
+///     Err(_) => {
+///         let sensor = sensor.calibrate(&mut buffer, wait_for_interrupt)?;
+///         let calibration = SensorCalibration::new(*sensor.cal_result()).unwrap();
+///         let mut blob = [0u8; SensorCalibration::BLOB_SIZE];
+///         calibration.to_bytes(&mut blob).unwrap();
+///         flash_write(&blob);
+///         sensor
+///     }
+/// };
+/// # Ok::<(), a121_sys::SensorError>(())
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SensorCalibration {
+    cal_result: acc_cal_result_t,
+    temperature: i16,
+}
+
+impl SensorCalibration {
+    /// Size of the blob written by [`to_bytes`](Self::to_bytes)
+    pub const BLOB_SIZE: usize = HEADER_SIZE + ACC_CAL_RESULT_DATA_SIZE as usize + CRC_SIZE;
+
+    /// Checks `cal_result` and reads its temperature
+    pub fn new(cal_result: acc_cal_result_t) -> Result<Self, CalibrationError> {
+        let mut cal_info = acc_cal_info_t { temperature: 0 };
+        let valid = unsafe {
+            acc_sensor_validate_calibration(&cal_result)
+                && acc_sensor_get_cal_info(&cal_result, &mut cal_info)
+        };
+        if !valid {
+            return Err(CalibrationError::InvalidCalibration);
+        }
+        Ok(Self {
+            cal_result,
+            temperature: cal_info.temperature,
+        })
+    }
+
+    pub fn cal_result(&self) -> &acc_cal_result_t {
+        &self.cal_result
+    }
+
+    /// Temperature at calibration, in °C. Calibrate again when the sensor temperature has moved
+    /// too far from it.
+    pub fn temperature(&self) -> i16 {
+        self.temperature
+    }
+
+    /// Writes the blob into `out`, returning its length, [`BLOB_SIZE`](Self::BLOB_SIZE)
+    pub fn to_bytes(&self, out: &mut [u8]) -> Result<usize, CalibrationError> {
+        let mut payload = [0u8; ACC_CAL_RESULT_DATA_SIZE as usize];
+        for (bytes, word) in payload.chunks_exact_mut(4).zip(self.cal_result.data) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        write_blob(KIND_SENSOR, rss_version(), self.temperature, &payload, out)
+    }
+
+    /// Reads a blob written by [`to_bytes`](Self::to_bytes) with the running RSS version
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CalibrationError> {
+        let (temperature, payload) = read_blob(KIND_SENSOR, rss_version(), bytes)?;
+        if payload.len() != ACC_CAL_RESULT_DATA_SIZE as usize {
+            return Err(CalibrationError::InvalidCalibration);
+        }
+        let mut cal_result = acc_cal_result_t {
+            data: [0; CAL_RESULT_WORDS],
+        };
+        for (word, bytes) in cal_result.data.iter_mut().zip(payload.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let calibration = Self::new(cal_result)?;
+        if calibration.temperature != temperature {
+            return Err(CalibrationError::InvalidCalibration);
+        }
+        Ok(calibration)
+    }
+}
+
+/// The static part of a distance detector calibration, from `acc_detector_distance_calibrate`,
+/// with the temperature of the sensor calibration it was made with
+///
+/// The dynamic part is cheap to redo with `acc_detector_distance_update_calibration` and not
+/// stored.
+#[cfg(feature = "distance")]
+#[derive(Clone, Copy, Debug)]
+pub struct DistanceCalibration<'a> {
+    static_result: &'a [u8],
+    temperature: i16,
+}
+
+#[cfg(feature = "distance")]
+impl<'a> DistanceCalibration<'a> {
+    pub fn new(static_result: &'a [u8], sensor_calibration: &SensorCalibration) -> Self {
+        Self {
+            static_result,
+            temperature: sensor_calibration.temperature,
+        }
+    }
+
+    /// The static result, to copy into the buffer passed to `acc_detector_distance_process`
+    pub fn static_result(&self) -> &'a [u8] {
+        self.static_result
+    }
+
+    pub fn temperature(&self) -> i16 {
+        self.temperature
+    }
+
+    /// Size of the blob written by [`to_bytes`](Self::to_bytes)
+    pub fn blob_size(&self) -> usize {
+        HEADER_SIZE + self.static_result.len() + CRC_SIZE
+    }
+
+    /// Writes the blob into `out`, returning its length
+    pub fn to_bytes(&self, out: &mut [u8]) -> Result<usize, CalibrationError> {
+        write_blob(
+            KIND_DISTANCE,
+            rss_version(),
+            self.temperature,
+            self.static_result,
+            out,
+        )
+    }
+
+    /// Reads a blob written by [`to_bytes`](Self::to_bytes) with the running RSS version, the
+    /// static result borrows from `bytes`
+    ///
+    /// `static_size` is the `detector_cal_result_static_size` from
+    /// `acc_detector_distance_get_sizes` for the detector the result is for. A stored result of
+    /// another size, e.g. made with another config, is rejected.
+    pub fn from_bytes(bytes: &'a [u8], static_size: u32) -> Result<Self, CalibrationError> {
+        let (temperature, static_result) = read_blob(KIND_DISTANCE, rss_version(), bytes)?;
+        if u32::try_from(static_result.len()) != Ok(static_size) {
+            return Err(CalibrationError::InvalidCalibration);
+        }
+        Ok(Self {
+            static_result,
+            temperature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Any RSS version, the format does not depend on the running one
+    const VERSION: u32 = 0x0102_0003;
+    const PAYLOAD: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    const BLOB_SIZE: usize = HEADER_SIZE + PAYLOAD.len() + CRC_SIZE;
+
+    fn blob() -> [u8; BLOB_SIZE] {
+        let mut blob = [0; BLOB_SIZE];
+        assert_eq!(
+            write_blob(KIND_SENSOR, VERSION, -5, &PAYLOAD, &mut blob),
+            Ok(BLOB_SIZE)
+        );
+        blob
+    }
+
+    /// Writes a new CRC after changing the header
+    fn update_crc(blob: &mut [u8]) {
+        let crc = crc32(&blob[..blob.len() - CRC_SIZE]);
+        let end = blob.len();
+        blob[end - CRC_SIZE..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn crc32_known_answer() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let blob = blob();
+        assert_eq!(blob[..4], MAGIC);
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob),
+            Ok((-5, &PAYLOAD[..]))
+        );
+
+        let mut larger = [0xFF; BLOB_SIZE + 16];
+        larger[..BLOB_SIZE].copy_from_slice(&blob);
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &larger),
+            Ok((-5, &PAYLOAD[..]))
+        );
+    }
+
+    #[test]
+    fn write_needs_room_for_the_blob() {
+        let mut out = [0; BLOB_SIZE - 1];
+        assert_eq!(
+            write_blob(KIND_SENSOR, VERSION, 0, &PAYLOAD, &mut out),
+            Err(CalibrationError::BufferTooSmall { needed: BLOB_SIZE })
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let blob = blob();
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob[..HEADER_SIZE - 1]),
+            Err(CalibrationError::Truncated)
+        );
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob[..BLOB_SIZE - 1]),
+            Err(CalibrationError::Truncated)
+        );
+
+        // A payload length past the end of the bytes
+        let mut blob = blob;
+        blob[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob),
+            Err(CalibrationError::Truncated)
+        );
+    }
+
+    #[test]
+    fn invalid_magic() {
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &[0xFF; BLOB_SIZE]),
+            Err(CalibrationError::InvalidMagic)
+        );
+    }
+
+    #[test]
+    fn unsupported_format() {
+        let mut blob = blob();
+        blob[4] = FORMAT_VERSION + 1;
+        update_crc(&mut blob);
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob),
+            Err(CalibrationError::UnsupportedFormat(FORMAT_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn crc_mismatch() {
+        let mut blob = blob();
+        blob[HEADER_SIZE + 2] ^= 0x01;
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob),
+            Err(CalibrationError::CrcMismatch)
+        );
+    }
+
+    #[test]
+    fn wrong_kind() {
+        let blob = blob();
+        assert_eq!(
+            read_blob(KIND_SENSOR + 1, VERSION, &blob),
+            Err(CalibrationError::WrongKind)
+        );
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let stored = VERSION + 1;
+        let mut blob = blob();
+        blob[8..12].copy_from_slice(&stored.to_le_bytes());
+        update_crc(&mut blob);
+        assert_eq!(
+            read_blob(KIND_SENSOR, VERSION, &blob),
+            Err(CalibrationError::VersionMismatch {
+                stored,
+                current: VERSION
+            })
+        );
+    }
+}
@@ -0,0 +1,913 @@
+//! Safe owner of an `acc_config_t`
+//!
+//! [`Config`] creates the config with `acc_config_create`, destroys it on drop and wraps the
+//! `acc_config_*_set`/`_get` functions. Setters of values the RSS constrains check them first and
+//! return a [`ConfigError`] instead of leaving the RSS to reject the config when the sensor is
+//! prepared. [`ConfigBuilder`] sets several values at once and also checks the constraints
+//! between them.
+//!
+//! The `acc_config_subsweep_*` functions are reached through [`Subsweep`] and [`SubsweepMut`],
+//! which only exist for indices below the number of subsweeps of the config, so a subsweep past
+//! the end of the config can not be touched:
+//!
+//! ```no_run
+//! use a121_sys::{Config, ConfigError};
+//!
+//! let mut config = Config::builder().num_subsweeps(2).build()?;
+//! if let Some(mut far) = config.subsweep_mut(1) {
+//!     far.set_start_point(400);
+//!     far.set_hwaas(32)?;
+//! }
+//! for subsweep in config.subsweeps() {
+//!     // subsweep.start_point(), subsweep.profile(), ...
+//! }
+//! assert!(config.subsweep(2).is_none());
+//! # Ok::<(), ConfigError>(())
+//! ```
+//!
+//! The config is created through the HAL, so `acc_rss_hal_register` has to be called first.
+
+use crate::*;
+use core::fmt;
+use core::ptr::NonNull;
+
+/// Why a [`Config`] could not be created or a value was rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigError {
+    /// `acc_config_create` returned null, the HAL is not registered or out of memory
+    CreateFailed,
+    /// Step lengths up to 24 have to divide 24, longer ones have to be a multiple of 24
+    InvalidStepLength(u16),
+    /// A frame needs at least one sweep
+    ZeroSweepsPerFrame,
+    /// HWAAS has to be between 1 and 511
+    InvalidHwaas(u16),
+    /// The receiver gain has to be between 0 and 23
+    InvalidReceiverGain(u8),
+    /// Not one of the `ACC_CONFIG_PROFILE_*` values
+    InvalidProfile(acc_config_profile_t),
+    /// Not one of the `ACC_CONFIG_PRF_*` values
+    InvalidPrf(acc_config_prf_t),
+    /// Not one of the `ACC_CONFIG_IDLE_STATE_*` values
+    InvalidIdleState(acc_config_idle_state_t),
+    /// Configs have 1 to `ACC_MAX_NUM_SUBSWEEPS` subsweeps
+    InvalidNumSubsweeps(u8),
+    /// Rates have to be finite and at least 0
+    InvalidSweepRate(f32),
+    /// Like the sweep rate, with 0 for an unlimited frame rate
+    InvalidFrameRate(f32),
+    /// The inter frame idle state has to be at least as deep as the inter sweep idle state
+    IdleStateOrder,
+    /// Continuous sweep mode needs an unlimited frame rate, a sweep rate and the same idle
+    /// state between frames and sweeps
+    ContinuousSweepMode,
+    /// Loopback can not be enabled with profile 2
+    LoopbackWithProfile2,
+    /// A PRF of 19.5 MHz is only available with profile 1
+    PrfNeedsProfile1,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::CreateFailed => write!(f, "Config could not be created"),
+            ConfigError::InvalidStepLength(step_length) => write!(
+                f,
+                "Invalid step length {}, must divide 24 or be a multiple of it",
+                step_length
+            ),
+            ConfigError::ZeroSweepsPerFrame => write!(f, "Sweeps per frame must not be 0"),
+            ConfigError::InvalidHwaas(hwaas) => {
+                write!(f, "Invalid HWAAS {}, must be between 1 and 511", hwaas)
+            }
+            ConfigError::InvalidReceiverGain(gain) => {
+                write!(
+                    f,
+                    "Invalid receiver gain {}, must be between 0 and 23",
+                    gain
+                )
+            }
+            ConfigError::InvalidProfile(profile) => write!(f, "Invalid profile {}", profile),
+            ConfigError::InvalidPrf(prf) => write!(f, "Invalid PRF {}", prf),
+            ConfigError::InvalidIdleState(state) => write!(f, "Invalid idle state {}", state),
+            ConfigError::InvalidNumSubsweeps(num_subsweeps) => write!(
+                f,
+                "Invalid number of subsweeps {}, must be between 1 and {}",
+                num_subsweeps, ACC_MAX_NUM_SUBSWEEPS
+            ),
+            ConfigError::InvalidSweepRate(rate) => write!(f, "Invalid sweep rate {} Hz", rate),
+            ConfigError::InvalidFrameRate(rate) => write!(f, "Invalid frame rate {} Hz", rate),
+            ConfigError::IdleStateOrder => write!(
+                f,
+                "Inter frame idle state must be at least as deep as the inter sweep idle state"
+            ),
+            ConfigError::ContinuousSweepMode => write!(
+                f,
+                "Continuous sweep mode needs frame rate 0, a sweep rate and equal idle states"
+            ),
+            ConfigError::LoopbackWithProfile2 => {
+                write!(f, "Loopback can not be enabled with profile 2")
+            }
+            ConfigError::PrfNeedsProfile1 => write!(f, "PRF 19.5 MHz needs profile 1"),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
+
+fn check_step_length(step_length: u16) -> Result<(), ConfigError> {
+    let valid = match step_length {
+        0 => false,
+        1..=24 => 24 % step_length == 0,
+        _ => step_length % 24 == 0,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidStepLength(step_length))
+    }
+}
+
+fn check_profile(profile: acc_config_profile_t) -> Result<(), ConfigError> {
+    if (acc_config_profile_t_ACC_CONFIG_PROFILE_1..=acc_config_profile_t_ACC_CONFIG_PROFILE_5)
+        .contains(&profile)
+    {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidProfile(profile))
+    }
+}
+
+fn check_hwaas(hwaas: u16) -> Result<(), ConfigError> {
+    if (1..=511).contains(&hwaas) {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidHwaas(hwaas))
+    }
+}
+
+fn check_receiver_gain(gain: u8) -> Result<(), ConfigError> {
+    if gain <= 23 {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidReceiverGain(gain))
+    }
+}
+
+fn check_prf(prf: acc_config_prf_t) -> Result<(), ConfigError> {
+    if prf <= acc_config_prf_t_ACC_CONFIG_PRF_5_2_MHZ {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidPrf(prf))
+    }
+}
+
+fn check_idle_state(state: acc_config_idle_state_t) -> Result<(), ConfigError> {
+    if state <= acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidIdleState(state))
+    }
+}
+
+fn rate_valid(rate: f32) -> bool {
+    rate.is_finite() && rate >= 0.0
+}
+
+/// Radar config, destroyed with `acc_config_destroy` when dropped
+///
+/// ```no_run
+/// use a121_sys::{Config, ConfigError};
+///
+/// let config = Config::builder()
+///     .start_point(100)
+///     .num_points(40)
+///     .step_length(4)
+///     .sweeps_per_frame(8)
+///     .build()?;
+/// // Pass config.as_ptr() to acc_sensor_prepare and the other raw functions
+/// # Ok::<(), ConfigError>(())
+/// ```
+pub struct Config {
+    inner: NonNull<acc_config_t>,
+}
+
+impl Config {
+    /// Creates a config with the RSS defaults, the HAL has to be registered first
+    pub fn new() -> Result<Self, ConfigError> {
+        let inner =
+            NonNull::new(unsafe { acc_config_create() }).ok_or(ConfigError::CreateFailed)?;
+        Ok(Self { inner })
+    }
+
+    /// Starts a [`ConfigBuilder`], its subsweep values like the start point apply to subsweep 0
+    ///
+    /// Nothing is checked until [`build`](ConfigBuilder::build), which creates the config and
+    /// returns the first rejected value or broken constraint.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Pointer for the raw `acc_*` functions, valid as long as the config
+    pub fn as_ptr(&self) -> *const acc_config_t {
+        self.inner.as_ptr()
+    }
+
+    /// Mutable pointer for the raw `acc_*` functions, valid as long as the config
+    pub fn as_mut_ptr(&mut self) -> *mut acc_config_t {
+        self.inner.as_ptr()
+    }
+
+    /// Logs the config through the HAL with `acc_config_log`
+    pub fn log(&self) {
+        unsafe { acc_config_log(self.as_ptr()) }
+    }
+
+    /// Bytes of the buffer `acc_sensor_calibrate`, `acc_sensor_prepare` and `acc_sensor_read`
+    /// need for this config, from `acc_rss_get_buffer_size`. None for a config the RSS rejects.
+    pub fn buffer_size(&self) -> Option<u32> {
+        let mut buffer_size = 0;
+        unsafe { acc_rss_get_buffer_size(self.as_ptr(), &mut buffer_size) }.then_some(buffer_size)
+    }
+
+    /// Start of the sweep in points of about 2.5 mm
+    pub fn start_point(&self) -> i32 {
+        unsafe { acc_config_start_point_get(self.as_ptr()) }
+    }
+
+    pub fn set_start_point(&mut self, start_point: i32) {
+        unsafe { acc_config_start_point_set(self.as_mut_ptr(), start_point) }
+    }
+
+    pub fn num_points(&self) -> u16 {
+        unsafe { acc_config_num_points_get(self.as_ptr()) }
+    }
+
+    pub fn set_num_points(&mut self, num_points: u16) {
+        unsafe { acc_config_num_points_set(self.as_mut_ptr(), num_points) }
+    }
+
+    pub fn step_length(&self) -> u16 {
+        unsafe { acc_config_step_length_get(self.as_ptr()) }
+    }
+
+    /// Valid step lengths are 1, 2, 3, 4, 6, 8, 12, 24 and multiples of 24
+    pub fn set_step_length(&mut self, step_length: u16) -> Result<(), ConfigError> {
+        check_step_length(step_length)?;
+        unsafe { acc_config_step_length_set(self.as_mut_ptr(), step_length) };
+        Ok(())
+    }
+
+    pub fn profile(&self) -> acc_config_profile_t {
+        unsafe { acc_config_profile_get(self.as_ptr()) }
+    }
+
+    pub fn set_profile(&mut self, profile: acc_config_profile_t) -> Result<(), ConfigError> {
+        check_profile(profile)?;
+        unsafe { acc_config_profile_set(self.as_mut_ptr(), profile) };
+        Ok(())
+    }
+
+    pub fn hwaas(&self) -> u16 {
+        unsafe { acc_config_hwaas_get(self.as_ptr()) }
+    }
+
+    pub fn set_hwaas(&mut self, hwaas: u16) -> Result<(), ConfigError> {
+        check_hwaas(hwaas)?;
+        unsafe { acc_config_hwaas_set(self.as_mut_ptr(), hwaas) };
+        Ok(())
+    }
+
+    pub fn receiver_gain(&self) -> u8 {
+        unsafe { acc_config_receiver_gain_get(self.as_ptr()) }
+    }
+
+    pub fn set_receiver_gain(&mut self, gain: u8) -> Result<(), ConfigError> {
+        check_receiver_gain(gain)?;
+        unsafe { acc_config_receiver_gain_set(self.as_mut_ptr(), gain) };
+        Ok(())
+    }
+
+    pub fn sweeps_per_frame(&self) -> u16 {
+        unsafe { acc_config_sweeps_per_frame_get(self.as_ptr()) }
+    }
+
+    pub fn set_sweeps_per_frame(&mut self, sweeps: u16) -> Result<(), ConfigError> {
+        if sweeps == 0 {
+            return Err(ConfigError::ZeroSweepsPerFrame);
+        }
+        unsafe { acc_config_sweeps_per_frame_set(self.as_mut_ptr(), sweeps) };
+        Ok(())
+    }
+
+    /// Sweep rate in Hz, 0 for the highest rate
+    pub fn sweep_rate(&self) -> f32 {
+        unsafe { acc_config_sweep_rate_get(self.as_ptr()) }
+    }
+
+    pub fn set_sweep_rate(&mut self, sweep_rate: f32) -> Result<(), ConfigError> {
+        if !rate_valid(sweep_rate) {
+            return Err(ConfigError::InvalidSweepRate(sweep_rate));
+        }
+        unsafe { acc_config_sweep_rate_set(self.as_mut_ptr(), sweep_rate) };
+        Ok(())
+    }
+
+    pub fn continuous_sweep_mode(&self) -> bool {
+        unsafe { acc_config_continuous_sweep_mode_get(self.as_ptr()) }
+    }
+
+    pub fn set_continuous_sweep_mode(&mut self, enabled: bool) {
+        unsafe { acc_config_continuous_sweep_mode_set(self.as_mut_ptr(), enabled) }
+    }
+
+    /// Frame rate in Hz, 0 for unlimited
+    pub fn frame_rate(&self) -> f32 {
+        unsafe { acc_config_frame_rate_get(self.as_ptr()) }
+    }
+
+    pub fn set_frame_rate(&mut self, frame_rate: f32) -> Result<(), ConfigError> {
+        if !rate_valid(frame_rate) {
+            return Err(ConfigError::InvalidFrameRate(frame_rate));
+        }
+        unsafe { acc_config_frame_rate_set(self.as_mut_ptr(), frame_rate) };
+        Ok(())
+    }
+
+    pub fn enable_tx(&self) -> bool {
+        unsafe { acc_config_enable_tx_get(self.as_ptr()) }
+    }
+
+    pub fn set_enable_tx(&mut self, enable: bool) {
+        unsafe { acc_config_enable_tx_set(self.as_mut_ptr(), enable) }
+    }
+
+    pub fn inter_frame_idle_state(&self) -> acc_config_idle_state_t {
+        unsafe { acc_config_inter_frame_idle_state_get(self.as_ptr()) }
+    }
+
+    pub fn set_inter_frame_idle_state(
+        &mut self,
+        state: acc_config_idle_state_t,
+    ) -> Result<(), ConfigError> {
+        check_idle_state(state)?;
+        unsafe { acc_config_inter_frame_idle_state_set(self.as_mut_ptr(), state) };
+        Ok(())
+    }
+
+    pub fn inter_sweep_idle_state(&self) -> acc_config_idle_state_t {
+        unsafe { acc_config_inter_sweep_idle_state_get(self.as_ptr()) }
+    }
+
+    pub fn set_inter_sweep_idle_state(
+        &mut self,
+        state: acc_config_idle_state_t,
+    ) -> Result<(), ConfigError> {
+        check_idle_state(state)?;
+        unsafe { acc_config_inter_sweep_idle_state_set(self.as_mut_ptr(), state) };
+        Ok(())
+    }
+
+    pub fn prf(&self) -> acc_config_prf_t {
+        unsafe { acc_config_prf_get(self.as_ptr()) }
+    }
+
+    pub fn set_prf(&mut self, prf: acc_config_prf_t) -> Result<(), ConfigError> {
+        check_prf(prf)?;
+        unsafe { acc_config_prf_set(self.as_mut_ptr(), prf) };
+        Ok(())
+    }
+
+    pub fn phase_enhancement(&self) -> bool {
+        unsafe { acc_config_phase_enhancement_get(self.as_ptr()) }
+    }
+
+    pub fn set_phase_enhancement(&mut self, enable: bool) {
+        unsafe { acc_config_phase_enhancement_set(self.as_mut_ptr(), enable) }
+    }
+
+    pub fn iq_imbalance_compensation(&self) -> bool {
+        unsafe { acc_config_iq_imbalance_compensation_get(self.as_ptr()) }
+    }
+
+    pub fn set_iq_imbalance_compensation(&mut self, enable: bool) {
+        unsafe { acc_config_iq_imbalance_compensation_set(self.as_mut_ptr(), enable) }
+    }
+
+    pub fn enable_loopback(&self) -> bool {
+        unsafe { acc_config_enable_loopback_get(self.as_ptr()) }
+    }
+
+    pub fn set_enable_loopback(&mut self, enable: bool) {
+        unsafe { acc_config_enable_loopback_set(self.as_mut_ptr(), enable) }
+    }
+
+    pub fn double_buffering(&self) -> bool {
+        unsafe { acc_config_double_buffering_get(self.as_ptr()) }
+    }
+
+    pub fn set_double_buffering(&mut self, enable: bool) {
+        unsafe { acc_config_double_buffering_set(self.as_mut_ptr(), enable) }
+    }
+
+    pub fn num_subsweeps(&self) -> u8 {
+        unsafe { acc_config_num_subsweeps_get(self.as_ptr()) }
+    }
+
+    /// Subsweeps added by raising the number start with the RSS defaults
+    pub fn set_num_subsweeps(&mut self, num_subsweeps: u8) -> Result<(), ConfigError> {
+        if num_subsweeps == 0 || u32::from(num_subsweeps) > ACC_MAX_NUM_SUBSWEEPS {
+            return Err(ConfigError::InvalidNumSubsweeps(num_subsweeps));
+        }
+        unsafe { acc_config_num_subsweeps_set(self.as_mut_ptr(), num_subsweeps) };
+        Ok(())
+    }
+
+    /// Number of subsweeps that can be accessed, never above `ACC_MAX_NUM_SUBSWEEPS`
+    fn subsweep_count(&self) -> u8 {
+        self.num_subsweeps().min(ACC_MAX_NUM_SUBSWEEPS as u8)
+    }
+
+    /// Subsweep `index`, None when the config has no such subsweep
+    ///
+    /// The plain getters and setters of the config act on subsweep 0.
+    pub fn subsweep(&self, index: u8) -> Option<Subsweep<'_>> {
+        (index < self.subsweep_count()).then_some(Subsweep {
+            config: self,
+            index,
+        })
+    }
+
+    /// Subsweep `index` for changing its values, None when the config has no such subsweep
+    pub fn subsweep_mut(&mut self, index: u8) -> Option<SubsweepMut<'_>> {
+        (index < self.subsweep_count()).then_some(SubsweepMut {
+            config: self,
+            index,
+        })
+    }
+
+    /// All subsweeps of the config, in order
+    pub fn subsweeps(&self) -> Subsweeps<'_> {
+        Subsweeps {
+            config: self,
+            next: 0,
+            end: self.subsweep_count(),
+        }
+    }
+
+    /// Checks the constraints between values, which single setters can not
+    pub fn check(&self) -> Result<(), ConfigError> {
+        // Deeper idle states have lower values
+        if self.inter_frame_idle_state() > self.inter_sweep_idle_state() {
+            return Err(ConfigError::IdleStateOrder);
+        }
+        if self.continuous_sweep_mode()
+            && (self.frame_rate() != 0.0
+                || self.sweep_rate() <= 0.0
+                || self.inter_frame_idle_state() != self.inter_sweep_idle_state())
+        {
+            return Err(ConfigError::ContinuousSweepMode);
+        }
+        for subsweep in self.subsweeps() {
+            let profile = subsweep.profile();
+            if subsweep.enable_loopback() && profile == acc_config_profile_t_ACC_CONFIG_PROFILE_2 {
+                return Err(ConfigError::LoopbackWithProfile2);
+            }
+            if subsweep.prf() == acc_config_prf_t_ACC_CONFIG_PRF_19_5_MHZ
+                && profile != acc_config_profile_t_ACC_CONFIG_PROFILE_1
+            {
+                return Err(ConfigError::PrfNeedsProfile1);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        unsafe { acc_config_destroy(self.as_mut_ptr()) }
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("start_point", &self.start_point())
+            .field("num_points", &self.num_points())
+            .field("step_length", &self.step_length())
+            .field("profile", &self.profile())
+            .field("hwaas", &self.hwaas())
+            .field("receiver_gain", &self.receiver_gain())
+            .field("sweeps_per_frame", &self.sweeps_per_frame())
+            .field("sweep_rate", &self.sweep_rate())
+            .field("continuous_sweep_mode", &self.continuous_sweep_mode())
+            .field("frame_rate", &self.frame_rate())
+            .field("enable_tx", &self.enable_tx())
+            .field("inter_frame_idle_state", &self.inter_frame_idle_state())
+            .field("inter_sweep_idle_state", &self.inter_sweep_idle_state())
+            .field("prf", &self.prf())
+            .field("phase_enhancement", &self.phase_enhancement())
+            .field(
+                "iq_imbalance_compensation",
+                &self.iq_imbalance_compensation(),
+            )
+            .field("enable_loopback", &self.enable_loopback())
+            .field("double_buffering", &self.double_buffering())
+            .field("num_subsweeps", &self.num_subsweeps())
+            .finish()
+    }
+}
+
+/// Collects config values and creates a [`Config`] with them, values not given keep the RSS
+/// defaults
+///
+/// [`build`](ConfigBuilder::build) returns the first value a setter of [`Config`] rejects, or
+/// a broken constraint between values found by [`Config::check`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConfigBuilder {
+    start_point: Option<i32>,
+    num_points: Option<u16>,
+    step_length: Option<u16>,
+    profile: Option<acc_config_profile_t>,
+    hwaas: Option<u16>,
+    receiver_gain: Option<u8>,
+    sweeps_per_frame: Option<u16>,
+    sweep_rate: Option<f32>,
+    continuous_sweep_mode: Option<bool>,
+    frame_rate: Option<f32>,
+    enable_tx: Option<bool>,
+    inter_frame_idle_state: Option<acc_config_idle_state_t>,
+    inter_sweep_idle_state: Option<acc_config_idle_state_t>,
+    prf: Option<acc_config_prf_t>,
+    phase_enhancement: Option<bool>,
+    iq_imbalance_compensation: Option<bool>,
+    enable_loopback: Option<bool>,
+    double_buffering: Option<bool>,
+    num_subsweeps: Option<u8>,
+}
+
+impl ConfigBuilder {
+    pub fn start_point(mut self, start_point: i32) -> Self {
+        self.start_point = Some(start_point);
+        self
+    }
+
+    pub fn num_points(mut self, num_points: u16) -> Self {
+        self.num_points = Some(num_points);
+        self
+    }
+
+    pub fn step_length(mut self, step_length: u16) -> Self {
+        self.step_length = Some(step_length);
+        self
+    }
+
+    pub fn profile(mut self, profile: acc_config_profile_t) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn hwaas(mut self, hwaas: u16) -> Self {
+        self.hwaas = Some(hwaas);
+        self
+    }
+
+    pub fn receiver_gain(mut self, gain: u8) -> Self {
+        self.receiver_gain = Some(gain);
+        self
+    }
+
+    pub fn sweeps_per_frame(mut self, sweeps: u16) -> Self {
+        self.sweeps_per_frame = Some(sweeps);
+        self
+    }
+
+    pub fn sweep_rate(mut self, sweep_rate: f32) -> Self {
+        self.sweep_rate = Some(sweep_rate);
+        self
+    }
+
+    pub fn continuous_sweep_mode(mut self, enabled: bool) -> Self {
+        self.continuous_sweep_mode = Some(enabled);
+        self
+    }
+
+    pub fn frame_rate(mut self, frame_rate: f32) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    pub fn enable_tx(mut self, enable: bool) -> Self {
+        self.enable_tx = Some(enable);
+        self
+    }
+
+    pub fn inter_frame_idle_state(mut self, state: acc_config_idle_state_t) -> Self {
+        self.inter_frame_idle_state = Some(state);
+        self
+    }
+
+    pub fn inter_sweep_idle_state(mut self, state: acc_config_idle_state_t) -> Self {
+        self.inter_sweep_idle_state = Some(state);
+        self
+    }
+
+    pub fn prf(mut self, prf: acc_config_prf_t) -> Self {
+        self.prf = Some(prf);
+        self
+    }
+
+    pub fn phase_enhancement(mut self, enable: bool) -> Self {
+        self.phase_enhancement = Some(enable);
+        self
+    }
+
+    pub fn iq_imbalance_compensation(mut self, enable: bool) -> Self {
+        self.iq_imbalance_compensation = Some(enable);
+        self
+    }
+
+    pub fn enable_loopback(mut self, enable: bool) -> Self {
+        self.enable_loopback = Some(enable);
+        self
+    }
+
+    pub fn double_buffering(mut self, enable: bool) -> Self {
+        self.double_buffering = Some(enable);
+        self
+    }
+
+    /// Further subsweeps are set up through [`Config::subsweep_mut`] after building
+    pub fn num_subsweeps(mut self, num_subsweeps: u8) -> Self {
+        self.num_subsweeps = Some(num_subsweeps);
+        self
+    }
+
+    /// Creates the config and applies the values, the HAL has to be registered first
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let mut config = Config::new()?;
+        if let Some(num_subsweeps) = self.num_subsweeps {
+            config.set_num_subsweeps(num_subsweeps)?;
+        }
+        if let Some(start_point) = self.start_point {
+            config.set_start_point(start_point);
+        }
+        if let Some(num_points) = self.num_points {
+            config.set_num_points(num_points);
+        }
+        if let Some(step_length) = self.step_length {
+            config.set_step_length(step_length)?;
+        }
+        if let Some(profile) = self.profile {
+            config.set_profile(profile)?;
+        }
+        if let Some(hwaas) = self.hwaas {
+            config.set_hwaas(hwaas)?;
+        }
+        if let Some(gain) = self.receiver_gain {
+            config.set_receiver_gain(gain)?;
+        }
+        if let Some(sweeps) = self.sweeps_per_frame {
+            config.set_sweeps_per_frame(sweeps)?;
+        }
+        if let Some(sweep_rate) = self.sweep_rate {
+            config.set_sweep_rate(sweep_rate)?;
+        }
+        if let Some(enabled) = self.continuous_sweep_mode {
+            config.set_continuous_sweep_mode(enabled);
+        }
+        if let Some(frame_rate) = self.frame_rate {
+            config.set_frame_rate(frame_rate)?;
+        }
+        if let Some(enable) = self.enable_tx {
+            config.set_enable_tx(enable);
+        }
+        if let Some(state) = self.inter_frame_idle_state {
+            config.set_inter_frame_idle_state(state)?;
+        }
+        if let Some(state) = self.inter_sweep_idle_state {
+            config.set_inter_sweep_idle_state(state)?;
+        }
+        if let Some(prf) = self.prf {
+            config.set_prf(prf)?;
+        }
+        if let Some(enable) = self.phase_enhancement {
+            config.set_phase_enhancement(enable);
+        }
+        if let Some(enable) = self.iq_imbalance_compensation {
+            config.set_iq_imbalance_compensation(enable);
+        }
+        if let Some(enable) = self.enable_loopback {
+            config.set_enable_loopback(enable);
+        }
+        if let Some(enable) = self.double_buffering {
+            config.set_double_buffering(enable);
+        }
+        config.check()?;
+        Ok(config)
+    }
+}
+
+/// Getters shared by [`Subsweep`] and [`SubsweepMut`]
+macro_rules! subsweep_getters {
+    () => {
+        /// Position of the subsweep in its config
+        pub fn index(&self) -> u8 {
+            self.index
+        }
+
+        pub fn start_point(&self) -> i32 {
+            unsafe { acc_config_subsweep_start_point_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn num_points(&self) -> u16 {
+            unsafe { acc_config_subsweep_num_points_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn step_length(&self) -> u16 {
+            unsafe { acc_config_subsweep_step_length_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn profile(&self) -> acc_config_profile_t {
+            unsafe { acc_config_subsweep_profile_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn hwaas(&self) -> u16 {
+            unsafe { acc_config_subsweep_hwaas_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn receiver_gain(&self) -> u8 {
+            unsafe { acc_config_subsweep_receiver_gain_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn enable_tx(&self) -> bool {
+            unsafe { acc_config_subsweep_enable_tx_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn prf(&self) -> acc_config_prf_t {
+            unsafe { acc_config_subsweep_prf_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn phase_enhancement(&self) -> bool {
+            unsafe { acc_config_subsweep_phase_enhancement_get(self.config.as_ptr(), self.index) }
+        }
+
+        pub fn iq_imbalance_compensation(&self) -> bool {
+            unsafe {
+                acc_config_subsweep_iq_imbalance_compensation_get(self.config.as_ptr(), self.index)
+            }
+        }
+
+        pub fn enable_loopback(&self) -> bool {
+            unsafe { acc_config_subsweep_enable_loopback_get(self.config.as_ptr(), self.index) }
+        }
+    };
+}
+
+/// One subsweep of a [`Config`], from [`Config::subsweep`] or [`Config::subsweeps`]
+#[derive(Clone, Copy)]
+pub struct Subsweep<'a> {
+    config: &'a Config,
+    index: u8,
+}
+
+impl Subsweep<'_> {
+    subsweep_getters!();
+}
+
+impl fmt::Debug for Subsweep<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subsweep")
+            .field("index", &self.index)
+            .field("start_point", &self.start_point())
+            .field("num_points", &self.num_points())
+            .field("step_length", &self.step_length())
+            .field("profile", &self.profile())
+            .field("hwaas", &self.hwaas())
+            .field("receiver_gain", &self.receiver_gain())
+            .field("enable_tx", &self.enable_tx())
+            .field("prf", &self.prf())
+            .field("phase_enhancement", &self.phase_enhancement())
+            .field(
+                "iq_imbalance_compensation",
+                &self.iq_imbalance_compensation(),
+            )
+            .field("enable_loopback", &self.enable_loopback())
+            .finish()
+    }
+}
+
+/// One subsweep of a [`Config`] for changing its values, from [`Config::subsweep_mut`]
+///
+/// Setters check values like those of [`Config`] do.
+pub struct SubsweepMut<'a> {
+    config: &'a mut Config,
+    index: u8,
+}
+
+impl SubsweepMut<'_> {
+    subsweep_getters!();
+
+    pub fn set_start_point(&mut self, start_point: i32) {
+        unsafe {
+            acc_config_subsweep_start_point_set(self.config.as_mut_ptr(), start_point, self.index)
+        }
+    }
+
+    pub fn set_num_points(&mut self, num_points: u16) {
+        unsafe {
+            acc_config_subsweep_num_points_set(self.config.as_mut_ptr(), num_points, self.index)
+        }
+    }
+
+    pub fn set_step_length(&mut self, step_length: u16) -> Result<(), ConfigError> {
+        check_step_length(step_length)?;
+        unsafe {
+            acc_config_subsweep_step_length_set(self.config.as_mut_ptr(), step_length, self.index)
+        };
+        Ok(())
+    }
+
+    pub fn set_profile(&mut self, profile: acc_config_profile_t) -> Result<(), ConfigError> {
+        check_profile(profile)?;
+        unsafe { acc_config_subsweep_profile_set(self.config.as_mut_ptr(), profile, self.index) };
+        Ok(())
+    }
+
+    pub fn set_hwaas(&mut self, hwaas: u16) -> Result<(), ConfigError> {
+        check_hwaas(hwaas)?;
+        unsafe { acc_config_subsweep_hwaas_set(self.config.as_mut_ptr(), hwaas, self.index) };
+        Ok(())
+    }
+
+    pub fn set_receiver_gain(&mut self, gain: u8) -> Result<(), ConfigError> {
+        check_receiver_gain(gain)?;
+        unsafe {
+            acc_config_subsweep_receiver_gain_set(self.config.as_mut_ptr(), gain, self.index)
+        };
+        Ok(())
+    }
+
+    pub fn set_enable_tx(&mut self, enable: bool) {
+        unsafe { acc_config_subsweep_enable_tx_set(self.config.as_mut_ptr(), enable, self.index) }
+    }
+
+    pub fn set_prf(&mut self, prf: acc_config_prf_t) -> Result<(), ConfigError> {
+        check_prf(prf)?;
+        unsafe { acc_config_subsweep_prf_set(self.config.as_mut_ptr(), prf, self.index) };
+        Ok(())
+    }
+
+    pub fn set_phase_enhancement(&mut self, enable: bool) {
+        unsafe {
+            acc_config_subsweep_phase_enhancement_set(self.config.as_mut_ptr(), enable, self.index)
+        }
+    }
+
+    pub fn set_iq_imbalance_compensation(&mut self, enable: bool) {
+        unsafe {
+            acc_config_subsweep_iq_imbalance_compensation_set(
+                self.config.as_mut_ptr(),
+                enable,
+                self.index,
+            )
+        }
+    }
+
+    pub fn set_enable_loopback(&mut self, enable: bool) {
+        unsafe {
+            acc_config_subsweep_enable_loopback_set(self.config.as_mut_ptr(), enable, self.index)
+        }
+    }
+}
+
+/// Iterator over the subsweeps of a [`Config`], from [`Config::subsweeps`]
+#[derive(Clone)]
+pub struct Subsweeps<'a> {
+    config: &'a Config,
+    next: u8,
+    end: u8,
+}
+
+impl<'a> Iterator for Subsweeps<'a> {
+    type Item = Subsweep<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+        Some(Subsweep {
+            config: self.config,
+            index,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::from(self.end - self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Subsweeps<'_> {}
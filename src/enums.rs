@@ -0,0 +1,360 @@
+//! Rust enums for the C enums of the SDK, which bindgen turns into integer constants
+//!
+//! Each enum converts from `u32` and `i32` with `TryFrom`, rejecting values the SDK does not
+//! define, and back to its raw type with `From`, so it can be passed to the raw functions with
+//! `.into()`. bindgen makes the C enums `u32` on most targets but `i32` on others, like MSVC, so
+//! both work whichever the raw type is. The raw constants stay available next to them.
+//!
+//! ```no_run
+//! use a121_sys::{Config, Prf, Profile};
+//!
+//! let mut config = Config::new().unwrap();
+//! config.set_profile(Profile::P1.into()).unwrap();
+//! config.set_prf(Prf::Mhz19_5.into()).unwrap();
+//! let profile = Profile::try_from(config.profile()).unwrap();
+//! assert_eq!(profile.to_string(), "profile 1");
+//! ```
+
+use crate::*;
+use core::fmt;
+
+/// A raw value that is not one of the variants of the enum it was converted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidEnumValue {
+    /// Name of the C enum, e.g. `"acc_config_profile_t"`
+    pub name: &'static str,
+    /// The raw value, widened as bindgen makes the C enums signed on some targets
+    pub value: i64,
+}
+
+impl fmt::Display for InvalidEnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid {} value {}", self.name, self.value)
+    }
+}
+
+impl core::error::Error for InvalidEnumValue {}
+
+/// Defines an enum with the values of the raw constants, and the conversions and `Display`
+macro_rules! sdk_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident: $raw:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $value:ident => $display:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )*
+        }
+
+        impl $name {
+            fn try_from_raw(value: i64) -> Result<Self, InvalidEnumValue> {
+                $(
+                    if value == i64::from($value) {
+                        return Ok(Self::$variant);
+                    }
+                )*
+                Err(InvalidEnumValue {
+                    name: stringify!($raw),
+                    value,
+                })
+            }
+        }
+
+        // The raw type is one of these, depending on the target
+        impl TryFrom<u32> for $name {
+            type Error = InvalidEnumValue;
+
+            fn try_from(value: u32) -> Result<Self, InvalidEnumValue> {
+                Self::try_from_raw(i64::from(value))
+            }
+        }
+
+        impl TryFrom<i32> for $name {
+            type Error = InvalidEnumValue;
+
+            fn try_from(value: i32) -> Result<Self, InvalidEnumValue> {
+                Self::try_from_raw(i64::from(value))
+            }
+        }
+
+        impl From<$name> for $raw {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => $display,)*
+                })
+            }
+        }
+    };
+}
+
+sdk_enum! {
+    /// `acc_config_profile_t`, lower profiles have a higher depth resolution and higher ones a
+    /// higher radar loop gain
+    Profile: acc_config_profile_t {
+        P1 = acc_config_profile_t_ACC_CONFIG_PROFILE_1 => "profile 1",
+        P2 = acc_config_profile_t_ACC_CONFIG_PROFILE_2 => "profile 2",
+        P3 = acc_config_profile_t_ACC_CONFIG_PROFILE_3 => "profile 3",
+        P4 = acc_config_profile_t_ACC_CONFIG_PROFILE_4 => "profile 4",
+        P5 = acc_config_profile_t_ACC_CONFIG_PROFILE_5 => "profile 5",
+    }
+}
+
+sdk_enum! {
+    /// `acc_config_prf_t`, the pulse repetition frequency
+    Prf: acc_config_prf_t {
+        /// Only available with profile 1
+        Mhz19_5 = acc_config_prf_t_ACC_CONFIG_PRF_19_5_MHZ => "19.5 MHz",
+        Mhz15_6 = acc_config_prf_t_ACC_CONFIG_PRF_15_6_MHZ => "15.6 MHz",
+        Mhz13_0 = acc_config_prf_t_ACC_CONFIG_PRF_13_0_MHZ => "13.0 MHz",
+        Mhz8_7 = acc_config_prf_t_ACC_CONFIG_PRF_8_7_MHZ => "8.7 MHz",
+        Mhz6_5 = acc_config_prf_t_ACC_CONFIG_PRF_6_5_MHZ => "6.5 MHz",
+        Mhz5_2 = acc_config_prf_t_ACC_CONFIG_PRF_5_2_MHZ => "5.2 MHz",
+    }
+}
+
+sdk_enum! {
+    /// `acc_config_idle_state_t`, from the deepest state to the quickest to leave
+    IdleState: acc_config_idle_state_t {
+        DeepSleep = acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_DEEP_SLEEP => "deep sleep",
+        Sleep = acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_SLEEP => "sleep",
+        Ready = acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY => "ready",
+    }
+}
+
+sdk_enum! {
+    /// `acc_log_level_t`, from the least to the most verbose
+    LogLevel: acc_log_level_t {
+        Error = acc_log_level_t_ACC_LOG_LEVEL_ERROR => "ERROR",
+        Warning = acc_log_level_t_ACC_LOG_LEVEL_WARNING => "WARNING",
+        Info = acc_log_level_t_ACC_LOG_LEVEL_INFO => "INFO",
+        Verbose = acc_log_level_t_ACC_LOG_LEVEL_VERBOSE => "VERBOSE",
+        Debug = acc_log_level_t_ACC_LOG_LEVEL_DEBUG => "DEBUG",
+    }
+}
+
+sdk_enum! {
+    /// `acc_rss_test_state_t`, what the application has to do before calling a test again
+    RssTestState: acc_rss_test_state_t {
+        Ongoing = acc_rss_test_state_t_ACC_RSS_TEST_STATE_ONGOING => "ongoing",
+        ToggleEnablePin =
+            acc_rss_test_state_t_ACC_RSS_TEST_STATE_TOGGLE_ENABLE_PIN => "toggle enable pin",
+        WaitForInterrupt =
+            acc_rss_test_state_t_ACC_RSS_TEST_STATE_WAIT_FOR_INTERRUPT => "wait for interrupt",
+        Complete = acc_rss_test_state_t_ACC_RSS_TEST_STATE_COMPLETE => "complete",
+    }
+}
+
+sdk_enum! {
+    /// `acc_rss_assembly_test_test_id_t`, one test of `acc_rss_assembly_test`
+    AssemblyTestId: acc_rss_assembly_test_test_id_t {
+        BasicRead =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_BASIC_READ => "basic read",
+        Communication =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_COMMUNICATION
+                => "communication",
+        EnablePin =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_ENABLE_PIN => "enable pin",
+        Interrupt =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT => "interrupt",
+        ClockAndSupply =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_CLOCK_AND_SUPPLY
+                => "clock and supply",
+        SensorCalibration =
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_SENSOR_CALIBRATION
+                => "sensor calibration",
+    }
+}
+
+#[cfg(feature = "distance")]
+sdk_enum! {
+    /// `acc_detector_distance_threshold_method_t`, what the distance detector compares the
+    /// processed data against
+    DistanceThresholdMethod: acc_detector_distance_threshold_method_t {
+        FixedAmplitude =
+            acc_detector_distance_threshold_method_t_ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_FIXED_AMPLITUDE
+                => "fixed amplitude",
+        FixedStrength =
+            acc_detector_distance_threshold_method_t_ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_FIXED_STRENGTH
+                => "fixed strength",
+        Recorded =
+            acc_detector_distance_threshold_method_t_ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_RECORDED
+                => "recorded",
+        Cfar =
+            acc_detector_distance_threshold_method_t_ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_CFAR
+                => "CFAR",
+    }
+}
+
+#[cfg(feature = "distance")]
+sdk_enum! {
+    /// `acc_detector_distance_peak_sorting_t`, the order of the distance detector peaks
+    DistancePeakSorting: acc_detector_distance_peak_sorting_t {
+        Closest =
+            acc_detector_distance_peak_sorting_t_ACC_DETECTOR_DISTANCE_PEAK_SORTING_CLOSEST
+                => "closest",
+        Strongest =
+            acc_detector_distance_peak_sorting_t_ACC_DETECTOR_DISTANCE_PEAK_SORTING_STRONGEST
+                => "strongest",
+    }
+}
+
+#[cfg(feature = "distance")]
+sdk_enum! {
+    /// `acc_detector_distance_reflector_shape_t`, the shape assumed for the RCS of a peak
+    DistanceReflectorShape: acc_detector_distance_reflector_shape_t {
+        Generic =
+            acc_detector_distance_reflector_shape_t_ACC_DETECTOR_DISTANCE_REFLECTOR_SHAPE_GENERIC
+                => "generic",
+        Planar =
+            acc_detector_distance_reflector_shape_t_ACC_DETECTOR_DISTANCE_REFLECTOR_SHAPE_PLANAR
+                => "planar",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ops::RangeInclusive;
+
+    /// Converts every raw value of `R` up to past the last variant, and 255. The values in `valid`
+    /// have to convert and back, displayed as `displays` in order, and the others have to fail.
+    fn check<R, E>(name: &str, valid: RangeInclusive<u8>, displays: &[&str])
+    where
+        R: From<u8> + Into<i64> + Copy + PartialEq + fmt::Debug,
+        E: TryFrom<R, Error = InvalidEnumValue> + Into<R> + Copy + fmt::Debug + fmt::Display,
+    {
+        assert_eq!(valid.clone().count(), displays.len());
+        for value in (0..=*valid.end() + 2).chain([u8::MAX]) {
+            match E::try_from(R::from(value)) {
+                Ok(variant) if valid.contains(&value) => {
+                    assert_eq!(variant.into(), R::from(value));
+                    let index = (value - *valid.start()) as usize;
+                    assert_eq!(variant.to_string(), displays[index]);
+                }
+                Err(error) if !valid.contains(&value) => {
+                    assert_eq!(error.name, name);
+                    assert_eq!(error.value, i64::from(value));
+                }
+                result => panic!("{} value {} converted to {:?}", name, value, result),
+            }
+        }
+    }
+
+    #[test]
+    fn profiles_start_at_one() {
+        check::<acc_config_profile_t, Profile>(
+            "acc_config_profile_t",
+            1..=5,
+            &[
+                "profile 1",
+                "profile 2",
+                "profile 3",
+                "profile 4",
+                "profile 5",
+            ],
+        );
+        assert_eq!(
+            Profile::try_from(acc_config_profile_t_ACC_CONFIG_PROFILE_3),
+            Ok(Profile::P3)
+        );
+        assert_eq!(
+            Profile::try_from(0).unwrap_err().to_string(),
+            "Invalid acc_config_profile_t value 0"
+        );
+    }
+
+    #[test]
+    fn converts_from_both_raw_types() {
+        assert_eq!(Profile::try_from(2u32), Ok(Profile::P2));
+        assert_eq!(Profile::try_from(2i32), Ok(Profile::P2));
+        assert_eq!(Profile::try_from(-1i32).unwrap_err().value, -1);
+        assert_eq!(
+            Profile::try_from(u32::MAX).unwrap_err().value,
+            i64::from(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn config_enums() {
+        check::<acc_config_prf_t, Prf>(
+            "acc_config_prf_t",
+            0..=5,
+            &[
+                "19.5 MHz", "15.6 MHz", "13.0 MHz", "8.7 MHz", "6.5 MHz", "5.2 MHz",
+            ],
+        );
+        check::<acc_config_idle_state_t, IdleState>(
+            "acc_config_idle_state_t",
+            0..=2,
+            &["deep sleep", "sleep", "ready"],
+        );
+    }
+
+    #[test]
+    fn rss_enums() {
+        check::<acc_log_level_t, LogLevel>(
+            "acc_log_level_t",
+            0..=4,
+            &["ERROR", "WARNING", "INFO", "VERBOSE", "DEBUG"],
+        );
+        check::<acc_rss_test_state_t, RssTestState>(
+            "acc_rss_test_state_t",
+            0..=3,
+            &[
+                "ongoing",
+                "toggle enable pin",
+                "wait for interrupt",
+                "complete",
+            ],
+        );
+        check::<acc_rss_assembly_test_test_id_t, AssemblyTestId>(
+            "acc_rss_assembly_test_test_id_t",
+            0..=5,
+            &[
+                "basic read",
+                "communication",
+                "enable pin",
+                "interrupt",
+                "clock and supply",
+                "sensor calibration",
+            ],
+        );
+    }
+
+    #[cfg(feature = "distance")]
+    #[test]
+    fn distance_enums() {
+        check::<acc_detector_distance_threshold_method_t, DistanceThresholdMethod>(
+            "acc_detector_distance_threshold_method_t",
+            0..=3,
+            &["fixed amplitude", "fixed strength", "recorded", "CFAR"],
+        );
+        check::<acc_detector_distance_peak_sorting_t, DistancePeakSorting>(
+            "acc_detector_distance_peak_sorting_t",
+            0..=1,
+            &["closest", "strongest"],
+        );
+        check::<acc_detector_distance_reflector_shape_t, DistanceReflectorShape>(
+            "acc_detector_distance_reflector_shape_t",
+            0..=1,
+            &["generic", "planar"],
+        );
+    }
+}
@@ -29,7 +29,16 @@
 //! - **distance**: Enable distance measurement functionality
 //! - **presence**: Enable presence detection functionality
 //! - **stub_library**: Use stub implementations for testing/development without hardware
+//! - **rust_stubs**: Implement the stubs in Rust instead of C, implies `stub_library` and `std`
 //! - **std**: Enable functionality requiring the standard library
+//! - **log**: Forward RSS log lines to the [`log`](https://docs.rs/log) facade by default
+//! - **defmt**: Write RSS log lines to [`defmt`](https://docs.rs/defmt), without a line buffer
+//! - **log_chunks**: Deliver log lines longer than the log buffer in chunks instead of cutting them
+//! - **bundled_printf**: Format log lines with a bundled printf instead of the toolchain's libc
+//! - **log_ring**: Keep the last RSS log records in memory, see `LogRing`
+//! - **log_capture**: Return what `acc_sensor_status` and the `*_config_log` functions log, see
+//!   `capture_log`
+//! - **enums**: Rust enums for the C enums of the SDK, next to the raw constants
 //!
 //! ## Library Requirements
 //!
@@ -45,15 +54,44 @@
 //! ## Logging Integration
 //!
 //! The crate provides a way to integrate with the sensor's native logging through a C log wrapper.
-//! Implement the `rust_log` function to capture log messages:
+//! Register `c_log_stub` as the HAL's `log` function and install a sink to receive the log lines.
+//! With `rust_stubs` no C is built and there is no `c_log_stub`, leave `log` as `None` then:
 //!
 //! ```no_run
-//! use core::ffi::c_char;
+//! use a121_sys::{acc_log_level_t, set_log_sink, LogPart};
+//! use core::ffi::CStr;
 //!
-//! #[no_mangle]
-//! pub extern "C" fn rust_log(level: u32, message: *const c_char) {
+//! fn sink(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
 //!     // Your logging implementation here
 //! }
+//!
+//! set_log_sink(sink);
+//! ```
+//!
+//! Until a sink is installed, log lines are dropped, or with the `log` feature forwarded to the
+//! `log` facade by `log_sink`. `LogFilter` sets levels per RSS module, and lines longer than the
+//! log buffer are cut or, with `log_chunks`, split as told by `LogPart`. The `defmt`, `log_ring`
+//! and `log_capture` features send the lines to defmt, a `LogRing` or `capture_log` instead.
+//!
+//! ## Safe Wrappers
+//!
+//! Next to the raw bindings, `Config` owns an `acc_config_t` and rejects values the RSS would,
+//! `Sensor` owns an `acc_sensor_t` and tracks its state in the type, and `SensorCalibration` and
+//! `DistanceCalibration` store calibration results across reboots. With the `enums` feature the
+//! common C enums also get Rust enums, like `Profile`. Everything is created through the HAL, so
+//! `acc_rss_hal_register` has to be called first:
+//!
+//! ```no_run
+//! use a121_sys::{Config, Sensor, SensorError};
+//!
+//! # fn wait_for_interrupt() -> bool { true }
+//! let config = Config::builder().sweeps_per_frame(4).build().unwrap();
+//! let mut buffer = [0u64; 2048];
+//!
+//! let sensor = Sensor::new(1)?.calibrate(&mut buffer, wait_for_interrupt)?;
+//! let sensor = sensor.prepare(&config, &mut buffer)?;
+//! let sensor = sensor.measure()?.read(&mut buffer, wait_for_interrupt)?;
+//! # Ok::<(), SensorError>(())
 //! ```
 //!
 //! ## Embedded Usage
@@ -83,7 +121,7 @@
 //!
 //! This crate provides raw FFI bindings which are inherently unsafe. Users should:
 //!
-//! - Take care when implementing the logging callback
+//! - Keep the log sink short, it runs inside the SDK call that logged
 //! - Ensure proper initialization of the sensor
 //! - Follow proper memory management practices
 //! - Consider using higher-level safe wrappers (like the `a121` crate)
@@ -97,6 +135,31 @@
 //!    a121-sys = { version = "0.5", features = ["stub_library"] }
 //!    ```
 //!
+//!    The stubs need a registered HAL like the real library, drive its SPI transfers with
+//!    emulated register traffic, synthesize IQ frames from a scene of point reflectors and can
+//!    inject faults and trace every call, see `c_src/stub/acc_stub.h`. With `rust_stubs` the
+//!    same stubs are compiled from Rust, so no C compiler is needed and they run under Miri.
+//!
+//!    ```no_run
+//!    # #[cfg(feature = "stub_library")]
+//!    # unsafe {
+//!    use a121_sys::*;
+//!    use core::ffi::CStr;
+//!
+//!    acc_stub_scene_clear();
+//!    // 1000 IQ units at 0.5 m, moving away at 0.2 m/s
+//!    acc_stub_scene_add_reflector(0.5, 1000.0, 0.2);
+//!
+//!    acc_stub_trace_clear();
+//!    acc_stub_trace_enable(true);
+//!    let config = acc_config_create();
+//!    acc_config_sweeps_per_frame_set(config, 8);
+//!    let entry = &*acc_stub_trace_get(1);
+//!    assert_eq!(CStr::from_ptr(entry.function), c"acc_config_sweeps_per_frame_set");
+//!    assert_eq!(entry.args[1].integer, 8);
+//!    # }
+//!    ```
+//!
 //! 2. Install required toolchain:
 //!    ```bash
 //!    rustup target add thumbv7em-none-eabihf
@@ -106,6 +169,7 @@
 //! ## Environment Variables
 //!
 //! - `ACC_RSS_LIBS`: Path to the Acconeer A121 libraries
+//! - `A121_SYS_LOG_BUFFER_SIZE`: Size of the buffer `c_log_stub` formats a line into, 256 by default
 //! - `CPATH`: Additional C header paths (usually set automatically)
 //!
 //! ## Version Compatibility
@@ -119,3 +183,46 @@ use core::env;
 use core::include;
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+mod calibration;
+mod config;
+#[cfg(feature = "enums")]
+mod enums;
+#[cfg(feature = "log_capture")]
+mod log_capture;
+#[cfg(feature = "log_ring")]
+mod log_ring;
+mod logging;
+mod sensor;
+#[cfg(feature = "rust_stubs")]
+mod stub;
+
+#[cfg(feature = "distance")]
+pub use calibration::DistanceCalibration;
+pub use calibration::{CalibrationError, SensorCalibration};
+pub use config::{Config, ConfigBuilder, ConfigError, Subsweep, SubsweepMut, Subsweeps};
+#[cfg(feature = "enums")]
+pub use enums::{
+    AssemblyTestId, IdleState, InvalidEnumValue, LogLevel, Prf, Profile, RssTestState,
+};
+#[cfg(all(feature = "enums", feature = "distance"))]
+pub use enums::{DistancePeakSorting, DistanceReflectorShape, DistanceThresholdMethod};
+#[cfg(feature = "log_capture")]
+pub use heapless;
+#[cfg(all(feature = "log_capture", feature = "distance"))]
+pub use log_capture::distance_config_log;
+#[cfg(all(feature = "log_capture", feature = "presence"))]
+pub use log_capture::presence_config_log;
+#[cfg(feature = "log_capture")]
+pub use log_capture::{capture_log, config_log, sensor_status, CaptureError, LogCapture};
+#[cfg(feature = "log_ring")]
+pub use log_ring::{
+    log_ring_sink, LogRecord, LogRing, LOG_RECORD_MESSAGE_LENGTH, LOG_RECORD_MODULE_LENGTH,
+    LOG_RING, LOG_RING_RECORDS,
+};
+#[cfg(feature = "log")]
+pub use logging::log_sink;
+pub use logging::{
+    clear_log_filter, clear_log_sink, set_log_filter, set_log_sink, LogFilter, LogPart, LogSink,
+};
+pub use sensor::{Calibrated, Created, Measuring, Prepared, Sensor, SensorError, SensorFailure};
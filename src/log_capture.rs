@@ -0,0 +1,252 @@
+//! Capture of the RSS log lines written during a call, for the SDK functions that only report
+//! through the log, like `acc_sensor_status` and `acc_config_log`
+//!
+//! While [`capture_log`] runs its closure, every line reaching the C log wrapper goes into the
+//! capture output instead of the installed [`LogSink`](crate::LogSink) or defmt, and the
+//! [`LogFilter`](crate::LogFilter) is ignored. Lines are only seen when the HAL passed to
+//! `acc_rss_hal_register` logs through `c_log_stub`, and when their level passes the global
+//! `acc_rss_set_log_level`.
+//!
+//! [`sensor_status`], [`config_log`], [`distance_config_log`] and [`presence_config_log`] call the
+//! SDK function and return the lines it logged, as a [`heapless::String`] with the lines joined by
+//! `'\n'` or, with `std`, as a `Vec<String>` of lines:
+//!
+//! ```no_run
+//! use a121_sys::{acc_config_create, config_log, heapless};
+//!
+//! let config = unsafe { acc_config_create() };
+//! let summary: heapless::String<512> = unsafe { config_log(config) }.unwrap();
+//! ```
+//!
+//! The running capture is guarded by a critical section, and only one capture runs at a time,
+//! others fail with [`CaptureError::Busy`]. Like [`LogRing`](crate::LogRing), the capture needs a
+//! critical section implementation.
+
+use crate::*;
+use core::cell::RefCell;
+use core::fmt;
+use core::ptr;
+use critical_section::Mutex;
+
+/// Why [`capture_log`] did not run its closure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureError {
+    /// Another capture is running, around the caller or on another thread
+    Busy,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Busy => write!(f, "Another log capture is running"),
+        }
+    }
+}
+
+impl core::error::Error for CaptureError {}
+
+/// Output of [`capture_log`], built up from the text of the captured lines
+///
+/// `Send`, as lines are appended from whichever thread or interrupt logs them.
+pub trait LogCapture: Default + Send {
+    /// Appends `text` to the output, starting a new line first when `new_line` is set. A line
+    /// may arrive in several pieces.
+    fn append(&mut self, text: &str, new_line: bool);
+}
+
+/// Lines joined with `'\n'`, output that does not fit is cut at a character boundary
+impl<const N: usize> LogCapture for heapless::String<N> {
+    fn append(&mut self, text: &str, new_line: bool) {
+        if new_line && !self.is_empty() && self.push('\n').is_err() {
+            return;
+        }
+        for c in text.chars() {
+            if self.push(c).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// One string per line
+#[cfg(feature = "std")]
+impl LogCapture for Vec<String> {
+    fn append(&mut self, text: &str, new_line: bool) {
+        match self.last_mut() {
+            Some(line) if !new_line => line.push_str(text),
+            _ => self.push(text.to_owned()),
+        }
+    }
+}
+
+struct Capture {
+    output: *mut (),
+    append: unsafe fn(output: *mut (), text: &str, new_line: bool),
+    new_line: bool,
+}
+
+// The output is a `LogCapture`, so `Send`, and `capture_log` leaves it alone while it is
+// registered
+unsafe impl Send for Capture {}
+
+impl Capture {
+    fn write(&mut self, text: &str) {
+        unsafe { (self.append)(self.output, text, self.new_line) };
+        self.new_line = false;
+    }
+}
+
+unsafe fn append<O: LogCapture>(output: *mut (), text: &str, new_line: bool) {
+    (*output.cast::<O>()).append(text, new_line);
+}
+
+// The running capture, pointing at the output on the stack of `capture_log`. The output is only
+// written in the critical section.
+static CAPTURE: Mutex<RefCell<Option<Capture>>> = Mutex::new(RefCell::new(None));
+
+/// Unregisters the running capture, also when the closure panics
+struct Unregister;
+
+impl Drop for Unregister {
+    fn drop(&mut self) {
+        critical_section::with(|cs| CAPTURE.borrow_ref_mut(cs).take());
+    }
+}
+
+/// Runs `f` and returns the log lines written meanwhile
+///
+/// Fails without running `f` while another capture runs, whether around the caller or on
+/// another thread. Lines logged by other threads or interrupts while `f` runs are captured too.
+///
+/// ```no_run
+/// use a121_sys::*;
+///
+/// let sensor = unsafe { acc_sensor_create(1) };
+/// let lines: heapless::String<256> = capture_log(|| unsafe { acc_sensor_status(sensor) })?;
+/// # Ok::<(), CaptureError>(())
+/// ```
+pub fn capture_log<O: LogCapture>(f: impl FnOnce()) -> Result<O, CaptureError> {
+    let mut output = O::default();
+    let capture = Capture {
+        output: ptr::from_mut(&mut output).cast(),
+        append: append::<O>,
+        new_line: true,
+    };
+    critical_section::with(|cs| {
+        let mut running = CAPTURE.borrow_ref_mut(cs);
+        if running.is_some() {
+            return Err(CaptureError::Busy);
+        }
+        *running = Some(capture);
+        Ok(())
+    })?;
+    {
+        let _unregister = Unregister;
+        f();
+    }
+    Ok(output)
+}
+
+/// Whether a [`capture_log`] is running, its lines bypass the log filter
+pub(crate) fn capturing() -> bool {
+    critical_section::with(|cs| CAPTURE.borrow_ref(cs).is_some())
+}
+
+/// Appends a piece of a log line to the running capture, ending the line when `end_line` is set.
+/// Returns false when no capture is running. Invalid UTF-8 is replaced with U+FFFD.
+pub(crate) fn capture_text(text: &[u8], end_line: bool) -> bool {
+    critical_section::with(|cs| {
+        // Already borrowed when a `LogCapture` logs from `append`, that line goes to the sink
+        let Ok(mut running) = CAPTURE.borrow(cs).try_borrow_mut() else {
+            return false;
+        };
+        let Some(capture) = running.as_mut() else {
+            return false;
+        };
+        if text.is_empty() {
+            capture.write("");
+        }
+        for chunk in text.utf8_chunks() {
+            capture.write(chunk.valid());
+            if !chunk.invalid().is_empty() {
+                capture.write("\u{FFFD}");
+            }
+        }
+        capture.new_line = end_line;
+        true
+    })
+}
+
+/// Returns what `acc_sensor_status` logs about `sensor`
+///
+/// # Safety
+///
+/// `sensor` must be valid for `acc_sensor_status`.
+pub unsafe fn sensor_status<O: LogCapture>(sensor: *const acc_sensor_t) -> Result<O, CaptureError> {
+    capture_log(|| acc_sensor_status(sensor))
+}
+
+/// Returns what `acc_config_log` logs about `config`
+///
+/// # Safety
+///
+/// `config` must be valid for `acc_config_log`.
+pub unsafe fn config_log<O: LogCapture>(config: *const acc_config_t) -> Result<O, CaptureError> {
+    capture_log(|| acc_config_log(config))
+}
+
+/// Returns what `acc_detector_distance_config_log` logs about `handle` and `config`
+///
+/// # Safety
+///
+/// `handle` and `config` must be valid for `acc_detector_distance_config_log`.
+#[cfg(feature = "distance")]
+pub unsafe fn distance_config_log<O: LogCapture>(
+    handle: *const acc_detector_distance_handle_t,
+    config: *const acc_detector_distance_config_t,
+) -> Result<O, CaptureError> {
+    capture_log(|| acc_detector_distance_config_log(handle, config))
+}
+
+/// Returns what `acc_detector_presence_config_log` logs about `config`
+///
+/// # Safety
+///
+/// `config` must be valid for `acc_detector_presence_config_log`.
+#[cfg(feature = "presence")]
+pub unsafe fn presence_config_log<O: LogCapture>(
+    config: *mut acc_detector_presence_config_t,
+) -> Result<O, CaptureError> {
+    capture_log(|| acc_detector_presence_config_log(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One test, as the capture is global
+    #[test]
+    fn captures_one_at_a_time() {
+        let _globals = crate::logging::tests::lock_globals();
+        let lines: Result<heapless::String<64>, _> = capture_log(|| {
+            assert!(capturing());
+            capture_text(b"first ", false);
+            capture_text(b"line", true);
+            capture_text(b"bad \xff", true);
+            let nested: Result<heapless::String<8>, _> = capture_log(|| unreachable!());
+            assert_eq!(nested, Err(CaptureError::Busy));
+        });
+        assert_eq!(lines.unwrap(), "first line\nbad \u{FFFD}");
+        assert!(!capturing());
+        assert!(!capture_text(b"dropped", true));
+
+        // Unregistered after a panic
+        let panicked = std::panic::catch_unwind(|| {
+            let _: Result<heapless::String<8>, _> = capture_log(|| panic!());
+        });
+        assert!(panicked.is_err());
+        assert!(!capturing());
+        let empty: heapless::String<8> = capture_log(|| {}).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,357 @@
+//! In-memory ring of the last RSS log records, for attaching to crash reports
+//!
+//! Records are pushed by a [`LogSink`](crate::LogSink) and read back with [`LogRing::pop`],
+//! [`LogRing::drain`] or [`LogRing::snapshot`]. Access is guarded by a critical section, so the
+//! ring can be filled from SDK calls in interrupt context and read from the main loop. The
+//! application has to provide a [`critical-section`](https://docs.rs/critical-section)
+//! implementation, e.g. through `cortex-m`, or on a host the `std` feature of `critical-section`.
+//!
+//! [`log_ring_sink`] keeps the last [`LOG_RING_RECORDS`] records in the static [`LOG_RING`], each
+//! with its level, module, message and a sequence number. After a failing SDK call the preceding
+//! messages can be read back without a UART attached:
+//!
+//! ```no_run
+//! use a121_sys::{log_ring_sink, set_log_sink, LOG_RING};
+//!
+//! set_log_sink(log_ring_sink);
+//! // ... acc_sensor_calibrate returned false
+//! LOG_RING.drain(|record| {
+//!     // Attach record.sequence(), record.module() and record.message() to the crash report
+//! });
+//! ```
+
+use crate::{acc_log_level_t, LogPart};
+use core::cell::RefCell;
+use core::ffi::CStr;
+use critical_section::Mutex;
+
+/// Longest module name kept in a record, longer names are cut
+pub const LOG_RECORD_MODULE_LENGTH: usize = 24;
+/// Longest message kept in a record, longer messages are cut and marked truncated
+pub const LOG_RECORD_MESSAGE_LENGTH: usize = 128;
+
+/// A log line as stored in a [`LogRing`]
+#[derive(Clone, Copy)]
+pub struct LogRecord {
+    sequence: u32,
+    level: acc_log_level_t,
+    part: LogPart,
+    module_length: u8,
+    message_length: u8,
+    module: [u8; LOG_RECORD_MODULE_LENGTH],
+    message: [u8; LOG_RECORD_MESSAGE_LENGTH],
+}
+
+impl LogRecord {
+    const EMPTY: Self = Self {
+        sequence: 0,
+        level: 0,
+        part: LogPart::Complete,
+        module_length: 0,
+        message_length: 0,
+        module: [0; LOG_RECORD_MODULE_LENGTH],
+        message: [0; LOG_RECORD_MESSAGE_LENGTH],
+    };
+
+    /// Number of the record in its ring, counting every record pushed. Gaps show overwritten
+    /// records.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn level(&self) -> acc_log_level_t {
+        self.level
+    }
+
+    /// Whether the message is whole, also [`LogPart::Truncated`] when the record cut it
+    pub fn part(&self) -> LogPart {
+        self.part
+    }
+
+    pub fn module(&self) -> &str {
+        stored_str(&self.module[..usize::from(self.module_length)])
+    }
+
+    pub fn message(&self) -> &str {
+        stored_str(&self.message[..usize::from(self.message_length)])
+    }
+}
+
+impl core::fmt::Debug for LogRecord {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LogRecord")
+            .field("sequence", &self.sequence)
+            .field("level", &self.level)
+            .field("part", &self.part)
+            .field("module", &self.module())
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+/// Only ever filled by `copy_str`, which keeps whole characters
+fn stored_str(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or_default()
+}
+
+/// Copies as much of `source` as fits into `target`, replacing invalid UTF-8 with `?` and never
+/// splitting a character. Returns the copied length and whether all of `source` fit.
+fn copy_str(target: &mut [u8], source: &[u8]) -> (usize, bool) {
+    let mut length = 0;
+    for chunk in source.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if length + c.len_utf8() > target.len() {
+                return (length, false);
+            }
+            length += c.encode_utf8(&mut target[length..]).len();
+        }
+        if !chunk.invalid().is_empty() {
+            if length == target.len() {
+                return (length, false);
+            }
+            target[length] = b'?';
+            length += 1;
+        }
+    }
+    (length, true)
+}
+
+struct Ring<const N: usize> {
+    records: [LogRecord; N],
+    // Index of the oldest record
+    head: usize,
+    length: usize,
+    next_sequence: u32,
+}
+
+/// Ring of the last `N` log records, overwriting the oldest when full
+///
+/// Put it in a `static` and push to it from a log sink:
+///
+/// ```no_run
+/// use a121_sys::{acc_log_level_t, set_log_sink, LogPart, LogRing};
+/// use core::ffi::CStr;
+///
+/// static RING: LogRing<64> = LogRing::new();
+///
+/// fn sink(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+///     RING.push(level, module, message, part);
+/// }
+///
+/// set_log_sink(sink);
+/// ```
+pub struct LogRing<const N: usize> {
+    ring: Mutex<RefCell<Ring<N>>>,
+}
+
+impl<const N: usize> LogRing<N> {
+    pub const fn new() -> Self {
+        Self {
+            ring: Mutex::new(RefCell::new(Ring {
+                records: [LogRecord::EMPTY; N],
+                head: 0,
+                length: 0,
+                next_sequence: 0,
+            })),
+        }
+    }
+
+    /// Stores a log line, dropping the oldest record when the ring is full
+    pub fn push(&self, level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+        if N == 0 {
+            return;
+        }
+
+        critical_section::with(|cs| {
+            let mut ring = self.ring.borrow_ref_mut(cs);
+            let index = if ring.length == N {
+                let index = ring.head;
+                ring.head = (ring.head + 1) % N;
+                index
+            } else {
+                ring.length += 1;
+                (ring.head + ring.length - 1) % N
+            };
+            let sequence = ring.next_sequence;
+            ring.next_sequence = sequence.wrapping_add(1);
+
+            // Filled in place, a record is too large to build on the stack of every SDK call
+            let record = &mut ring.records[index];
+            let (module_length, _) = copy_str(&mut record.module, module.to_bytes());
+            let (message_length, complete) = copy_str(&mut record.message, message.to_bytes());
+            record.sequence = sequence;
+            record.level = level;
+            record.part = if complete { part } else { LogPart::Truncated };
+            record.module_length = module_length as u8;
+            record.message_length = message_length as u8;
+        });
+    }
+
+    /// Removes and returns the oldest record
+    pub fn pop(&self) -> Option<LogRecord> {
+        critical_section::with(|cs| {
+            let mut ring = self.ring.borrow_ref_mut(cs);
+            if ring.length == 0 {
+                return None;
+            }
+
+            let record = ring.records[ring.head];
+            ring.head = (ring.head + 1) % N;
+            ring.length -= 1;
+            Some(record)
+        })
+    }
+
+    /// Removes all records, passing them to `f` oldest first. Records pushed meanwhile are
+    /// drained as well.
+    pub fn drain(&self, mut f: impl FnMut(&LogRecord)) {
+        while let Some(record) = self.pop() {
+            f(&record);
+        }
+    }
+
+    /// Passes all records to `f`, oldest first, without removing them
+    ///
+    /// `f` runs inside the critical section, so it should only copy or encode the records.
+    pub fn snapshot(&self, mut f: impl FnMut(&LogRecord)) {
+        critical_section::with(|cs| {
+            let ring = self.ring.borrow_ref(cs);
+            for i in 0..ring.length {
+                f(&ring.records[(ring.head + i) % N]);
+            }
+        });
+    }
+
+    /// Number of records stored
+    pub fn len(&self) -> usize {
+        critical_section::with(|cs| self.ring.borrow_ref(cs).length)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all records, sequence numbers keep counting
+    pub fn clear(&self) {
+        critical_section::with(|cs| {
+            let mut ring = self.ring.borrow_ref_mut(cs);
+            ring.head = 0;
+            ring.length = 0;
+        });
+    }
+}
+
+impl<const N: usize> Default for LogRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of records kept by [`LOG_RING`]
+pub const LOG_RING_RECORDS: usize = 32;
+
+/// Ring filled by [`log_ring_sink`]
+pub static LOG_RING: LogRing<LOG_RING_RECORDS> = LogRing::new();
+
+/// Log sink storing every line in [`LOG_RING`], install it with
+/// [`set_log_sink`](crate::set_log_sink)
+pub fn log_ring_sink(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+    LOG_RING.push(level, module, message, part);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn messages<const N: usize>(ring: &LogRing<N>) -> Vec<(u32, String)> {
+        let mut messages = Vec::new();
+        ring.snapshot(|record| messages.push((record.sequence(), record.message().into())));
+        messages
+    }
+
+    fn push(ring: &LogRing<3>, message: &CStr) {
+        ring.push(2, c"test", message, LogPart::Complete);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let ring = LogRing::<3>::new();
+        push(&ring, c"a");
+        push(&ring, c"b");
+        assert_eq!(ring.pop().unwrap().message(), "a");
+        push(&ring, c"c");
+        push(&ring, c"d");
+        // Fills the last slot and the one freed by pop
+        assert_eq!(ring.len(), 3);
+        assert_eq!(
+            messages(&ring),
+            [(1, "b".into()), (2, "c".into()), (3, "d".into())]
+        );
+
+        let mut drained = Vec::new();
+        ring.drain(|record| drained.push(record.sequence()));
+        assert_eq!(drained, [1, 2, 3]);
+        assert!(ring.is_empty());
+        assert!(ring.pop().is_none());
+    }
+
+    #[test]
+    fn overwrites_oldest() {
+        let ring = LogRing::<3>::new();
+        for message in [c"a", c"b", c"c", c"d", c"e"] {
+            push(&ring, message);
+        }
+        assert_eq!(ring.len(), 3);
+        assert_eq!(
+            messages(&ring),
+            [(2, "c".into()), (3, "d".into()), (4, "e".into())]
+        );
+
+        let record = ring.pop().unwrap();
+        assert_eq!(record.level(), 2);
+        assert_eq!(record.module(), "test");
+        assert_eq!(record.part(), LogPart::Complete);
+
+        // Sequence numbers keep counting after a clear
+        ring.clear();
+        push(&ring, c"f");
+        assert_eq!(messages(&ring), [(5, "f".into())]);
+    }
+
+    #[test]
+    fn truncates_module_and_message() {
+        let ring = LogRing::<1>::new();
+        let module = c"a_module_name_longer_than_the_limit";
+        let message = CString::new("m".repeat(LOG_RECORD_MESSAGE_LENGTH + 10)).unwrap();
+        ring.push(1, module, &message, LogPart::Complete);
+        let record = ring.pop().unwrap();
+        assert_eq!(
+            record.module().as_bytes(),
+            &module.to_bytes()[..LOG_RECORD_MODULE_LENGTH]
+        );
+        assert_eq!(record.message(), "m".repeat(LOG_RECORD_MESSAGE_LENGTH));
+        assert_eq!(record.part(), LogPart::Truncated);
+
+        // Exactly at the limits nothing is cut
+        let module = CString::new("n".repeat(LOG_RECORD_MODULE_LENGTH)).unwrap();
+        let message = CString::new("m".repeat(LOG_RECORD_MESSAGE_LENGTH)).unwrap();
+        ring.push(1, &module, &message, LogPart::Complete);
+        let record = ring.pop().unwrap();
+        assert_eq!(record.module().len(), LOG_RECORD_MODULE_LENGTH);
+        assert_eq!(record.message().len(), LOG_RECORD_MESSAGE_LENGTH);
+        assert_eq!(record.part(), LogPart::Complete);
+    }
+
+    #[test]
+    fn cuts_between_characters() {
+        let ring = LogRing::<1>::new();
+        // 23 bytes, then a character of two that does not fit
+        let module =
+            CString::new(format!("{}é", "n".repeat(LOG_RECORD_MODULE_LENGTH - 1))).unwrap();
+        ring.push(1, &module, c"bad \xff byte", LogPart::Complete);
+        let record = ring.pop().unwrap();
+        assert_eq!(record.module(), "n".repeat(LOG_RECORD_MODULE_LENGTH - 1));
+        assert_eq!(record.message(), "bad ? byte");
+    }
+}
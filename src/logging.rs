@@ -0,0 +1,773 @@
+//! Runtime log sink behind the C log wrapper
+//!
+//! `c_log_stub` formats the RSS log line and hands it to [`a121_sys_log`], which forwards it to
+//! whatever sink was installed with [`set_log_sink`]. Until then, or after [`clear_log_sink`], log
+//! lines are dropped, or with the `log` feature go to [`log_sink`]. The sink gets the level, the
+//! RSS module and the formatted message separately, so lines can be filtered or tagged by module:
+//!
+//! ```no_run
+//! use a121_sys::{acc_log_level_t, set_log_sink, LogPart};
+//! use core::ffi::CStr;
+//!
+//! fn sink(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+//!     // Your logging implementation here
+//! }
+//!
+//! set_log_sink(sink);
+//! ```
+//!
+//! `acc_rss_set_log_level` sets one level for the whole RSS. A [`LogFilter`] installed with
+//! [`set_log_filter`] narrows that down per module. Before formatting, `c_log_stub` asks
+//! [`a121_sys_log_enabled`] whether the filter lets the line through, so filtered lines cost no
+//! formatting. Lines the global level drops never reach the filter, so set that to the most
+//! verbose level any module needs.
+//!
+//! Lines are formatted into a buffer of `A121_SYS_LOG_BUFFER_SIZE` bytes, 256 unless set when
+//! building. Longer lines are cut and passed on as [`LogPart::Truncated`], or with the
+//! `log_chunks` feature split into chunks of the buffer size, all but the last passed on as
+//! [`LogPart::Continued`]. Chunks are split at byte boundaries, and a single numeric conversion
+//! is still limited to 31 characters.
+//!
+//! By default `c_log_stub` formats with the toolchain's `vsnprintf`. With the `bundled_printf`
+//! feature it uses a small formatter shipped with the crate instead, which handles floats without
+//! pulling in newlib's printf and works on targets without a libc. It covers the C99
+//! conversions, but computes at most 17 fractional digits and writes `%f` values above 1e18 as
+//! with `%e`.
+//!
+//! With the `defmt` feature `c_log_stub` instead calls `a121_sys_log_defmt`, which writes the
+//! message straight into a defmt frame at the matching level, tagged with the module as
+//! `[module]`. Text from the format string and `%s` arguments are written from where they are,
+//! and other conversions go through a 32 byte buffer, so no line is staged on the stack.
+//!
+//! With the `log_capture` feature, lines written while [`capture_log`](crate::capture_log) runs go
+//! to the capture instead, whatever the sink, filter or `defmt` feature.
+//!
+//! With `rust_stubs` no C is compiled and there is no `c_log_stub`. The Rust stubs call the HAL's
+//! `log` function with each line as the argument of a `"%s"` format, and with `log` left `None`
+//! hand their lines straight to the filter and then the sink, capture or defmt. Lines are passed
+//! on whole, so the buffer size, `log_chunks` and `bundled_printf` have no effect.
+
+use crate::acc_log_level_t;
+use core::ffi::{c_char, CStr};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// Receives every log line, with the RSS module (e.g. `"sensor"`), the formatted message and
+/// whether that is the whole message
+pub type LogSink = fn(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart);
+
+/// How a message handed to a [`LogSink`] relates to the line the RSS logged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogPart {
+    /// The whole line, or the last chunk of a line split with `log_chunks`
+    Complete,
+    /// The line did not fit the log buffer and was cut
+    Truncated,
+    /// A chunk of a line split with `log_chunks`, the next message continues it
+    Continued,
+}
+
+// Flags passed by `c_log_stub`, must match c_src/logging.c
+const LOG_FLAG_TRUNCATED: u32 = 1;
+const LOG_FLAG_CONTINUED: u32 = 2;
+
+// Only plain loads and stores, so this also works on targets without atomic read-modify-write
+static SINK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+#[cfg(feature = "log")]
+const DEFAULT_SINK: Option<LogSink> = Some(log_sink);
+#[cfg(not(feature = "log"))]
+const DEFAULT_SINK: Option<LogSink> = None;
+
+/// Installs `sink` as the receiver of all log lines, replacing any previous sink
+pub fn set_log_sink(sink: LogSink) {
+    SINK.store(sink as *mut (), Ordering::Release);
+}
+
+/// Removes the installed sink, returning to the default of dropping log lines, or forwarding
+/// them to [`log_sink`] with the `log` feature
+pub fn clear_log_sink() {
+    SINK.store(ptr::null_mut(), Ordering::Release);
+}
+
+/// Minimum log level per RSS module, on top of the global `acc_rss_set_log_level`
+///
+/// A line passes when its level is at most as verbose as the level of its module, or of
+/// `default` for modules not listed. Module names are matched exactly.
+///
+/// ```no_run
+/// use a121_sys::*;
+///
+/// static FILTER: LogFilter = LogFilter::new(
+///     acc_log_level_t_ACC_LOG_LEVEL_INFO,
+///     &[
+///         (c"distance", acc_log_level_t_ACC_LOG_LEVEL_VERBOSE),
+///         (c"sensor", acc_log_level_t_ACC_LOG_LEVEL_WARNING),
+///     ],
+/// );
+///
+/// set_log_filter(&FILTER);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LogFilter<'a> {
+    default: acc_log_level_t,
+    modules: &'a [(&'a CStr, acc_log_level_t)],
+}
+
+impl<'a> LogFilter<'a> {
+    pub const fn new(default: acc_log_level_t, modules: &'a [(&'a CStr, acc_log_level_t)]) -> Self {
+        Self { default, modules }
+    }
+
+    /// Level lines of `module` have to be at or below to pass
+    pub fn level(&self, module: &CStr) -> acc_log_level_t {
+        self.modules
+            .iter()
+            .find(|(name, _)| *name == module)
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, level: acc_log_level_t, module: &CStr) -> bool {
+        level <= self.level(module)
+    }
+}
+
+static FILTER: AtomicPtr<LogFilter<'static>> = AtomicPtr::new(ptr::null_mut());
+
+/// Installs `filter`, replacing any previous filter. Swap in another `static` filter to change
+/// levels at runtime.
+pub fn set_log_filter(filter: &'static LogFilter<'static>) {
+    FILTER.store(ptr::from_ref(filter).cast_mut(), Ordering::Release);
+}
+
+/// Removes the installed filter, letting all lines through again
+pub fn clear_log_filter() {
+    FILTER.store(ptr::null_mut(), Ordering::Release);
+}
+
+/// Called by `c_log_stub` before formatting a line, which is dropped when this returns false
+///
+/// # Safety
+///
+/// `module` must be null or a valid NUL terminated string.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn a121_sys_log_enabled(
+    level: acc_log_level_t,
+    module: *const c_char,
+) -> bool {
+    #[cfg(feature = "log_capture")]
+    if crate::log_capture::capturing() {
+        return true;
+    }
+
+    // Only ever stored from a `&'static LogFilter` in `set_log_filter`
+    let Some(filter) = FILTER.load(Ordering::Acquire).as_ref() else {
+        return true;
+    };
+    let module = if module.is_null() {
+        c""
+    } else {
+        CStr::from_ptr(module)
+    };
+    filter.enabled(level, module)
+}
+
+/// Hands a line logged by the Rust stubs on like `c_log_stub` does with a formatted line
+#[cfg(feature = "rust_stubs")]
+pub(crate) fn stub_log(level: acc_log_level_t, module: &CStr, message: &CStr) {
+    unsafe {
+        if !a121_sys_log_enabled(level, module.as_ptr()) {
+            return;
+        }
+        #[cfg(feature = "defmt")]
+        defmt_log::log_text(level, module.as_ptr(), message.to_bytes());
+        #[cfg(not(feature = "defmt"))]
+        a121_sys_log(level, module.as_ptr(), message.as_ptr(), 0);
+    }
+}
+
+/// Called by `c_log_stub` with the formatted log line
+///
+/// # Safety
+///
+/// `module` and `message` must be null or valid NUL terminated strings.
+#[doc(hidden)]
+#[no_mangle]
+pub unsafe extern "C" fn a121_sys_log(
+    level: acc_log_level_t,
+    module: *const c_char,
+    message: *const c_char,
+    flags: u32,
+) {
+    let sink = SINK.load(Ordering::Acquire);
+    if message.is_null() {
+        return;
+    }
+    #[cfg(feature = "log_capture")]
+    if crate::log_capture::capture_text(
+        CStr::from_ptr(message).to_bytes(),
+        flags & LOG_FLAG_CONTINUED == 0,
+    ) {
+        return;
+    }
+
+    let sink = if sink.is_null() {
+        match DEFAULT_SINK {
+            Some(sink) => sink,
+            None => return,
+        }
+    } else {
+        // Only ever stored from a `LogSink` in `set_log_sink`
+        core::mem::transmute::<*mut (), LogSink>(sink)
+    };
+    let module = if module.is_null() {
+        c""
+    } else {
+        CStr::from_ptr(module)
+    };
+    let part = if flags & LOG_FLAG_CONTINUED != 0 {
+        LogPart::Continued
+    } else if flags & LOG_FLAG_TRUNCATED != 0 {
+        LogPart::Truncated
+    } else {
+        LogPart::Complete
+    };
+    sink(level, module, CStr::from_ptr(message), part);
+}
+
+/// Forwards a log line to the [`log`] facade, with the RSS module as target
+///
+/// VERBOSE maps to [`log::Level::Debug`] and DEBUG to [`log::Level::Trace`]. Invalid UTF-8 in
+/// the message is replaced with U+FFFD. Truncated lines end in `" [truncated]"`, chunks of a
+/// split line are logged as separate records.
+#[cfg(feature = "log")]
+pub fn log_sink(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+    let level = match level {
+        crate::acc_log_level_t_ACC_LOG_LEVEL_ERROR => log::Level::Error,
+        crate::acc_log_level_t_ACC_LOG_LEVEL_WARNING => log::Level::Warn,
+        crate::acc_log_level_t_ACC_LOG_LEVEL_INFO => log::Level::Info,
+        crate::acc_log_level_t_ACC_LOG_LEVEL_VERBOSE => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    let target = match module.to_str() {
+        Ok(module) if !module.is_empty() => module,
+        _ => "a121",
+    };
+    let marker = if part == LogPart::Truncated {
+        " [truncated]"
+    } else {
+        ""
+    };
+    log::log!(target: target, level, "{}{}", Lossy(message.to_bytes()), marker);
+}
+
+/// Displays bytes as UTF-8 without allocating, replacing invalid sequences
+#[cfg(feature = "log")]
+struct Lossy<'a>(&'a [u8]);
+
+#[cfg(feature = "log")]
+impl core::fmt::Display for Lossy<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for chunk in self.0.utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                f.write_str("\u{FFFD}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+mod defmt_log {
+    use super::*;
+    #[cfg(not(feature = "rust_stubs"))]
+    use core::ffi::c_void;
+
+    /// Arguments of a `c_log_stub` call, only read by `c_log_write_segments`
+    #[cfg(not(feature = "rust_stubs"))]
+    #[repr(C)]
+    pub struct c_log_args {
+        _private: [u8; 0],
+    }
+
+    #[cfg(not(feature = "rust_stubs"))]
+    type c_log_segment_t =
+        unsafe extern "C" fn(context: *mut c_void, segment: *const c_char, length: usize);
+
+    #[cfg(not(feature = "rust_stubs"))]
+    extern "C" {
+        fn c_log_write_segments(
+            args: *mut c_log_args,
+            write: c_log_segment_t,
+            context: *mut c_void,
+        );
+    }
+
+    /// Called by `c_log_stub` with the format and arguments of a log line, built with `defmt`
+    ///
+    /// The level picks the defmt macro and the module is written as a `[module]` tag. The
+    /// message is formatted while the frame is written, so sinks installed with
+    /// [`set_log_sink`] are not called.
+    ///
+    /// # Safety
+    ///
+    /// `module` must be null or a valid NUL terminated string and `args` must come from
+    /// `c_log_stub`.
+    #[cfg(not(feature = "rust_stubs"))]
+    #[doc(hidden)]
+    #[no_mangle]
+    pub unsafe extern "C" fn a121_sys_log_defmt(
+        level: acc_log_level_t,
+        module: *const c_char,
+        args: *mut c_log_args,
+    ) {
+        #[cfg(feature = "log_capture")]
+        if crate::log_capture::capturing() {
+            unsafe extern "C" fn write(_: *mut c_void, segment: *const c_char, length: usize) {
+                let segment = core::slice::from_raw_parts(segment.cast::<u8>(), length);
+                crate::log_capture::capture_text(segment, false);
+            }
+
+            c_log_write_segments(args, write, ptr::null_mut());
+            crate::log_capture::capture_text(b"", true);
+            return;
+        }
+
+        log(level, module, DefmtMessage(args));
+    }
+
+    /// Writes a line logged by the Rust stubs to defmt, like `a121_sys_log_defmt`
+    #[cfg(feature = "rust_stubs")]
+    pub(super) unsafe fn log_text(level: acc_log_level_t, module: *const c_char, message: &[u8]) {
+        #[cfg(feature = "log_capture")]
+        if crate::log_capture::capture_text(message, true) {
+            return;
+        }
+
+        log(level, module, DefmtText(message));
+    }
+
+    unsafe fn log(level: acc_log_level_t, module: *const c_char, message: impl defmt::Format) {
+        let module = if module.is_null() {
+            ""
+        } else {
+            CStr::from_ptr(module).to_str().unwrap_or("?")
+        };
+        match level {
+            crate::acc_log_level_t_ACC_LOG_LEVEL_ERROR => {
+                defmt::error!("[{=str}] {}", module, message)
+            }
+            crate::acc_log_level_t_ACC_LOG_LEVEL_WARNING => {
+                defmt::warn!("[{=str}] {}", module, message)
+            }
+            crate::acc_log_level_t_ACC_LOG_LEVEL_INFO => {
+                defmt::info!("[{=str}] {}", module, message)
+            }
+            crate::acc_log_level_t_ACC_LOG_LEVEL_VERBOSE => {
+                defmt::debug!("[{=str}] {}", module, message)
+            }
+            _ => defmt::trace!("[{=str}] {}", module, message),
+        }
+    }
+
+    /// Writes bytes as UTF-8, replacing invalid sequences
+    fn write_lossy(f: defmt::Formatter<'_>, text: &[u8]) {
+        for chunk in text.utf8_chunks() {
+            defmt::write!(f, "{=str}", chunk.valid());
+            if !chunk.invalid().is_empty() {
+                defmt::write!(f, "\u{FFFD}");
+            }
+        }
+    }
+
+    /// Message of a `c_log_stub` call, formatted by C as it is written
+    #[cfg(not(feature = "rust_stubs"))]
+    struct DefmtMessage(*mut c_log_args);
+
+    #[cfg(not(feature = "rust_stubs"))]
+    impl defmt::Format for DefmtMessage {
+        fn format(&self, f: defmt::Formatter<'_>) {
+            unsafe extern "C" fn write(
+                context: *mut c_void,
+                segment: *const c_char,
+                length: usize,
+            ) {
+                let f = *context.cast::<defmt::Formatter<'_>>();
+                write_lossy(f, core::slice::from_raw_parts(segment.cast::<u8>(), length));
+            }
+
+            // The arguments can only be walked once, defmt formats each log call once
+            unsafe {
+                c_log_write_segments(
+                    self.0,
+                    write,
+                    (&f as *const defmt::Formatter<'_>).cast_mut().cast(),
+                )
+            };
+        }
+    }
+
+    /// Message of a line logged by the Rust stubs
+    #[cfg(feature = "rust_stubs")]
+    struct DefmtText<'a>(&'a [u8]);
+
+    #[cfg(feature = "rust_stubs")]
+    impl defmt::Format for DefmtText<'_> {
+        fn format(&self, f: defmt::Formatter<'_>) {
+            write_lossy(f, self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        acc_log_level_t_ACC_LOG_LEVEL_DEBUG as DEBUG, acc_log_level_t_ACC_LOG_LEVEL_ERROR as ERROR,
+        acc_log_level_t_ACC_LOG_LEVEL_INFO as INFO,
+        acc_log_level_t_ACC_LOG_LEVEL_VERBOSE as VERBOSE,
+        acc_log_level_t_ACC_LOG_LEVEL_WARNING as WARNING,
+    };
+    use core::sync::atomic::AtomicUsize;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    static GLOBALS: Mutex<()> = Mutex::new(());
+
+    /// Held by the tests using the installed sink, filter or capture
+    pub(crate) fn lock_globals() -> MutexGuard<'static, ()> {
+        lock(&GLOBALS)
+    }
+
+    type Line = (acc_log_level_t, String, String, LogPart);
+
+    static LINES: Mutex<Vec<Line>> = Mutex::new(Vec::new());
+
+    /// Sink recording every line, see [`take_lines`]
+    fn record(level: acc_log_level_t, module: &CStr, message: &CStr, part: LogPart) {
+        let module = module.to_string_lossy().into_owned();
+        let message = message.to_string_lossy().into_owned();
+        lock(&LINES).push((level, module, message, part));
+    }
+
+    fn take_lines() -> Vec<Line> {
+        core::mem::take(&mut *lock(&LINES))
+    }
+
+    fn lock<T>(mutex: &'static Mutex<T>) -> MutexGuard<'static, T> {
+        mutex.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    static REPLACED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Sink counting the lines, to replace [`record`] with
+    fn count(_: acc_log_level_t, _: &CStr, _: &CStr, _: LogPart) {
+        REPLACED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Hands `message` to the installed sink as `c_log_stub` does
+    fn log_line(message: &CStr) {
+        unsafe { a121_sys_log(INFO, c"test".as_ptr(), message.as_ptr(), 0) };
+    }
+
+    /// Size of the log buffer `c_log_stub` was built with
+    #[cfg(not(any(feature = "rust_stubs", feature = "defmt")))]
+    fn buffer_size() -> usize {
+        option_env!("A121_SYS_LOG_BUFFER_SIZE").map_or(256, |size| size.trim().parse().unwrap())
+    }
+
+    /// Logs `message` through `c_log_stub` into the recording sink
+    #[cfg(not(any(feature = "rust_stubs", feature = "defmt")))]
+    fn log_through_c(message: &str) -> Vec<Line> {
+        let message = std::ffi::CString::new(message).unwrap();
+        set_log_sink(record);
+        take_lines();
+        unsafe { crate::c_log_stub(INFO, c"test".as_ptr(), c"%s".as_ptr(), message.as_ptr()) };
+        clear_log_sink();
+        take_lines()
+    }
+
+    /// Logger recording every record as level, target and message, see [`take_records`]
+    #[cfg(feature = "log")]
+    struct Records;
+
+    #[cfg(feature = "log")]
+    static RECORDS: Mutex<Vec<(log::Level, String, String)>> = Mutex::new(Vec::new());
+
+    #[cfg(feature = "log")]
+    impl log::Log for Records {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            let target = record.target().into();
+            lock(&RECORDS).push((record.level(), target, record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[cfg(feature = "log")]
+    fn take_records() -> Vec<(log::Level, String, String)> {
+        core::mem::take(&mut *lock(&RECORDS))
+    }
+
+    /// Global logger stub keeping the raw defmt frames, see [`take_frames`]
+    #[cfg(feature = "defmt")]
+    #[defmt::global_logger]
+    struct Frames;
+
+    #[cfg(feature = "defmt")]
+    static FRAMES: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+    #[cfg(feature = "defmt")]
+    unsafe impl defmt::Logger for Frames {
+        fn acquire() {}
+
+        unsafe fn flush() {}
+
+        unsafe fn release() {}
+
+        unsafe fn write(bytes: &[u8]) {
+            lock(&FRAMES).extend_from_slice(bytes);
+        }
+    }
+
+    // Provided by the defmt linker script on targets, which host tests do not link with
+    #[cfg(feature = "defmt")]
+    defmt::timestamp!("");
+
+    #[cfg(feature = "defmt")]
+    fn take_frames() -> Vec<u8> {
+        core::mem::take(&mut *lock(&FRAMES))
+    }
+
+    static LEVELS: LogFilter =
+        LogFilter::new(INFO, &[(c"distance", VERBOSE), (c"sensor", WARNING)]);
+
+    #[test]
+    fn unlisted_modules_use_the_default() {
+        assert_eq!(LEVELS.level(c"processing"), INFO);
+        assert!(LEVELS.enabled(ERROR, c"processing"));
+        assert!(LEVELS.enabled(INFO, c"processing"));
+        assert!(!LEVELS.enabled(VERBOSE, c"processing"));
+        assert!(!LEVELS.enabled(VERBOSE, c""));
+    }
+
+    #[test]
+    fn listed_modules_override_the_default() {
+        assert_eq!(LEVELS.level(c"sensor"), WARNING);
+        assert!(LEVELS.enabled(WARNING, c"sensor"));
+        assert!(!LEVELS.enabled(INFO, c"sensor"));
+        assert_eq!(LEVELS.level(c"distance"), VERBOSE);
+        assert!(LEVELS.enabled(VERBOSE, c"distance"));
+        assert!(!LEVELS.enabled(DEBUG, c"distance"));
+    }
+
+    #[test]
+    fn modules_match_exactly() {
+        for module in [c"dist", c"distances", c"Distance", c"sensor "] {
+            assert_eq!(LEVELS.level(module), INFO, "{:?}", module);
+        }
+    }
+
+    #[test]
+    fn log_enabled_follows_the_installed_filter() {
+        let _globals = lock_globals();
+        unsafe {
+            assert!(a121_sys_log_enabled(DEBUG, c"sensor".as_ptr()));
+
+            set_log_filter(&LEVELS);
+            assert!(!a121_sys_log_enabled(INFO, c"sensor".as_ptr()));
+            assert!(a121_sys_log_enabled(WARNING, c"sensor".as_ptr()));
+            // A null module is unlisted
+            assert!(a121_sys_log_enabled(INFO, ptr::null()));
+            assert!(!a121_sys_log_enabled(VERBOSE, ptr::null()));
+
+            clear_log_filter();
+            assert!(a121_sys_log_enabled(DEBUG, c"sensor".as_ptr()));
+        }
+    }
+
+    #[test]
+    fn sinks_are_set_replaced_and_cleared() {
+        let _globals = lock_globals();
+        clear_log_sink();
+        take_lines();
+        // Dropped, or with `log` handed to `log_sink`
+        log_line(c"no sink");
+        assert_eq!(take_lines(), []);
+
+        set_log_sink(record);
+        log_line(c"recorded");
+        assert_eq!(
+            take_lines(),
+            [(INFO, "test".into(), "recorded".into(), LogPart::Complete)]
+        );
+
+        let replaced = REPLACED.load(Ordering::SeqCst);
+        set_log_sink(count);
+        log_line(c"counted");
+        assert_eq!(take_lines(), []);
+        assert_eq!(REPLACED.load(Ordering::SeqCst), replaced + 1);
+
+        clear_log_sink();
+        log_line(c"cleared");
+        assert_eq!(REPLACED.load(Ordering::SeqCst), replaced + 1);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log_sink_forwards_to_the_log_facade() {
+        let _globals = lock_globals();
+        // Only the first logger set in the process is kept, this is the only test setting one
+        log::set_logger(&Records).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        take_records();
+
+        for (level, expected) in [
+            (ERROR, log::Level::Error),
+            (WARNING, log::Level::Warn),
+            (INFO, log::Level::Info),
+            (VERBOSE, log::Level::Debug),
+            (DEBUG, log::Level::Trace),
+        ] {
+            log_sink(level, c"sensor", c"line", LogPart::Complete);
+            assert_eq!(take_records(), [(expected, "sensor".into(), "line".into())]);
+        }
+
+        log_sink(INFO, c"", c"cut", LogPart::Truncated);
+        log_sink(INFO, c"sensor", c"a\xffb", LogPart::Continued);
+        assert_eq!(
+            take_records(),
+            [
+                (log::Level::Info, "a121".into(), "cut [truncated]".into()),
+                (log::Level::Info, "sensor".into(), "a\u{FFFD}b".into()),
+            ]
+        );
+
+        // It is the default sink
+        clear_log_sink();
+        unsafe { a121_sys_log(WARNING, c"distance".as_ptr(), c"default".as_ptr(), 0) };
+        assert_eq!(
+            take_records(),
+            [(log::Level::Warn, "distance".into(), "default".into())]
+        );
+    }
+
+    #[cfg(not(any(feature = "rust_stubs", feature = "defmt", feature = "log_chunks")))]
+    #[test]
+    fn long_lines_are_truncated() {
+        let _globals = lock_globals();
+        let fits = "x".repeat(buffer_size() - 1);
+        assert_eq!(
+            log_through_c(&fits),
+            [(INFO, "test".into(), fits.clone(), LogPart::Complete)]
+        );
+
+        let long = format!("{}yz", fits);
+        assert_eq!(
+            log_through_c(&long),
+            [(INFO, "test".into(), fits, LogPart::Truncated)]
+        );
+    }
+
+    #[cfg(all(
+        feature = "log_chunks",
+        not(any(feature = "rust_stubs", feature = "defmt"))
+    ))]
+    #[test]
+    fn long_lines_are_split_into_chunks() {
+        let _globals = lock_globals();
+        let chunk = buffer_size() - 1;
+        let fits = "x".repeat(chunk);
+        assert_eq!(
+            log_through_c(&fits),
+            [(INFO, "test".into(), fits, LogPart::Complete)]
+        );
+
+        let long: String = ('a'..='z').cycle().take(chunk * 2 + 1).collect();
+        let lines = log_through_c(&long);
+        let parts: Vec<_> = lines
+            .iter()
+            .map(|(_, _, message, part)| (message.len(), *part))
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                (chunk, LogPart::Continued),
+                (chunk, LogPart::Continued),
+                (1, LogPart::Complete)
+            ]
+        );
+        let joined: String = lines
+            .into_iter()
+            .map(|(_, _, message, _)| message)
+            .collect();
+        assert_eq!(joined, long);
+    }
+
+    #[cfg(all(
+        feature = "log_chunks",
+        not(any(feature = "rust_stubs", feature = "defmt"))
+    ))]
+    #[test]
+    fn long_widths_and_precisions_are_clamped() {
+        let _globals = lock_globals();
+        set_log_sink(record);
+        take_lines();
+        unsafe {
+            crate::c_log_stub(
+                INFO,
+                c"test".as_ptr(),
+                c"%99999999999999999999d|%.99999999999999999999s|%*d|".as_ptr(),
+                7,
+                c"text".as_ptr(),
+                i32::MIN,
+                7,
+            )
+        };
+        clear_log_sink();
+        let message: String = take_lines()
+            .into_iter()
+            .map(|(_, _, message, _)| message)
+            .collect();
+        // Numbers are cut at the 31 characters of the conversion, the first 7 is past that
+        assert_eq!(
+            message,
+            format!("{}|text|7{}|", " ".repeat(31), " ".repeat(30))
+        );
+    }
+
+    #[cfg(all(feature = "defmt", not(feature = "rust_stubs")))]
+    #[test]
+    fn defmt_frames_hold_each_segment_of_the_message() {
+        let _globals = lock_globals();
+        take_frames();
+        // Without `DEFMT_LOG` set when building, defmt only keeps errors
+        unsafe {
+            crate::c_log_stub(
+                ERROR,
+                c"test".as_ptr(),
+                c"%s=%d%%".as_ptr(),
+                c"value".as_ptr(),
+                42,
+            )
+        };
+        let frames = take_frames();
+
+        // The module and every literal and conversion are written as a u32 length and the text
+        let mut rest = &frames[..];
+        for segment in ["test", "value", "=", "42", "%"] {
+            let mut expected = (segment.len() as u32).to_le_bytes().to_vec();
+            expected.extend_from_slice(segment.as_bytes());
+            let start = rest
+                .windows(expected.len())
+                .position(|window| window == expected)
+                .unwrap_or_else(|| panic!("{:?} not in {:?}", segment, frames));
+            rest = &rest[start + expected.len()..];
+        }
+    }
+}
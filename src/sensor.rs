@@ -0,0 +1,347 @@
+//! Safe owner of an `acc_sensor_t`, with its state in the type
+//!
+//! A [`Sensor`] goes from [`Created`] through [`Calibrated`] and [`Prepared`] to [`Measuring`]
+//! and back to [`Prepared`] once the frame is read. Each step consumes the sensor and returns it
+//! in the next state, so calling `acc_sensor_prepare` before a calibration or `acc_sensor_read`
+//! before `acc_sensor_measure` does not compile. A failed step hands the sensor back in a state
+//! it can be retried from, inside a [`SensorFailure`].
+//!
+//! ```compile_fail,E0599
+//! # use a121_sys::{Config, Sensor};
+//! let config = Config::builder().build().unwrap();
+//! let mut buffer = [0u64; 2048];
+//! // Not calibrated yet
+//! let sensor = Sensor::new(1).unwrap().prepare(&config, &mut buffer);
+//! ```
+//!
+//! ```compile_fail,E0599
+//! # use a121_sys::{Config, Sensor};
+//! # let config = Config::builder().build().unwrap();
+//! # let mut buffer = [0u64; 2048];
+//! # let sensor = Sensor::new(1).unwrap().calibrate(&mut buffer, || true).unwrap();
+//! let sensor = sensor.prepare(&config, &mut buffer).unwrap();
+//! // Not measuring yet
+//! let sensor = sensor.read(&mut buffer, || true);
+//! ```
+
+use crate::*;
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ptr::{self, NonNull};
+
+/// Why a step of a [`Sensor`] failed. The RSS logs the details through the HAL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SensorError {
+    /// `acc_sensor_create` returned null, the HAL is not registered, the sensor is not powered
+    /// or out of memory
+    CreateFailed,
+    CalibrationFailed,
+    /// The wait for the sensor interrupt returned false
+    InterruptTimeout,
+    PrepareFailed,
+    MeasureFailed,
+    ReadFailed,
+}
+
+impl fmt::Display for SensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensorError::CreateFailed => write!(f, "Sensor could not be created"),
+            SensorError::CalibrationFailed => write!(f, "Sensor calibration failed"),
+            SensorError::InterruptTimeout => write!(f, "Timeout waiting for the sensor interrupt"),
+            SensorError::PrepareFailed => write!(f, "Sensor could not be prepared"),
+            SensorError::MeasureFailed => write!(f, "Sensor measurement could not be started"),
+            SensorError::ReadFailed => write!(f, "Sensor data could not be read"),
+        }
+    }
+}
+
+impl core::error::Error for SensorError {}
+
+/// A failed step, with the sensor in the state to retry from
+pub struct SensorFailure<S> {
+    pub error: SensorError,
+    pub sensor: Sensor<S>,
+}
+
+impl<S> fmt::Debug for SensorFailure<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SensorFailure")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> fmt::Display for SensorFailure<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<S> From<SensorFailure<S>> for SensorError {
+    fn from(failure: SensorFailure<S>) -> Self {
+        failure.error
+    }
+}
+
+/// Sensor state after `acc_sensor_create`
+#[derive(Debug)]
+pub struct Created;
+
+/// Sensor state after a completed `acc_sensor_calibrate`
+#[derive(Debug)]
+pub struct Calibrated {
+    cal_result: acc_cal_result_t,
+}
+
+/// Sensor state after `acc_sensor_prepare`, borrowing the config it was prepared for
+#[derive(Debug)]
+pub struct Prepared<'a> {
+    calibrated: Calibrated,
+    config: &'a Config,
+}
+
+/// Sensor state after `acc_sensor_measure`, until the frame is read
+#[derive(Debug)]
+pub struct Measuring<'a> {
+    prepared: Prepared<'a>,
+}
+
+/// Radar sensor in state `S`, destroyed with `acc_sensor_destroy` when dropped
+///
+/// Buffers passed to the steps have to hold at least [`Config::buffer_size`] bytes. They are
+/// `u64` slices, as the RSS needs them aligned for any built-in type, which `u32` is not.
+/// `wait_for_interrupt` waits for the sensor interrupt, like
+/// `acc_hal_integration_wait_for_sensor_interrupt`, and returns false on timeout.
+///
+/// ```no_run
+/// use a121_sys::{Config, Sensor, SensorError};
+///
+/// # fn wait_for_interrupt() -> bool { true }
+/// let config = Config::builder().sweeps_per_frame(4).build().unwrap();
+/// let mut buffer = [0u64; 2048];
+///
+/// let sensor = Sensor::new(1)?;
+/// let sensor = sensor.calibrate(&mut buffer, wait_for_interrupt)?;
+/// let mut sensor = sensor.prepare(&config, &mut buffer)?;
+/// loop {
+///     let measuring = sensor.measure()?;
+///     sensor = measuring.read(&mut buffer, wait_for_interrupt)?;
+///     // Process the frame in buffer with acc_processing_execute
+/// }
+/// # Ok::<(), SensorError>(())
+/// ```
+pub struct Sensor<S> {
+    inner: NonNull<acc_sensor_t>,
+    state: S,
+}
+
+/// Size of `buffer` in bytes, as passed to the RSS
+fn byte_size(buffer: &[u64]) -> u32 {
+    u32::try_from(size_of_val(buffer)).unwrap_or(u32::MAX)
+}
+
+impl<S> Sensor<S> {
+    /// Pointer for the raw `acc_*` functions, valid as long as the sensor
+    pub fn as_ptr(&self) -> *const acc_sensor_t {
+        self.inner.as_ptr()
+    }
+
+    /// Logs the sensor status through the HAL with `acc_sensor_status`
+    pub fn status(&self) {
+        unsafe { acc_sensor_status(self.as_ptr()) }
+    }
+
+    /// Same sensor in another state, without destroying it
+    fn into_state<T>(self, map: impl FnOnce(S) -> T) -> Sensor<T> {
+        let this = ManuallyDrop::new(self);
+        // Read once from a sensor that is never dropped
+        let state = unsafe { ptr::read(&this.state) };
+        // Destroys the sensor should `map` panic
+        let guard = Sensor {
+            inner: this.inner,
+            state: (),
+        };
+        let state = map(state);
+        let guard = ManuallyDrop::new(guard);
+        Sensor {
+            inner: guard.inner,
+            state,
+        }
+    }
+
+    fn fail<T>(self, error: SensorError, map: impl FnOnce(S) -> T) -> SensorFailure<T> {
+        SensorFailure {
+            error,
+            sensor: self.into_state(map),
+        }
+    }
+}
+
+impl<S> Drop for Sensor<S> {
+    fn drop(&mut self) {
+        unsafe { acc_sensor_destroy(self.inner.as_ptr()) }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Sensor<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sensor")
+            .field("inner", &self.inner)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl Sensor<Created> {
+    /// Creates the sensor instance for `sensor_id`, which has to be powered on
+    pub fn new(sensor_id: acc_sensor_id_t) -> Result<Self, SensorError> {
+        let inner = NonNull::new(unsafe { acc_sensor_create(sensor_id) })
+            .ok_or(SensorError::CreateFailed)?;
+        Ok(Self {
+            inner,
+            state: Created,
+        })
+    }
+
+    /// Whether the sensor `sensor_id` responds, with `acc_sensor_connected`
+    pub fn connected(sensor_id: acc_sensor_id_t) -> bool {
+        unsafe { acc_sensor_connected(sensor_id) }
+    }
+
+    /// Calibrates the sensor, waiting for the interrupt until the calibration is complete
+    pub fn calibrate(
+        self,
+        buffer: &mut [u64],
+        mut wait_for_interrupt: impl FnMut() -> bool,
+    ) -> Result<Sensor<Calibrated>, SensorFailure<Created>> {
+        let mut cal_result = acc_cal_result_t {
+            data: [0; (ACC_CAL_RESULT_DATA_SIZE / 4) as usize],
+        };
+        loop {
+            let mut complete = false;
+            let calibrated = unsafe {
+                acc_sensor_calibrate(
+                    self.inner.as_ptr(),
+                    &mut complete,
+                    &mut cal_result,
+                    buffer.as_mut_ptr().cast(),
+                    byte_size(buffer),
+                )
+            };
+            if !calibrated {
+                return Err(self.fail(SensorError::CalibrationFailed, |state| state));
+            }
+            if complete {
+                return Ok(self.into_state(|_| Calibrated { cal_result }));
+            }
+            if !wait_for_interrupt() {
+                return Err(self.fail(SensorError::InterruptTimeout, |state| state));
+            }
+        }
+    }
+
+    /// Uses a stored calibration instead of calibrating, e.g. one loaded after a reboot
+    pub fn with_calibration(self, calibration: &SensorCalibration) -> Sensor<Calibrated> {
+        let cal_result = *calibration.cal_result();
+        self.into_state(|_| Calibrated { cal_result })
+    }
+}
+
+impl Sensor<Calibrated> {
+    /// Result of the calibration, for `acc_processing_*` and the detectors
+    pub fn cal_result(&self) -> &acc_cal_result_t {
+        &self.state.cal_result
+    }
+
+    /// Prepares a measurement with `config`, which has to outlive the prepared sensor
+    // The calibration result makes the error large, the sensor is handed back to retry
+    #[allow(clippy::result_large_err)]
+    pub fn prepare<'a>(
+        self,
+        config: &'a Config,
+        buffer: &mut [u64],
+    ) -> Result<Sensor<Prepared<'a>>, SensorFailure<Calibrated>> {
+        let prepared = unsafe {
+            acc_sensor_prepare(
+                self.inner.as_ptr(),
+                config.as_ptr(),
+                &self.state.cal_result,
+                buffer.as_mut_ptr().cast(),
+                byte_size(buffer),
+            )
+        };
+        if !prepared {
+            return Err(self.fail(SensorError::PrepareFailed, |state| state));
+        }
+        Ok(self.into_state(|calibrated| Prepared { calibrated, config }))
+    }
+
+    /// Drops the calibration, to calibrate again, e.g. after `calibration_needed` was reported
+    pub fn into_created(self) -> Sensor<Created> {
+        self.into_state(|_| Created)
+    }
+}
+
+impl<'a> Sensor<Prepared<'a>> {
+    /// Result of the calibration the sensor was prepared with, for `acc_processing_*` and the
+    /// detectors
+    pub fn cal_result(&self) -> &acc_cal_result_t {
+        &self.state.calibrated.cal_result
+    }
+
+    /// The config the sensor was prepared for
+    pub fn config(&self) -> &'a Config {
+        self.state.config
+    }
+
+    /// Starts measuring a frame
+    #[allow(clippy::result_large_err)]
+    pub fn measure(self) -> Result<Sensor<Measuring<'a>>, SensorFailure<Prepared<'a>>> {
+        if !unsafe { acc_sensor_measure(self.inner.as_ptr()) } {
+            return Err(self.fail(SensorError::MeasureFailed, |state| state));
+        }
+        Ok(self.into_state(|prepared| Measuring { prepared }))
+    }
+
+    /// Releases the config, to prepare the sensor for another one
+    pub fn into_calibrated(self) -> Sensor<Calibrated> {
+        self.into_state(|prepared| prepared.calibrated)
+    }
+}
+
+impl<'a> Sensor<Measuring<'a>> {
+    /// The config the sensor is measuring with
+    pub fn config(&self) -> &'a Config {
+        self.state.prepared.config
+    }
+
+    /// Waits for the interrupt and reads the frame into `buffer`, returning the sensor ready for
+    /// the next measurement
+    ///
+    /// On failure the sensor is handed back still measuring, as the frame was not read. After an
+    /// [`InterruptTimeout`](SensorError::InterruptTimeout) call `read` again. After a
+    /// [`ReadFailed`](SensorError::ReadFailed) the sensor is in an unknown state, so drop it,
+    /// reset the sensor through its enable pin and create it again.
+    #[allow(clippy::result_large_err)]
+    pub fn read(
+        self,
+        buffer: &mut [u64],
+        wait_for_interrupt: impl FnOnce() -> bool,
+    ) -> Result<Sensor<Prepared<'a>>, SensorFailure<Measuring<'a>>> {
+        if !wait_for_interrupt() {
+            return Err(self.fail(SensorError::InterruptTimeout, |state| state));
+        }
+        let read = unsafe {
+            acc_sensor_read(
+                self.inner.as_ptr(),
+                buffer.as_mut_ptr().cast(),
+                byte_size(buffer),
+            )
+        };
+        if !read {
+            return Err(self.fail(SensorError::ReadFailed, |state| state));
+        }
+        Ok(self.into_state(|state| state.prepared))
+    }
+}
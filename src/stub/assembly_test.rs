@@ -0,0 +1,184 @@
+//! Stubbed assembly test, the Rust counterpart of `acc_stub_assembly_test.c`
+
+use super::faults::{assembly_test_fails, sensor_disconnected};
+use super::hal;
+use crate::*;
+use core::ffi::{c_void, CStr};
+
+/// One more than the last test id
+const NUM_TESTS: usize =
+    acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_SENSOR_CALIBRATION as usize + 1;
+
+const TEST_NAMES: [&CStr; NUM_TESTS] = [
+    c"basic_read",
+    c"communication",
+    c"enable_pin",
+    c"interrupt",
+    c"clock_and_supply",
+    c"sensor_calibration",
+];
+
+struct AssemblyTest {
+    sensor_id: acc_sensor_id_t,
+    enabled: [bool; NUM_TESTS],
+    /// Test run by the next call to execute, `NUM_TESTS` once every test has run
+    current_test: usize,
+    /// COMPLETE was returned, the next call to execute starts over
+    complete: bool,
+    /// The current test handed the pin toggle or interrupt wait to the application
+    waiting: bool,
+    results: [acc_rss_assembly_test_result_t; NUM_TESTS],
+    num_results: u16,
+}
+
+impl AssemblyTest {
+    fn finish_test(&mut self, passed: bool) {
+        let test_id = self.current_test as acc_rss_assembly_test_test_id_t;
+        self.results[usize::from(self.num_results)] = acc_rss_assembly_test_result_t {
+            test_name: TEST_NAMES[self.current_test].as_ptr(),
+            test_result: passed && !assembly_test_fails(test_id),
+        };
+        self.num_results += 1;
+        self.current_test += 1;
+        self.waiting = false;
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_create(
+    sensor_id: acc_sensor_id_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> *mut acc_rss_assembly_test_t {
+    if buffer.is_null() || buffer_size < ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE {
+        return core::ptr::null_mut();
+    }
+
+    let assembly_test: *mut acc_rss_assembly_test_t = hal::alloc(AssemblyTest {
+        sensor_id,
+        enabled: [false; NUM_TESTS],
+        current_test: 0,
+        complete: false,
+        waiting: false,
+        results: [acc_rss_assembly_test_result_t {
+            test_name: core::ptr::null(),
+            test_result: false,
+        }; NUM_TESTS],
+        num_results: 0,
+    })
+    .cast();
+    if assembly_test.is_null() {
+        return assembly_test;
+    }
+
+    acc_rss_assembly_test_enable_all_tests(assembly_test);
+    assembly_test
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_destroy(
+    assembly_test: *mut acc_rss_assembly_test_t,
+) {
+    hal::free(assembly_test.cast::<AssemblyTest>());
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_enable_all_tests(
+    assembly_test: *mut acc_rss_assembly_test_t,
+) {
+    if let Some(assembly_test) = assembly_test.cast::<AssemblyTest>().as_mut() {
+        assembly_test.enabled = [true; NUM_TESTS];
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_disable_all_tests(
+    assembly_test: *mut acc_rss_assembly_test_t,
+) {
+    if let Some(assembly_test) = assembly_test.cast::<AssemblyTest>().as_mut() {
+        assembly_test.enabled = [false; NUM_TESTS];
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_enable(
+    assembly_test: *mut acc_rss_assembly_test_t,
+    test_id: acc_rss_assembly_test_test_id_t,
+) {
+    if let Some(assembly_test) = assembly_test.cast::<AssemblyTest>().as_mut() {
+        if let Some(enabled) = assembly_test.enabled.get_mut(test_id as usize) {
+            *enabled = true;
+        }
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_disable(
+    assembly_test: *mut acc_rss_assembly_test_t,
+    test_id: acc_rss_assembly_test_test_id_t,
+) {
+    if let Some(assembly_test) = assembly_test.cast::<AssemblyTest>().as_mut() {
+        if let Some(enabled) = assembly_test.enabled.get_mut(test_id as usize) {
+            *enabled = false;
+        }
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_execute(
+    assembly_test: *mut acc_rss_assembly_test_t,
+    integration_status: acc_rss_test_integration_status_t,
+) -> acc_rss_test_state_t {
+    let Some(assembly_test) = assembly_test.cast::<AssemblyTest>().as_mut() else {
+        return acc_rss_test_state_t_ACC_RSS_TEST_STATE_COMPLETE;
+    };
+
+    if assembly_test.complete {
+        assembly_test.complete = false;
+        assembly_test.current_test = 0;
+        assembly_test.num_results = 0;
+    }
+
+    while assembly_test.current_test < NUM_TESTS
+        && !assembly_test.enabled[assembly_test.current_test]
+    {
+        assembly_test.current_test += 1;
+    }
+    if assembly_test.current_test >= NUM_TESTS {
+        assembly_test.complete = true;
+        return acc_rss_test_state_t_ACC_RSS_TEST_STATE_COMPLETE;
+    }
+
+    let connected = !sensor_disconnected(assembly_test.sensor_id);
+    match assembly_test.current_test as acc_rss_assembly_test_test_id_t {
+        acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_BASIC_READ
+        | acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_COMMUNICATION => {
+            assembly_test.finish_test(connected);
+        }
+        acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_ENABLE_PIN => {
+            if !assembly_test.waiting {
+                assembly_test.waiting = true;
+                return acc_rss_test_state_t_ACC_RSS_TEST_STATE_TOGGLE_ENABLE_PIN;
+            }
+            assembly_test.finish_test(true);
+        }
+        acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT => {
+            if !assembly_test.waiting {
+                assembly_test.waiting = true;
+                return acc_rss_test_state_t_ACC_RSS_TEST_STATE_WAIT_FOR_INTERRUPT;
+            }
+            assembly_test.finish_test(
+                integration_status
+                    == acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_OK,
+            );
+        }
+        _ => assembly_test.finish_test(true),
+    }
+
+    acc_rss_test_state_t_ACC_RSS_TEST_STATE_ONGOING
+}
+
+pub(super) unsafe fn model_acc_rss_assembly_test_get_results(
+    assembly_test: *const acc_rss_assembly_test_t,
+    nbr_of_test_results: *mut u16,
+) -> *const acc_rss_assembly_test_result_t {
+    let assembly_test = assembly_test.cast::<AssemblyTest>().as_ref();
+    if let Some(nbr_of_test_results) = nbr_of_test_results.as_mut() {
+        *nbr_of_test_results = assembly_test.map_or(0, |a| a.num_results);
+    }
+
+    assembly_test.map_or(core::ptr::null(), |a| a.results.as_ptr())
+}
@@ -0,0 +1,247 @@
+//! Stubbed distance detector, the Rust counterpart of `acc_stub_distance.c`
+
+use super::hal;
+use super::processing::FrameLayout;
+use crate::*;
+use core::ffi::c_void;
+
+struct DistanceHandle {
+    num_points: u16,
+    // The sensor config the detector would derive from its own config
+    sensor_config: *mut acc_config_t,
+}
+
+impl DistanceHandle {
+    unsafe fn required_buffer_size(&self) -> u32 {
+        FrameLayout::from_config(self.sensor_config).buffer_size()
+    }
+
+    /// A threshold per point
+    fn required_cal_result_static_size(&self) -> u32 {
+        u32::from(self.num_points) * size_of::<f32>() as u32
+    }
+}
+
+/// Step lengths below 24 the RSS accepts, the divisors of 24
+const VALID_STEP_LENGTHS: [u16; 7] = [1, 2, 3, 4, 6, 8, 12];
+
+/// Step length the detector measures with for a profile, about a quarter of the pulse width
+fn profile_step_length(profile: acc_config_profile_t) -> u16 {
+    match profile {
+        acc_config_profile_t_ACC_CONFIG_PROFILE_1 => 1,
+        acc_config_profile_t_ACC_CONFIG_PROFILE_2 => 2,
+        acc_config_profile_t_ACC_CONFIG_PROFILE_3 => 6,
+        _ => 12,
+    }
+}
+
+/// Longest step length the RSS accepts that is not above `limit`, which is at least 1
+fn valid_step_length_up_to(limit: u16) -> u16 {
+    VALID_STEP_LENGTHS
+        .into_iter()
+        .filter(|&step_length| step_length <= limit)
+        .last()
+        .unwrap_or(VALID_STEP_LENGTHS[0])
+}
+
+pub(super) unsafe fn model_acc_detector_distance_create(
+    config: *const acc_detector_distance_config_t,
+) -> *mut acc_detector_distance_handle_t {
+    if config.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    let profile = acc_detector_distance_config_max_profile_get(config);
+    let mut step_length = profile_step_length(profile);
+    let max_step_length = acc_detector_distance_config_max_step_length_get(config);
+    if max_step_length > 0 && max_step_length < step_length {
+        step_length = valid_step_length_up_to(max_step_length);
+    }
+    let start_point =
+        acc_processing_meter_to_points(acc_detector_distance_config_start_get(config));
+    let end_point = acc_processing_meter_to_points(acc_detector_distance_config_end_get(config));
+    let num_points = if end_point >= start_point {
+        (end_point - start_point) / i32::from(step_length) + 1
+    } else {
+        1
+    };
+    let Ok(num_points) = u16::try_from(num_points) else {
+        hal::log(
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"distance",
+            &format!(
+                "Range of {} points does not fit a sensor config",
+                num_points
+            ),
+        );
+        return core::ptr::null_mut();
+    };
+
+    let sensor_config = acc_config_create();
+    if sensor_config.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    acc_config_start_point_set(sensor_config, start_point);
+    acc_config_num_points_set(sensor_config, num_points);
+    acc_config_step_length_set(sensor_config, step_length);
+    acc_config_profile_set(sensor_config, profile);
+    let handle = hal::alloc(DistanceHandle {
+        num_points,
+        sensor_config,
+    });
+    if handle.is_null() {
+        acc_config_destroy(sensor_config);
+    }
+    handle.cast()
+}
+
+pub(super) unsafe fn model_acc_detector_distance_destroy(
+    handle: *mut acc_detector_distance_handle_t,
+) {
+    let handle = handle.cast::<DistanceHandle>();
+    if handle.is_null() {
+        return;
+    }
+
+    acc_config_destroy((*handle).sensor_config);
+    hal::free(handle);
+}
+
+pub(super) unsafe fn model_acc_detector_distance_get_sizes(
+    handle: *const acc_detector_distance_handle_t,
+    buffer_size: *mut u32,
+    detector_cal_result_static_size: *mut u32,
+) -> bool {
+    let (Some(handle), Some(buffer_size), Some(detector_cal_result_static_size)) = (
+        handle.cast::<DistanceHandle>().as_ref(),
+        buffer_size.as_mut(),
+        detector_cal_result_static_size.as_mut(),
+    ) else {
+        return false;
+    };
+
+    *buffer_size = handle.required_buffer_size();
+    *detector_cal_result_static_size = handle.required_cal_result_static_size();
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_distance_calibrate(
+    sensor: *mut acc_sensor_t,
+    handle: *mut acc_detector_distance_handle_t,
+    sensor_cal_result: *const acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+    detector_cal_result_static: *mut u8,
+    detector_cal_result_static_size: u32,
+    detector_cal_result_dynamic: *mut acc_detector_cal_result_dynamic_t,
+    calibration_complete: *mut bool,
+) -> bool {
+    let (Some(handle), Some(calibration_complete)) = (
+        handle.cast::<DistanceHandle>().as_ref(),
+        calibration_complete.as_mut(),
+    ) else {
+        return false;
+    };
+    if sensor.is_null()
+        || buffer.is_null()
+        || detector_cal_result_static.is_null()
+        || detector_cal_result_dynamic.is_null()
+        || !acc_sensor_validate_calibration(sensor_cal_result)
+    {
+        return false;
+    }
+    if buffer_size < handle.required_buffer_size()
+        || detector_cal_result_static_size < handle.required_cal_result_static_size()
+    {
+        return false;
+    }
+
+    detector_cal_result_static.write_bytes(0, handle.required_cal_result_static_size() as usize);
+    detector_cal_result_dynamic.write(core::mem::zeroed());
+    *calibration_complete = true;
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_distance_update_calibration(
+    sensor: *mut acc_sensor_t,
+    handle: *mut acc_detector_distance_handle_t,
+    sensor_cal_result: *const acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+    detector_cal_result_dynamic: *mut acc_detector_cal_result_dynamic_t,
+    calibration_complete: *mut bool,
+) -> bool {
+    let (Some(handle), Some(calibration_complete)) = (
+        handle.cast::<DistanceHandle>().as_ref(),
+        calibration_complete.as_mut(),
+    ) else {
+        return false;
+    };
+    if sensor.is_null()
+        || buffer.is_null()
+        || detector_cal_result_dynamic.is_null()
+        || !acc_sensor_validate_calibration(sensor_cal_result)
+    {
+        return false;
+    }
+    if buffer_size < handle.required_buffer_size() {
+        return false;
+    }
+
+    detector_cal_result_dynamic.write(core::mem::zeroed());
+    *calibration_complete = true;
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_distance_prepare(
+    handle: *const acc_detector_distance_handle_t,
+    config: *const acc_detector_distance_config_t,
+    sensor: *mut acc_sensor_t,
+    sensor_cal_result: *const acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> bool {
+    let Some(handle) = handle.cast::<DistanceHandle>().as_ref() else {
+        return false;
+    };
+    if config.is_null() {
+        return false;
+    }
+
+    acc_sensor_prepare(
+        sensor,
+        handle.sensor_config,
+        sensor_cal_result,
+        buffer,
+        buffer_size,
+    )
+}
+
+pub(super) unsafe fn model_acc_detector_distance_config_log(
+    handle: *const acc_detector_distance_handle_t,
+    config: *const acc_detector_distance_config_t,
+) {
+    if config.is_null() {
+        return;
+    }
+
+    hal::log(
+        acc_log_level_t_ACC_LOG_LEVEL_INFO,
+        c"distance",
+        &format!(
+            "start: {:.3} m, end: {:.3} m, max_profile: {}, max_step_length: {}",
+            acc_detector_distance_config_start_get(config),
+            acc_detector_distance_config_end_get(config),
+            acc_detector_distance_config_max_profile_get(config),
+            acc_detector_distance_config_max_step_length_get(config)
+        ),
+    );
+    if let Some(handle) = handle.cast::<DistanceHandle>().as_ref() {
+        hal::log(
+            acc_log_level_t_ACC_LOG_LEVEL_INFO,
+            c"distance",
+            &format!("num_points: {}", handle.num_points),
+        );
+    }
+}
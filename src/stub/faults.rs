@@ -0,0 +1,129 @@
+//! Fault injection, the Rust counterpart of `acc_stub_faults.c`
+
+use super::lock;
+use crate::*;
+use std::sync::Mutex;
+
+struct FrameFault {
+    first_frame: u32,
+    last_frame: u32,
+    flags: u32,
+}
+
+struct Faults {
+    sensor_create_fails: bool,
+    calibration_calls: u32,
+    calibration_fails: bool,
+    disconnected_sensors: u32,
+    frame_faults: Vec<FrameFault>,
+    failing_assembly_tests: u32,
+}
+
+const NO_FAULTS: Faults = Faults {
+    sensor_create_fails: false,
+    calibration_calls: 1,
+    calibration_fails: false,
+    disconnected_sensors: 0,
+    frame_faults: Vec::new(),
+    failing_assembly_tests: 0,
+};
+
+static FAULTS: Mutex<Faults> = Mutex::new(NO_FAULTS);
+
+#[no_mangle]
+pub extern "C" fn acc_stub_faults_clear() {
+    *lock(&FAULTS) = NO_FAULTS;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_fault_sensor_create(fail: bool) {
+    lock(&FAULTS).sensor_create_fails = fail;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_fault_calibration(calls_until_complete: u32, fail: bool) {
+    let mut faults = lock(&FAULTS);
+    faults.calibration_calls = calls_until_complete.max(1);
+    faults.calibration_fails = fail;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_fault_sensor_disconnected(
+    sensor_id: acc_sensor_id_t,
+    disconnected: bool,
+) {
+    if sensor_id >= 32 {
+        return;
+    }
+
+    let mut faults = lock(&FAULTS);
+    if disconnected {
+        faults.disconnected_sensors |= 1 << sensor_id;
+    } else {
+        faults.disconnected_sensors &= !(1 << sensor_id);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_fault_frame_flags(
+    first_frame: u32,
+    last_frame: u32,
+    flags: u32,
+) -> bool {
+    let mut faults = lock(&FAULTS);
+    if faults.frame_faults.len() >= ACC_STUB_MAX_FRAME_FAULTS as usize || last_frame < first_frame {
+        return false;
+    }
+
+    faults.frame_faults.push(FrameFault {
+        first_frame,
+        last_frame,
+        flags,
+    });
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_fault_assembly_test(
+    test_id: acc_rss_assembly_test_test_id_t,
+    fail: bool,
+) {
+    if test_id >= 32 {
+        return;
+    }
+
+    let mut faults = lock(&FAULTS);
+    if fail {
+        faults.failing_assembly_tests |= 1 << test_id;
+    } else {
+        faults.failing_assembly_tests &= !(1 << test_id);
+    }
+}
+
+pub(super) fn sensor_create_fails() -> bool {
+    lock(&FAULTS).sensor_create_fails
+}
+
+pub(super) fn sensor_disconnected(sensor_id: acc_sensor_id_t) -> bool {
+    sensor_id < 32 && lock(&FAULTS).disconnected_sensors & (1 << sensor_id) != 0
+}
+
+pub(super) fn calibration_calls() -> u32 {
+    lock(&FAULTS).calibration_calls
+}
+
+pub(super) fn calibration_fails() -> bool {
+    lock(&FAULTS).calibration_fails
+}
+
+pub(super) fn frame_flags(frame_index: u32) -> u32 {
+    lock(&FAULTS)
+        .frame_faults
+        .iter()
+        .filter(|fault| (fault.first_frame..=fault.last_frame).contains(&frame_index))
+        .fold(0, |flags, fault| flags | fault.flags)
+}
+
+pub(super) fn assembly_test_fails(test_id: acc_rss_assembly_test_test_id_t) -> bool {
+    test_id < 32 && lock(&FAULTS).failing_assembly_tests & (1 << test_id) != 0
+}
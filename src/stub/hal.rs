@@ -0,0 +1,161 @@
+//! Registered HAL, the Rust counterpart of `acc_stub_hal.c`
+
+use super::lock;
+use crate::*;
+use core::ffi::{c_void, CStr};
+use std::ffi::CString;
+use std::sync::Mutex;
+
+/// Every emulated SPI transfer starts with a header word holding the register address,
+/// with the top bit set for writes
+const SPI_WRITE_FLAG: u16 = 0x8000;
+const SPI_ADDRESS_MASK: u16 = 0x7FFF;
+
+static HAL: Mutex<Option<acc_hal_a121_t>> = Mutex::new(None);
+
+/// Copy of the registered HAL, so its functions are not called with the lock held
+fn registered_hal() -> Option<acc_hal_a121_t> {
+    *lock(&HAL)
+}
+
+pub(super) fn hal_registered() -> bool {
+    registered_hal().is_some()
+}
+
+/// Logs a line through the registered HAL, dropped before `acc_rss_hal_register`
+pub(super) fn log(level: acc_log_level_t, module: &CStr, message: &str) {
+    if let Some(hal) = registered_hal() {
+        log_to(&hal, level, module, message);
+    }
+}
+
+/// Calls the `log` function of `hal` with the line as the argument of a `"%s"` format, like the
+/// C stubs. Without one, as there is no `c_log_stub` with the Rust stubs, the line goes straight
+/// to the filter and sink.
+fn log_to(hal: &acc_hal_a121_t, level: acc_log_level_t, module: &CStr, message: &str) {
+    let Ok(message) = CString::new(message) else {
+        return;
+    };
+    match hal.log {
+        Some(log) => unsafe { log(level, module.as_ptr(), c"%s".as_ptr(), message.as_ptr()) },
+        None => crate::logging::stub_log(level, module, &message),
+    }
+}
+
+/// Moves `value` into memory from the registered HAL, null before `acc_rss_hal_register`
+pub(super) unsafe fn alloc<T>(value: T) -> *mut T {
+    let Some(mem_alloc) = registered_hal().and_then(|hal| hal.mem_alloc) else {
+        return core::ptr::null_mut();
+    };
+
+    let memory = mem_alloc(size_of::<T>()).cast::<T>();
+    if memory.is_null() {
+        log(
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"mem",
+            &format!("Out of memory, {} bytes requested", size_of::<T>()),
+        );
+        return memory;
+    }
+
+    debug_assert!(memory.is_aligned(), "mem_alloc returned misaligned memory");
+    memory.write(value);
+    memory
+}
+
+/// Drops a value moved into HAL memory by [`alloc`] and returns the memory
+pub(super) unsafe fn free<T>(memory: *mut T) {
+    if memory.is_null() {
+        return;
+    }
+
+    memory.drop_in_place();
+    if let Some(mem_free) = registered_hal().and_then(|hal| hal.mem_free) {
+        mem_free(memory.cast());
+    }
+}
+
+/// Emulates a burst access of `num_words` consecutive 16-bit registers through the registered
+/// HAL, using `transfer16` when set. The access is split into transfers fitting both the buffer
+/// and `max_spi_transfer_size`, each starting with a header word. A null buffer means a small
+/// internal one.
+pub(super) unsafe fn spi_burst(
+    sensor_id: acc_sensor_id_t,
+    mut address: u16,
+    write: bool,
+    mut num_words: u32,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) {
+    // Used when the caller has no buffer, sized to what every HAL has to handle
+    let mut scratch = [0u32; (ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED / 4) as usize];
+
+    let Some(hal) = registered_hal() else {
+        return;
+    };
+    let (words, buffer_size) = if buffer.is_null() || buffer_size < size_of_val(&scratch) as u32 {
+        (
+            scratch.as_mut_ptr().cast::<u16>(),
+            size_of_val(&scratch) as u32,
+        )
+    } else {
+        (buffer.cast::<u16>(), buffer_size)
+    };
+
+    let transfer_size = buffer_size.min(u32::from(hal.max_spi_transfer_size));
+    let max_payload = transfer_size / size_of::<u16>() as u32 - 1;
+    while num_words > 0 {
+        let payload = num_words.min(max_payload);
+        let header = if write { SPI_WRITE_FLAG } else { 0 } | (address & SPI_ADDRESS_MASK);
+        words.write(header);
+        for i in 1..=payload {
+            // Written registers get a recognizable value, read ones are filled in by the sensor
+            let value = if write {
+                address.wrapping_add(i as u16 - 1)
+            } else {
+                0
+            };
+            words.add(i as usize).write(value);
+        }
+
+        let length = payload as usize + 1;
+        if let Some(transfer16) = hal.optimization.transfer16 {
+            transfer16(sensor_id, words, length);
+        } else if let Some(transfer) = hal.transfer {
+            transfer(sensor_id, words.cast(), length * size_of::<u16>());
+        }
+
+        address = address.wrapping_add(payload as u16);
+        num_words -= payload;
+    }
+}
+
+pub(super) unsafe fn model_acc_rss_hal_register(hal: *const acc_hal_a121_t) -> bool {
+    let Some(hal) = hal.as_ref() else {
+        return false;
+    };
+    // Without the C log wrapper there is no `c_log_stub` to put in `log`, so it may be left out
+    if hal.mem_alloc.is_none() || hal.mem_free.is_none() || hal.transfer.is_none() {
+        return false;
+    }
+    if u32::from(hal.max_spi_transfer_size) < ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED {
+        log_to(
+            hal,
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"rss",
+            &format!(
+                "max_spi_transfer_size {} is below the required {}",
+                hal.max_spi_transfer_size, ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED
+            ),
+        );
+        return false;
+    }
+
+    *lock(&HAL) = Some(*hal);
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_hal_unregister() {
+    *lock(&HAL) = None;
+}
@@ -0,0 +1,48 @@
+//! Pure Rust stub backend, selected with the `rust_stubs` feature.
+//!
+//! The SDK functions are generated from the same header parse as the C stubs and forward to
+//! the models in this module where one exists, so both backends behave the same. Like the C
+//! models, the state is global and shared by every thread.
+//!
+//! Nothing is compiled with a C compiler, so `cargo test` only needs rustc and libclang, on
+//! stable, and `cargo miri test` checks the stubs too. The stubs log through the HAL's `log`
+//! function like the C ones, or through `logging::stub_log` for HALs leaving it `None`, as there
+//! is no C log wrapper to put there. The `parity` test checks every model of both backends
+//! against the same golden frames, outputs and call traces.
+
+// The generated functions mirror the SDK signatures
+#![allow(clippy::too_many_arguments)]
+
+use crate::*;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+mod assembly_test;
+#[cfg(feature = "distance")]
+mod distance;
+mod faults;
+mod hal;
+#[cfg(feature = "presence")]
+mod presence;
+#[cfg(feature = "presence")]
+mod presence_processing;
+mod processing;
+mod sensor;
+mod trace;
+
+use assembly_test::*;
+#[cfg(feature = "distance")]
+use distance::*;
+use hal::*;
+#[cfg(feature = "presence")]
+use presence::*;
+#[cfg(feature = "presence")]
+use presence_processing::*;
+use processing::*;
+use sensor::*;
+
+include!(concat!(env!("OUT_DIR"), "/stubs.rs"));
+
+/// Locks the state of a model, which stays usable after a test panicked while holding it
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,342 @@
+//! Stubbed presence detector, the Rust counterpart of `acc_stub_presence.c`
+
+use super::processing::FrameLayout;
+use super::{hal, lock};
+use crate::*;
+use core::ffi::c_void;
+use std::sync::Mutex;
+
+/// Spread of the synthesized depthwise score peak
+const DEPTHWISE_PEAK_WIDTH_M: f32 = 0.1;
+
+struct PresenceHandle {
+    start_m: f32,
+    step_length_m: f32,
+    num_points: u16,
+    reset_filters_on_prepare: bool,
+    sensor_config: *mut acc_config_t,
+    processing: *mut acc_processing_t,
+    // Boxed separately, the detector metadata points to it
+    processing_metadata: *mut acc_processing_metadata_t,
+    frame_index: u32,
+    depthwise_intra_presence_scores: Vec<f32>,
+    depthwise_inter_presence_scores: Vec<f32>,
+}
+
+fn fill_depthwise(
+    scores: &mut [f32],
+    start_m: f32,
+    step_length_m: f32,
+    score: f32,
+    scripted: &[f32],
+    distance: f32,
+) {
+    for (i, value) in scores.iter_mut().enumerate() {
+        *value = if scripted.is_empty() {
+            let offset = (start_m + i as f32 * step_length_m - distance) / DEPTHWISE_PEAK_WIDTH_M;
+            score * (-0.5 * offset * offset).exp()
+        } else {
+            scripted.get(i).copied().unwrap_or(0.0)
+        };
+    }
+}
+
+/// Scripted segments, the depthwise arrays they point to are owned by the caller
+struct Timeline(Vec<acc_stub_presence_segment_t>);
+
+// The segments are plain data, their pointers are only read by the detector
+unsafe impl Send for Timeline {}
+
+static TIMELINE: Mutex<Timeline> = Mutex::new(Timeline(Vec::new()));
+
+#[no_mangle]
+pub extern "C" fn acc_stub_presence_timeline_clear() {
+    lock(&TIMELINE).0.clear();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn acc_stub_presence_timeline_add(
+    segment: *const acc_stub_presence_segment_t,
+) -> bool {
+    let Some(segment) = segment.as_ref() else {
+        return false;
+    };
+    let mut timeline = lock(&TIMELINE);
+    if timeline.0.len() >= ACC_STUB_PRESENCE_MAX_SEGMENTS as usize
+        || segment.last_frame < segment.first_frame
+    {
+        return false;
+    }
+
+    timeline.0.push(*segment);
+    true
+}
+
+/// The segment reported at `frame_index`, the one added last wins
+fn find_segment(frame_index: u32) -> Option<acc_stub_presence_segment_t> {
+    lock(&TIMELINE)
+        .0
+        .iter()
+        .rev()
+        .find(|segment| (segment.first_frame..=segment.last_frame).contains(&frame_index))
+        .copied()
+}
+
+/// Scripted depthwise scores of a segment, empty when the peak should be synthesized
+unsafe fn scripted_scores<'a>(scores: *const f32, length: u32) -> &'a [f32] {
+    if scores.is_null() {
+        &[]
+    } else {
+        core::slice::from_raw_parts(scores, length as usize)
+    }
+}
+
+pub(super) unsafe fn model_acc_detector_presence_create(
+    presence_config: *mut acc_detector_presence_config_t,
+    metadata: *mut acc_detector_presence_metadata_t,
+) -> *mut acc_detector_presence_handle_t {
+    if presence_config.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    let start_m = acc_detector_presence_config_start_get(presence_config);
+    let end_m = acc_detector_presence_config_end_get(presence_config);
+    let step_length = acc_detector_presence_config_step_length_get(presence_config).max(1);
+    let start_point = acc_processing_meter_to_points(start_m);
+    let end_point = acc_processing_meter_to_points(end_m);
+    let num_points = if end_point >= start_point {
+        (end_point - start_point) / i32::from(step_length) + 1
+    } else {
+        1
+    } as u16;
+
+    let handle = hal::alloc(PresenceHandle {
+        start_m: acc_processing_points_to_meter(start_point),
+        step_length_m: acc_processing_points_to_meter(i32::from(step_length)),
+        num_points,
+        reset_filters_on_prepare: acc_detector_presence_config_reset_filters_on_prepare_get(
+            presence_config,
+        ),
+        sensor_config: core::ptr::null_mut(),
+        processing: core::ptr::null_mut(),
+        processing_metadata: Box::into_raw(Box::new(core::mem::zeroed())),
+        frame_index: 0,
+        depthwise_intra_presence_scores: vec![0.0; num_points as usize],
+        depthwise_inter_presence_scores: vec![0.0; num_points as usize],
+    });
+    if handle.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    // The sensor config the detector would derive from its own config
+    (*handle).sensor_config = acc_config_create();
+    let sensor_config = (*handle).sensor_config;
+    if sensor_config.is_null() {
+        acc_detector_presence_destroy(handle.cast());
+        return core::ptr::null_mut();
+    }
+
+    acc_config_start_point_set(sensor_config, start_point);
+    acc_config_num_points_set(sensor_config, num_points);
+    acc_config_step_length_set(sensor_config, step_length);
+    acc_config_profile_set(
+        sensor_config,
+        acc_detector_presence_config_profile_get(presence_config),
+    );
+    acc_config_hwaas_set(
+        sensor_config,
+        acc_detector_presence_config_hwaas_get(presence_config),
+    );
+    acc_config_sweeps_per_frame_set(
+        sensor_config,
+        acc_detector_presence_config_sweeps_per_frame_get(presence_config),
+    );
+    if !acc_detector_presence_config_frame_rate_app_driven_get(presence_config) {
+        acc_config_frame_rate_set(
+            sensor_config,
+            acc_detector_presence_config_frame_rate_get(presence_config),
+        );
+    }
+    acc_config_inter_frame_idle_state_set(
+        sensor_config,
+        acc_detector_presence_config_inter_frame_idle_state_get(presence_config),
+    );
+
+    let processing_metadata = (*handle).processing_metadata;
+    (*handle).processing = acc_processing_create(sensor_config, processing_metadata);
+    if (*handle).processing.is_null() {
+        acc_detector_presence_destroy(handle.cast());
+        return core::ptr::null_mut();
+    }
+
+    if let Some(metadata) = metadata.as_mut() {
+        let handle = &*handle;
+        metadata.start_m = handle.start_m;
+        metadata.end_m = handle.start_m + f32::from(num_points - 1) * handle.step_length_m;
+        metadata.step_length_m = handle.step_length_m;
+        metadata.num_points = num_points;
+        metadata.profile = acc_config_profile_get(sensor_config);
+        metadata.sensor_config = sensor_config;
+        metadata.processing_metadata = processing_metadata;
+    }
+
+    handle.cast()
+}
+
+impl PresenceHandle {
+    /// Frame data of the sensor config plus the depthwise score arrays of the result
+    unsafe fn required_buffer_size(&self) -> u32 {
+        FrameLayout::from_config(self.sensor_config).buffer_size()
+            + 2 * u32::from(self.num_points) * size_of::<f32>() as u32
+    }
+}
+
+pub(super) unsafe fn model_acc_detector_presence_get_buffer_size(
+    presence_handle: *const acc_detector_presence_handle_t,
+    buffer_size: *mut u32,
+) -> bool {
+    let (Some(handle), Some(buffer_size)) = (
+        presence_handle.cast::<PresenceHandle>().as_ref(),
+        buffer_size.as_mut(),
+    ) else {
+        return false;
+    };
+
+    *buffer_size = handle.required_buffer_size();
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_presence_destroy(
+    presence_handle: *mut acc_detector_presence_handle_t,
+) {
+    let handle = presence_handle.cast::<PresenceHandle>();
+    if handle.is_null() {
+        return;
+    }
+
+    acc_processing_destroy((*handle).processing);
+    acc_config_destroy((*handle).sensor_config);
+    drop(Box::from_raw((*handle).processing_metadata));
+    hal::free(handle);
+}
+
+pub(super) unsafe fn model_acc_detector_presence_prepare(
+    presence_handle: *const acc_detector_presence_handle_t,
+    presence_config: *mut acc_detector_presence_config_t,
+    sensor: *mut acc_sensor_t,
+    cal_result: *const acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> bool {
+    // The handle was allocated mutable, the SDK signature only promises not to reconfigure it
+    let handle = presence_handle.cast::<PresenceHandle>().cast_mut();
+    if handle.is_null()
+        || presence_config.is_null()
+        || buffer_size < (*handle).required_buffer_size()
+    {
+        return false;
+    }
+    if !acc_sensor_prepare(
+        sensor,
+        (*handle).sensor_config,
+        cal_result,
+        buffer,
+        buffer_size,
+    ) {
+        return false;
+    }
+
+    if (*handle).reset_filters_on_prepare {
+        (*handle).frame_index = 0;
+    }
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_presence_process(
+    presence_handle: *mut acc_detector_presence_handle_t,
+    buffer: *mut c_void,
+    result: *mut acc_detector_presence_result_t,
+) -> bool {
+    let handle = presence_handle.cast::<PresenceHandle>();
+    if handle.is_null() || buffer.is_null() || result.is_null() {
+        return false;
+    }
+
+    result.write(core::mem::zeroed());
+    acc_processing_execute(
+        (*handle).processing,
+        buffer,
+        &raw mut (*result).processing_result,
+    );
+
+    let handle = &mut *handle;
+    let result = &mut *result;
+    if let Some(segment) = find_segment(handle.frame_index) {
+        result.presence_detected = segment.presence_detected;
+        result.intra_presence_score = segment.intra_presence_score;
+        result.inter_presence_score = segment.inter_presence_score;
+        result.presence_distance = segment.presence_distance;
+        fill_depthwise(
+            &mut handle.depthwise_intra_presence_scores,
+            handle.start_m,
+            handle.step_length_m,
+            segment.intra_presence_score,
+            scripted_scores(
+                segment.depthwise_intra_presence_scores,
+                segment.depthwise_presence_scores_length,
+            ),
+            segment.presence_distance,
+        );
+        fill_depthwise(
+            &mut handle.depthwise_inter_presence_scores,
+            handle.start_m,
+            handle.step_length_m,
+            segment.inter_presence_score,
+            scripted_scores(
+                segment.depthwise_inter_presence_scores,
+                segment.depthwise_presence_scores_length,
+            ),
+            segment.presence_distance,
+        );
+    } else {
+        handle.depthwise_intra_presence_scores.fill(0.0);
+        handle.depthwise_inter_presence_scores.fill(0.0);
+    }
+
+    result.depthwise_intra_presence_scores = handle.depthwise_intra_presence_scores.as_mut_ptr();
+    result.depthwise_inter_presence_scores = handle.depthwise_inter_presence_scores.as_mut_ptr();
+    result.depthwise_presence_scores_length = u32::from(handle.num_points);
+    handle.frame_index += 1;
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_presence_get_distance_m(
+    presence_handle: *const acc_detector_presence_handle_t,
+    point_idx: u16,
+) -> f32 {
+    let Some(handle) = presence_handle.cast::<PresenceHandle>().as_ref() else {
+        return 0.0;
+    };
+
+    handle.start_m + f32::from(point_idx) * handle.step_length_m
+}
+
+pub(super) unsafe fn model_acc_detector_presence_config_log(
+    presence_config: *mut acc_detector_presence_config_t,
+) {
+    if presence_config.is_null() {
+        return;
+    }
+
+    hal::log(
+        acc_log_level_t_ACC_LOG_LEVEL_INFO,
+        c"presence",
+        &format!(
+            "start: {:.3} m, end: {:.3} m, step_length: {}, frame_rate: {:.3} Hz",
+            acc_detector_presence_config_start_get(presence_config),
+            acc_detector_presence_config_end_get(presence_config),
+            acc_detector_presence_config_step_length_get(presence_config),
+            acc_detector_presence_config_frame_rate_get(presence_config)
+        ),
+    );
+}
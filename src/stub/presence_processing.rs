@@ -0,0 +1,285 @@
+//! Stubbed presence processing, the Rust counterpart of `acc_stub_presence_processing.c`
+
+use super::hal;
+use super::processing::FrameLayout;
+use crate::*;
+use core::f32::consts::PI;
+use core::ffi::c_void;
+
+/// Working memory asked for per point, the filter state is kept in the handle instead
+const BUFFER_BYTES_PER_POINT: u32 = 8 * size_of::<f32>() as u32;
+/// Smallest noise level the scores are normalized by, the IQ data is integer
+const MIN_NOISE_LEVEL: f32 = 1.0;
+
+struct PresenceProcessingHandle {
+    layout: FrameLayout,
+    sweep_length: u16,
+    frame_rate: f32,
+    intra_detection: bool,
+    intra_detection_threshold: f32,
+    inter_detection: bool,
+    inter_detection_threshold: f32,
+    inter_frame_deviation_time_const: f32,
+    inter_frame_fast_cutoff: f32,
+    inter_frame_slow_cutoff: f32,
+    intra_frame_time_const: f32,
+    intra_output_time_const: f32,
+    inter_output_time_const: f32,
+    filters_initialized: bool,
+    intra_presence_score: f32,
+    inter_presence_score: f32,
+    // Per point filter state, sweep_length long
+    fast_real: Vec<f32>,
+    fast_imag: Vec<f32>,
+    slow_real: Vec<f32>,
+    slow_imag: Vec<f32>,
+    depthwise_intra_presence_scores: Vec<f32>,
+    depthwise_inter_presence_scores: Vec<f32>,
+}
+
+/// Smoothing factor of a first order low pass filter with the given time constant
+fn time_const_alpha(time_const: f32, frame_rate: f32) -> f32 {
+    if time_const <= 0.0 || frame_rate <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time_const * frame_rate)).exp()
+}
+
+/// Smoothing factor of a first order low pass filter with the given cutoff frequency
+fn cutoff_alpha(cutoff: f32, frame_rate: f32) -> f32 {
+    if cutoff <= 0.0 || frame_rate <= 0.0 {
+        return 1.0;
+    }
+    // No filtering above the Nyquist limit
+    if cutoff >= frame_rate / 2.0 {
+        return 0.0;
+    }
+    (-2.0 * PI * cutoff / frame_rate).exp()
+}
+
+fn low_pass(previous: f32, value: f32, alpha: f32) -> f32 {
+    alpha * previous + (1.0 - alpha) * value
+}
+
+/// Index of the first largest score
+fn peak(scores: &[f32]) -> usize {
+    let mut peak = 0;
+    for (i, score) in scores.iter().enumerate() {
+        if *score > scores[peak] {
+            peak = i;
+        }
+    }
+    peak
+}
+
+pub(super) unsafe fn model_acc_detector_presence_processing_get_buffer_size(
+    sensor_config: *const acc_config_t,
+) -> u32 {
+    if sensor_config.is_null() {
+        return 0;
+    }
+
+    u32::from(FrameLayout::from_config(sensor_config).sweep_length()) * BUFFER_BYTES_PER_POINT
+}
+
+pub(super) unsafe fn model_acc_detector_presence_processing_create(
+    processing_config: *const acc_detector_presence_processing_config_t,
+    sensor_config: *const acc_config_t,
+) -> *mut acc_detector_presence_processing_handle_t {
+    if processing_config.is_null() || sensor_config.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    let layout = FrameLayout::from_config(sensor_config);
+    let sweep_length = layout.sweep_length();
+    if sweep_length == 0 {
+        return core::ptr::null_mut();
+    }
+
+    let points = sweep_length as usize;
+    let handle = PresenceProcessingHandle {
+        layout,
+        sweep_length,
+        frame_rate: acc_detector_presence_processing_config_frame_rate_get(processing_config),
+        intra_detection: acc_detector_presence_processing_config_intra_detection_get(
+            processing_config,
+        ),
+        intra_detection_threshold:
+            acc_detector_presence_processing_config_intra_detection_threshold_get(processing_config),
+        inter_detection: acc_detector_presence_processing_config_inter_detection_get(
+            processing_config,
+        ),
+        inter_detection_threshold:
+            acc_detector_presence_processing_config_inter_detection_threshold_get(processing_config),
+        inter_frame_deviation_time_const:
+            acc_detector_presence_processing_config_inter_frame_deviation_time_const_get(
+                processing_config,
+            ),
+        inter_frame_fast_cutoff:
+            acc_detector_presence_processing_config_inter_frame_fast_cutoff_get(processing_config),
+        inter_frame_slow_cutoff:
+            acc_detector_presence_processing_config_inter_frame_slow_cutoff_get(processing_config),
+        intra_frame_time_const: acc_detector_presence_processing_config_intra_frame_time_const_get(
+            processing_config,
+        ),
+        intra_output_time_const:
+            acc_detector_presence_processing_config_intra_output_time_const_get(processing_config),
+        inter_output_time_const:
+            acc_detector_presence_processing_config_inter_output_time_const_get(processing_config),
+        filters_initialized: false,
+        intra_presence_score: 0.0,
+        inter_presence_score: 0.0,
+        fast_real: vec![0.0; points],
+        fast_imag: vec![0.0; points],
+        slow_real: vec![0.0; points],
+        slow_imag: vec![0.0; points],
+        depthwise_intra_presence_scores: vec![0.0; points],
+        depthwise_inter_presence_scores: vec![0.0; points],
+    };
+
+    hal::alloc(handle).cast()
+}
+
+pub(super) unsafe fn model_acc_detector_presence_processing_destroy(
+    processing_handle: *mut acc_detector_presence_processing_handle_t,
+) {
+    hal::free(processing_handle.cast::<PresenceProcessingHandle>());
+}
+
+pub(super) unsafe fn model_acc_detector_presence_processing_reset(
+    processing_handle: *mut acc_detector_presence_processing_handle_t,
+) -> bool {
+    let Some(handle) = processing_handle
+        .cast::<PresenceProcessingHandle>()
+        .as_mut()
+    else {
+        return false;
+    };
+
+    handle.filters_initialized = false;
+    handle.intra_presence_score = 0.0;
+    handle.inter_presence_score = 0.0;
+    handle.depthwise_intra_presence_scores.fill(0.0);
+    handle.depthwise_inter_presence_scores.fill(0.0);
+    true
+}
+
+pub(super) unsafe fn model_acc_detector_presence_processing_process(
+    processing_handle: *mut acc_detector_presence_processing_handle_t,
+    buffer: *mut c_void,
+    frame: *const acc_int16_complex_t,
+    presence_result: *mut acc_detector_presence_processing_result_t,
+) -> bool {
+    let handle = processing_handle.cast::<PresenceProcessingHandle>();
+    if handle.is_null() || buffer.is_null() || frame.is_null() || presence_result.is_null() {
+        return false;
+    }
+    let handle = &mut *handle;
+
+    let points = handle.sweep_length as usize;
+    let sweeps = handle.layout.sweeps_per_frame().max(1) as usize;
+    let frame = core::slice::from_raw_parts(frame, points * sweeps);
+    let intra_frame_alpha = time_const_alpha(handle.intra_frame_time_const, handle.frame_rate);
+    let deviation_alpha =
+        time_const_alpha(handle.inter_frame_deviation_time_const, handle.frame_rate);
+    let fast_alpha = cutoff_alpha(handle.inter_frame_fast_cutoff, handle.frame_rate);
+    let slow_alpha = cutoff_alpha(handle.inter_frame_slow_cutoff, handle.frame_rate);
+
+    // The mean sweep goes to the working buffer, followed by the spread of the sweeps around it
+    let work = core::slice::from_raw_parts_mut(buffer.cast::<f32>(), 4 * points);
+    let (mean_real, work) = work.split_at_mut(points);
+    let (mean_imag, work) = work.split_at_mut(points);
+    let (spread, sorted_spread) = work.split_at_mut(points);
+
+    for point in 0..points {
+        let samples = || frame.iter().skip(point).step_by(points);
+        mean_real[point] = samples().map(|s| f32::from(s.real)).sum::<f32>() / sweeps as f32;
+        mean_imag[point] = samples().map(|s| f32::from(s.imag)).sum::<f32>() / sweeps as f32;
+
+        let variance: f32 = samples()
+            .map(|s| {
+                let delta_real = f32::from(s.real) - mean_real[point];
+                let delta_imag = f32::from(s.imag) - mean_imag[point];
+                delta_real * delta_real + delta_imag * delta_imag
+            })
+            .sum();
+        spread[point] = (variance / sweeps as f32).sqrt();
+    }
+
+    // Presence only covers part of the range, the median spread tells the noise level
+    sorted_spread.copy_from_slice(spread);
+    sorted_spread.sort_unstable_by(f32::total_cmp);
+    let noise_level = sorted_spread[points / 2].max(MIN_NOISE_LEVEL);
+
+    if !handle.filters_initialized {
+        handle.fast_real.copy_from_slice(mean_real);
+        handle.fast_imag.copy_from_slice(mean_imag);
+        handle.slow_real.copy_from_slice(mean_real);
+        handle.slow_imag.copy_from_slice(mean_imag);
+        handle.filters_initialized = true;
+    }
+
+    for point in 0..points {
+        handle.fast_real[point] = low_pass(handle.fast_real[point], mean_real[point], fast_alpha);
+        handle.fast_imag[point] = low_pass(handle.fast_imag[point], mean_imag[point], fast_alpha);
+        handle.slow_real[point] = low_pass(handle.slow_real[point], mean_real[point], slow_alpha);
+        handle.slow_imag[point] = low_pass(handle.slow_imag[point], mean_imag[point], slow_alpha);
+
+        // Averaging the sweeps lowers the noise of the mean sweep
+        let deviation = (handle.fast_real[point] - handle.slow_real[point])
+            .hypot(handle.fast_imag[point] - handle.slow_imag[point])
+            * (sweeps as f32).sqrt()
+            / noise_level;
+
+        // Both scores count noise levels above the noise itself
+        handle.depthwise_intra_presence_scores[point] = low_pass(
+            handle.depthwise_intra_presence_scores[point],
+            spread[point] / noise_level - 1.0,
+            intra_frame_alpha,
+        );
+        handle.depthwise_inter_presence_scores[point] = low_pass(
+            handle.depthwise_inter_presence_scores[point],
+            deviation - 1.0,
+            deviation_alpha,
+        );
+    }
+
+    let intra_peak = peak(&handle.depthwise_intra_presence_scores);
+    let inter_peak = peak(&handle.depthwise_inter_presence_scores);
+    handle.intra_presence_score = low_pass(
+        handle.intra_presence_score,
+        handle.depthwise_intra_presence_scores[intra_peak],
+        time_const_alpha(handle.intra_output_time_const, handle.frame_rate),
+    );
+    handle.inter_presence_score = low_pass(
+        handle.inter_presence_score,
+        handle.depthwise_inter_presence_scores[inter_peak],
+        time_const_alpha(handle.inter_output_time_const, handle.frame_rate),
+    );
+
+    let intra_detected =
+        handle.intra_detection && handle.intra_presence_score > handle.intra_detection_threshold;
+    let inter_detected =
+        handle.inter_detection && handle.inter_presence_score > handle.inter_detection_threshold;
+
+    presence_result.write(acc_detector_presence_processing_result_t {
+        presence_detected: intra_detected || inter_detected,
+        intra_presence_score: handle.intra_presence_score,
+        inter_presence_score: handle.inter_presence_score,
+        presence_distance: 0.0,
+        depthwise_intra_presence_scores: handle.depthwise_intra_presence_scores.as_mut_ptr(),
+        depthwise_inter_presence_scores: handle.depthwise_inter_presence_scores.as_mut_ptr(),
+        depthwise_presence_scores_length: u32::from(handle.sweep_length),
+    });
+    if intra_detected || inter_detected {
+        // Fast motion is located more precisely by the intra-frame part
+        let peak = if intra_detected {
+            intra_peak
+        } else {
+            inter_peak
+        };
+        (*presence_result).presence_distance =
+            acc_processing_points_to_meter(handle.layout.point(peak as u16));
+    }
+    true
+}
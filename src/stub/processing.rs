@@ -0,0 +1,381 @@
+//! Synthetic scene and processing, the Rust counterpart of `acc_stub_processing.c`
+
+use super::faults::frame_flags;
+use super::sensor::last_frame_index;
+use super::{hal, lock};
+use crate::*;
+use core::f32::consts::PI;
+use core::ffi::c_void;
+use std::sync::Mutex;
+
+/// Distance between two A121 points
+const POINT_SPACING_M: f32 = 0.0025;
+/// Wavelength of the 60.5 GHz carrier
+const WAVELENGTH_M: f32 = 0.004955;
+/// Rough per-point measurement time and per-subsweep overhead used for the max sweep rate
+const SAMPLE_TIME_S: f32 = 0.5e-6;
+const SUBSWEEP_OVERHEAD_S: f32 = 20e-6;
+
+const MAX_SUBSWEEPS: usize = ACC_MAX_NUM_SUBSWEEPS as usize;
+
+#[derive(Clone, Copy)]
+struct Reflector {
+    distance_m: f32,
+    amplitude: f32,
+    radial_velocity_m_s: f32,
+}
+
+struct Scene {
+    reflectors: Vec<Reflector>,
+    noise_amplitude: f32,
+    noise_state: u32,
+    temperature: i16,
+}
+
+impl Scene {
+    /// xorshift32, mapped to [-1, 1]
+    fn next_noise(&mut self) -> f32 {
+        self.noise_state ^= self.noise_state << 13;
+        self.noise_state ^= self.noise_state >> 17;
+        self.noise_state ^= self.noise_state << 5;
+        self.noise_state as f32 / 2147483648.0 - 1.0
+    }
+}
+
+const EMPTY_SCENE: Scene = Scene {
+    reflectors: Vec::new(),
+    noise_amplitude: 0.0,
+    noise_state: 1,
+    temperature: ACC_STUB_DEFAULT_TEMPERATURE as i16,
+};
+
+static SCENE: Mutex<Scene> = Mutex::new(EMPTY_SCENE);
+
+/// Frame layout of a prepared config
+#[derive(Clone, Copy, Default)]
+pub(super) struct FrameLayout {
+    num_subsweeps: u8,
+    start_point: [i32; MAX_SUBSWEEPS],
+    num_points: [u16; MAX_SUBSWEEPS],
+    step_length: [u16; MAX_SUBSWEEPS],
+    hwaas: [u16; MAX_SUBSWEEPS],
+    profile: [acc_config_profile_t; MAX_SUBSWEEPS],
+    sweeps_per_frame: u16,
+    sweep_rate: f32,
+    frame_rate: f32,
+    continuous_sweep_mode: bool,
+    inter_sweep_idle_state: acc_config_idle_state_t,
+}
+
+impl FrameLayout {
+    pub(super) unsafe fn from_config(config: *const acc_config_t) -> Self {
+        // Same getter order as the C stubs, so both backends record the same trace
+        let mut layout = FrameLayout {
+            num_subsweeps: acc_config_num_subsweeps_get(config).min(MAX_SUBSWEEPS as u8),
+            ..Default::default()
+        };
+
+        for i in 0..layout.num_subsweeps {
+            let subsweep = i as usize;
+            layout.start_point[subsweep] = acc_config_subsweep_start_point_get(config, i);
+            layout.num_points[subsweep] = acc_config_subsweep_num_points_get(config, i);
+            layout.step_length[subsweep] = acc_config_subsweep_step_length_get(config, i);
+            layout.hwaas[subsweep] = acc_config_subsweep_hwaas_get(config, i);
+            layout.profile[subsweep] = acc_config_subsweep_profile_get(config, i);
+        }
+
+        layout.sweeps_per_frame = acc_config_sweeps_per_frame_get(config);
+        layout.sweep_rate = acc_config_sweep_rate_get(config);
+        layout.frame_rate = acc_config_frame_rate_get(config);
+        layout.continuous_sweep_mode = acc_config_continuous_sweep_mode_get(config);
+        layout.inter_sweep_idle_state = acc_config_inter_sweep_idle_state_get(config);
+        layout
+    }
+
+    fn subsweeps(&self) -> core::ops::Range<usize> {
+        0..self.num_subsweeps as usize
+    }
+
+    pub(super) fn sweep_length(&self) -> u16 {
+        self.subsweeps().map(|i| self.num_points[i]).sum()
+    }
+
+    pub(super) fn num_subsweeps(&self) -> u8 {
+        self.num_subsweeps
+    }
+
+    pub(super) fn sweeps_per_frame(&self) -> u16 {
+        self.sweeps_per_frame
+    }
+
+    /// Point of the sweep at `index`, walking the subsweeps it is made of
+    pub(super) fn point(&self, mut index: u16) -> i32 {
+        for i in self.subsweeps() {
+            if index < self.num_points[i] {
+                return self.start_point[i] + i32::from(index) * i32::from(self.step_length[i]);
+            }
+            index -= self.num_points[i];
+        }
+        0
+    }
+
+    pub(super) fn frame_length(&self) -> u32 {
+        u32::from(self.sweep_length()) * u32::from(self.sweeps_per_frame)
+    }
+
+    /// Working memory needed to measure with the layout, as reported by `acc_rss_get_buffer_size`
+    pub(super) fn buffer_size(&self) -> u32 {
+        self.frame_length() * size_of::<acc_int16_complex_t>() as u32 + ACC_STUB_BUFFER_OVERHEAD
+    }
+
+    fn high_speed_mode(&self) -> bool {
+        !self.continuous_sweep_mode
+            && self.inter_sweep_idle_state == acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY
+            && self.num_subsweeps == 1
+            && self.profile[0] >= acc_config_profile_t_ACC_CONFIG_PROFILE_3
+    }
+
+    fn max_sweep_rate(&self) -> f32 {
+        let mut sweep_time: f32 = self
+            .subsweeps()
+            .map(|i| {
+                SUBSWEEP_OVERHEAD_S
+                    + SAMPLE_TIME_S * f32::from(self.num_points[i]) * f32::from(self.hwaas[i])
+            })
+            .sum();
+        if self.high_speed_mode() {
+            sweep_time *= 0.5;
+        }
+        if sweep_time > 0.0 {
+            1.0 / sweep_time
+        } else {
+            0.0
+        }
+    }
+}
+
+fn profile_pulse_width_m(profile: acc_config_profile_t) -> f32 {
+    match profile {
+        acc_config_profile_t_ACC_CONFIG_PROFILE_1 => 0.015,
+        acc_config_profile_t_ACC_CONFIG_PROFILE_2 => 0.025,
+        acc_config_profile_t_ACC_CONFIG_PROFILE_3 => 0.05,
+        acc_config_profile_t_ACC_CONFIG_PROFILE_4 => 0.07,
+        _ => 0.12,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_scene_clear() {
+    *lock(&SCENE) = EMPTY_SCENE;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_scene_add_reflector(
+    distance_m: f32,
+    amplitude: f32,
+    radial_velocity_m_s: f32,
+) -> bool {
+    let mut scene = lock(&SCENE);
+    if scene.reflectors.len() >= ACC_STUB_SCENE_MAX_REFLECTORS as usize {
+        return false;
+    }
+
+    scene.reflectors.push(Reflector {
+        distance_m,
+        amplitude,
+        radial_velocity_m_s,
+    });
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_scene_set_noise(amplitude: f32) {
+    lock(&SCENE).noise_amplitude = amplitude;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_scene_set_temperature(temperature: i16) {
+    lock(&SCENE).temperature = temperature;
+}
+
+pub(super) fn scene_temperature() -> i16 {
+    lock(&SCENE).temperature
+}
+
+/// Fills `frame` with the sweeps the scene gives at `frame_index`
+pub(super) unsafe fn synthesize(
+    layout: &FrameLayout,
+    frame_index: u32,
+    frame: *mut acc_int16_complex_t,
+) {
+    let mut scene = lock(&SCENE);
+    let sweep_rate = if layout.sweep_rate > 0.0 {
+        layout.sweep_rate
+    } else {
+        layout.max_sweep_rate()
+    };
+    let sweep_period = if sweep_rate > 0.0 {
+        1.0 / sweep_rate
+    } else {
+        0.0
+    };
+    let frame_period = if layout.frame_rate > 0.0 {
+        1.0 / layout.frame_rate
+    } else {
+        sweep_period * f32::from(layout.sweeps_per_frame)
+    };
+
+    let mut point_data = frame;
+    for sweep in 0..layout.sweeps_per_frame {
+        let time = frame_index as f32 * frame_period + f32::from(sweep) * sweep_period;
+
+        for subsweep in layout.subsweeps() {
+            let width = profile_pulse_width_m(layout.profile[subsweep]);
+
+            for point in 0..layout.num_points[subsweep] {
+                let distance = (layout.start_point[subsweep]
+                    + i32::from(point) * i32::from(layout.step_length[subsweep]))
+                    as f32
+                    * POINT_SPACING_M;
+                let mut real = 0.0;
+                let mut imag = 0.0;
+
+                for reflector in &scene.reflectors {
+                    let range = reflector.distance_m + reflector.radial_velocity_m_s * time;
+                    let offset = (distance - range) / width;
+                    let envelope = reflector.amplitude * (-0.5 * offset * offset).exp();
+                    let phase = -4.0 * PI * range / WAVELENGTH_M;
+                    real += envelope * phase.cos();
+                    imag += envelope * phase.sin();
+                }
+
+                if scene.noise_amplitude > 0.0 {
+                    real += scene.noise_amplitude * scene.next_noise();
+                    imag += scene.noise_amplitude * scene.next_noise();
+                }
+
+                // Float to int casts saturate
+                point_data.write(acc_int16_complex_t {
+                    real: real.round() as i16,
+                    imag: imag.round() as i16,
+                });
+                point_data = point_data.add(1);
+            }
+        }
+    }
+}
+
+struct ProcessingHandle {
+    layout: FrameLayout,
+}
+
+pub(super) unsafe fn model_acc_processing_create(
+    config: *const acc_config_t,
+    processing_metadata: *mut acc_processing_metadata_t,
+) -> *mut acc_processing_t {
+    if config.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    let layout = FrameLayout::from_config(config);
+    let handle = hal::alloc(ProcessingHandle { layout });
+    if handle.is_null() {
+        return core::ptr::null_mut();
+    }
+
+    if let Some(metadata) = processing_metadata.as_mut() {
+        let mut offset = 0;
+        for i in 0..MAX_SUBSWEEPS {
+            let length = if i < layout.num_subsweeps as usize {
+                layout.num_points[i]
+            } else {
+                0
+            };
+            metadata.subsweep_data_offset[i] = offset;
+            metadata.subsweep_data_length[i] = length;
+            offset += length;
+        }
+        metadata.sweep_data_length = layout.sweep_length();
+        metadata.frame_data_length = layout.frame_length() as u16;
+        metadata.max_sweep_rate = layout.max_sweep_rate();
+        metadata.high_speed_mode = layout.high_speed_mode();
+    }
+
+    handle.cast()
+}
+
+pub(super) unsafe fn model_acc_processing_execute(
+    handle: *mut acc_processing_t,
+    buffer: *mut c_void,
+    result: *mut acc_processing_result_t,
+) {
+    let Some(result) = result.as_mut() else {
+        return;
+    };
+    if handle.is_null() {
+        return;
+    }
+
+    // The stubbed sensor reads frames already in processed layout
+    let flags = frame_flags(last_frame_index());
+    result.data_saturated = flags & ACC_STUB_FLAG_DATA_SATURATED != 0;
+    result.frame_delayed = flags & ACC_STUB_FLAG_FRAME_DELAYED != 0;
+    result.calibration_needed = flags & ACC_STUB_FLAG_CALIBRATION_NEEDED != 0;
+    result.temperature = scene_temperature();
+    result.frame = buffer.cast();
+}
+
+pub(super) unsafe fn model_acc_processing_destroy(handle: *mut acc_processing_t) {
+    hal::free(handle.cast::<ProcessingHandle>());
+}
+
+pub(super) fn model_acc_processing_points_to_meter(points: i32) -> f32 {
+    points as f32 * POINT_SPACING_M
+}
+
+pub(super) fn model_acc_processing_meter_to_points(length: f32) -> i32 {
+    (length / POINT_SPACING_M).round() as i32
+}
+
+pub(super) unsafe fn model_acc_rss_get_buffer_size(
+    config: *const acc_config_t,
+    buffer_size: *mut u32,
+) -> bool {
+    let Some(buffer_size) = buffer_size.as_mut() else {
+        return false;
+    };
+    if config.is_null() {
+        return false;
+    }
+
+    *buffer_size = FrameLayout::from_config(config).buffer_size();
+    true
+}
+
+pub(super) unsafe fn model_acc_config_log(config: *const acc_config_t) {
+    if config.is_null() {
+        return;
+    }
+
+    let layout = FrameLayout::from_config(config);
+    let info = |message: &str| hal::log(acc_log_level_t_ACC_LOG_LEVEL_INFO, c"config", message);
+    info(&format!(
+        "sweeps_per_frame: {}, num_subsweeps: {}",
+        layout.sweeps_per_frame, layout.num_subsweeps
+    ));
+    info(&format!(
+        "sweep_rate: {:.3} Hz, frame_rate: {:.3} Hz",
+        layout.sweep_rate, layout.frame_rate
+    ));
+    for i in 0..usize::from(layout.num_subsweeps) {
+        info(&format!(
+            "subsweep {}: start_point {}, num_points {}, step_length {}, profile {}, hwaas {}",
+            i,
+            layout.start_point[i],
+            layout.num_points[i],
+            layout.step_length[i],
+            layout.profile[i],
+            layout.hwaas[i]
+        ));
+    }
+}
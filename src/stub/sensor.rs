@@ -0,0 +1,264 @@
+//! Stubbed sensor, the Rust counterpart of `acc_stub_sensor.c`
+
+use super::faults::{
+    calibration_calls, calibration_fails, sensor_create_fails, sensor_disconnected,
+};
+use super::hal::{self, hal_registered, spi_burst};
+use super::processing::{scene_temperature, synthesize, FrameLayout};
+use crate::*;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Marks calibration results produced by the stub
+const CAL_RESULT_MAGIC: u32 = 0xACC0_CA1B;
+
+/// Registers of the emulated SPI traffic, not the real A121 register map
+const REG_CHIP_ID: u16 = 0x0000;
+const REG_COMMAND: u16 = 0x0001;
+const REG_CONFIG: u16 = 0x0100;
+const REG_CAL_RESULT: u16 = 0x0400;
+const REG_FRAME: u16 = 0x1000;
+
+/// Registers written by prepare
+const CONFIG_WORDS_COMMON: u32 = 16;
+const CONFIG_WORDS_PER_SUBSWEEP: u32 = 8;
+
+struct Sensor {
+    sensor_id: acc_sensor_id_t,
+    calibration_calls: u32,
+    prepared: bool,
+    measured: bool,
+    layout: FrameLayout,
+    frame_index: u32,
+}
+
+static LAST_FRAME_INDEX: AtomicU32 = AtomicU32::new(0);
+
+/// Index of the frame most recently read by any sensor
+pub(super) fn last_frame_index() -> u32 {
+    LAST_FRAME_INDEX.load(Ordering::Relaxed)
+}
+
+pub(super) unsafe fn model_acc_sensor_create(sensor_id: acc_sensor_id_t) -> *mut acc_sensor_t {
+    if sensor_create_fails() {
+        hal::log(
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"sensor",
+            &format!("Sensor {} could not be created", sensor_id),
+        );
+        return core::ptr::null_mut();
+    }
+
+    let sensor = Sensor {
+        sensor_id,
+        calibration_calls: 0,
+        prepared: false,
+        measured: false,
+        layout: FrameLayout::default(),
+        frame_index: 0,
+    };
+    let sensor = hal::alloc(sensor);
+    if !sensor.is_null() {
+        spi_burst(sensor_id, REG_CHIP_ID, false, 1, core::ptr::null_mut(), 0);
+    }
+    sensor.cast()
+}
+
+pub(super) unsafe fn model_acc_sensor_destroy(sensor: *mut acc_sensor_t) {
+    hal::free(sensor.cast::<Sensor>());
+}
+
+pub(super) unsafe fn model_acc_sensor_calibrate(
+    sensor: *mut acc_sensor_t,
+    cal_complete: *mut bool,
+    cal_result: *mut acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> bool {
+    let (Some(sensor), Some(cal_complete), Some(cal_result)) = (
+        sensor.cast::<Sensor>().as_mut(),
+        cal_complete.as_mut(),
+        cal_result.as_mut(),
+    ) else {
+        return false;
+    };
+    if calibration_fails() {
+        hal::log(
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"sensor",
+            &format!("Calibration of sensor {} failed", sensor.sensor_id),
+        );
+        return false;
+    }
+
+    spi_burst(sensor.sensor_id, REG_COMMAND, true, 1, buffer, buffer_size);
+    sensor.calibration_calls += 1;
+    *cal_complete = sensor.calibration_calls >= calibration_calls();
+    if !*cal_complete {
+        return true;
+    }
+
+    sensor.calibration_calls = 0;
+    spi_burst(
+        sensor.sensor_id,
+        REG_CAL_RESULT,
+        false,
+        ACC_CAL_RESULT_DATA_SIZE / size_of::<u16>() as u32,
+        buffer,
+        buffer_size,
+    );
+    cal_result.data[0] = CAL_RESULT_MAGIC;
+    cal_result.data[1] = u32::from(scene_temperature() as u16);
+    for (i, word) in cal_result.data.iter_mut().enumerate().skip(2) {
+        *word = sensor
+            .sensor_id
+            .wrapping_mul(0x9E37_79B9)
+            .wrapping_add(i as u32);
+    }
+    true
+}
+
+pub(super) unsafe fn model_acc_sensor_get_cal_info(
+    cal_result: *const acc_cal_result_t,
+    cal_info: *mut acc_cal_info_t,
+) -> bool {
+    let Some(cal_info) = cal_info.as_mut() else {
+        return false;
+    };
+    if !acc_sensor_validate_calibration(cal_result) {
+        return false;
+    }
+
+    cal_info.temperature = (*cal_result).data[1] as u16 as i16;
+    true
+}
+
+pub(super) unsafe fn model_acc_sensor_validate_calibration(
+    cal_result: *const acc_cal_result_t,
+) -> bool {
+    cal_result
+        .as_ref()
+        .is_some_and(|cal_result| cal_result.data[0] == CAL_RESULT_MAGIC)
+}
+
+pub(super) fn model_acc_sensor_connected(sensor_id: acc_sensor_id_t) -> bool {
+    hal_registered() && !sensor_disconnected(sensor_id)
+}
+
+pub(super) unsafe fn model_acc_sensor_prepare(
+    sensor: *mut acc_sensor_t,
+    config: *const acc_config_t,
+    cal_result: *const acc_cal_result_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> bool {
+    let Some(sensor) = sensor.cast::<Sensor>().as_mut() else {
+        return false;
+    };
+    if config.is_null() || cal_result.is_null() || buffer.is_null() {
+        return false;
+    }
+
+    let layout = FrameLayout::from_config(config);
+    if buffer_size < layout.buffer_size() {
+        hal::log(
+            acc_log_level_t_ACC_LOG_LEVEL_ERROR,
+            c"sensor",
+            &format!(
+                "Buffer of {} bytes is too small, {} bytes needed",
+                buffer_size,
+                layout.buffer_size()
+            ),
+        );
+        return false;
+    }
+
+    spi_burst(
+        sensor.sensor_id,
+        REG_CONFIG,
+        true,
+        CONFIG_WORDS_COMMON + CONFIG_WORDS_PER_SUBSWEEP * u32::from(layout.num_subsweeps()),
+        buffer,
+        buffer_size,
+    );
+    sensor.layout = layout;
+    sensor.prepared = true;
+    sensor.measured = false;
+    sensor.frame_index = 0;
+    true
+}
+
+pub(super) unsafe fn model_acc_sensor_measure(sensor: *mut acc_sensor_t) -> bool {
+    let Some(sensor) = sensor.cast::<Sensor>().as_mut() else {
+        return false;
+    };
+    if !sensor.prepared {
+        return false;
+    }
+
+    spi_burst(
+        sensor.sensor_id,
+        REG_COMMAND,
+        true,
+        1,
+        core::ptr::null_mut(),
+        0,
+    );
+    sensor.measured = true;
+    true
+}
+
+pub(super) unsafe fn model_acc_sensor_read(
+    sensor: *const acc_sensor_t,
+    buffer: *mut c_void,
+    buffer_size: u32,
+) -> bool {
+    // The handle was allocated mutable, the SDK signature only promises not to reconfigure it
+    let Some(sensor) = sensor.cast::<Sensor>().cast_mut().as_mut() else {
+        return false;
+    };
+    if buffer.is_null() || !sensor.measured {
+        return false;
+    }
+    let frame_size = sensor.layout.frame_length() as usize * size_of::<acc_int16_complex_t>();
+    if (buffer_size as usize) < frame_size {
+        return false;
+    }
+
+    // Each IQ sample is two words
+    spi_burst(
+        sensor.sensor_id,
+        REG_FRAME,
+        false,
+        sensor.layout.frame_length() * 2,
+        buffer,
+        buffer_size,
+    );
+    synthesize(&sensor.layout, sensor.frame_index, buffer.cast());
+    LAST_FRAME_INDEX.store(sensor.frame_index, Ordering::Relaxed);
+    sensor.measured = false;
+    sensor.frame_index += 1;
+    true
+}
+
+pub(super) unsafe fn model_acc_sensor_status(sensor: *const acc_sensor_t) {
+    let Some(sensor) = sensor.cast::<Sensor>().as_ref() else {
+        return;
+    };
+
+    let state = if sensor.measured {
+        "measured"
+    } else if sensor.prepared {
+        "prepared"
+    } else {
+        "created"
+    };
+    hal::log(
+        acc_log_level_t_ACC_LOG_LEVEL_INFO,
+        c"sensor",
+        &format!(
+            "sensor_id: {}, state: {}, frames read: {}",
+            sensor.sensor_id, state, sensor.frame_index
+        ),
+    );
+}
@@ -0,0 +1,180 @@
+//! Call trace recording, the Rust counterpart of `acc_stub_trace.c`
+
+use super::lock;
+use crate::*;
+use core::cell::UnsafeCell;
+use core::ffi::CStr;
+use std::sync::Mutex;
+
+/// Handle of a call that is not recorded
+const INVALID_TRACE: u32 = u32::MAX;
+
+const EMPTY_VALUE: acc_stub_trace_value_t = acc_stub_trace_value_t {
+    kind: acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_NONE,
+    integer: 0,
+    real: 0.0,
+};
+
+const EMPTY_ENTRY: acc_stub_trace_entry_t = acc_stub_trace_entry_t {
+    function: core::ptr::null(),
+    num_args: 0,
+    args: [EMPTY_VALUE; ACC_STUB_TRACE_MAX_ARGS as usize],
+    result: EMPTY_VALUE,
+};
+
+struct TraceState {
+    enabled: bool,
+    length: u32,
+    dropped: u32,
+}
+
+/// Recorded calls, handed out by pointer like the C model does
+struct Entries(UnsafeCell<[acc_stub_trace_entry_t; ACC_STUB_TRACE_CAPACITY as usize]>);
+
+// Entries are only written while holding STATE, and only through raw pointers so the
+// pointers returned by acc_stub_trace_get stay valid
+unsafe impl Sync for Entries {}
+
+static STATE: Mutex<TraceState> = Mutex::new(TraceState {
+    enabled: false,
+    length: 0,
+    dropped: 0,
+});
+static ENTRIES: Entries = Entries(UnsafeCell::new(
+    [EMPTY_ENTRY; ACC_STUB_TRACE_CAPACITY as usize],
+));
+
+fn entry(index: u32) -> *mut acc_stub_trace_entry_t {
+    ENTRIES
+        .0
+        .get()
+        .cast::<acc_stub_trace_entry_t>()
+        .wrapping_add(index as usize)
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_trace_enable(enable: bool) {
+    lock(&STATE).enabled = enable;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_trace_clear() {
+    let mut state = lock(&STATE);
+    state.length = 0;
+    state.dropped = 0;
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_trace_length() -> u32 {
+    lock(&STATE).length
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_trace_get(index: u32) -> *const acc_stub_trace_entry_t {
+    if index >= lock(&STATE).length {
+        return core::ptr::null();
+    }
+
+    entry(index)
+}
+
+#[no_mangle]
+pub extern "C" fn acc_stub_trace_dropped() -> u32 {
+    lock(&STATE).dropped
+}
+
+/// Starts recording a call, returning an invalid handle when tracing is off or the buffer is full
+pub(super) fn begin(function: &'static CStr) -> u32 {
+    let mut state = lock(&STATE);
+    if !state.enabled {
+        return INVALID_TRACE;
+    }
+
+    if state.length >= ACC_STUB_TRACE_CAPACITY {
+        state.dropped += 1;
+        return INVALID_TRACE;
+    }
+
+    let trace = state.length;
+    // SAFETY: in bounds, and the lock is held
+    unsafe {
+        entry(trace).write(acc_stub_trace_entry_t {
+            function: function.as_ptr(),
+            ..EMPTY_ENTRY
+        });
+    }
+    state.length += 1;
+    trace
+}
+
+fn add_arg(trace: u32, value: acc_stub_trace_value_t) {
+    let state = lock(&STATE);
+    if trace >= state.length {
+        return;
+    }
+
+    // SAFETY: in bounds, and the lock is held
+    let entry = unsafe { &mut *entry(trace) };
+    if let Some(arg) = entry.args.get_mut(entry.num_args as usize) {
+        *arg = value;
+        entry.num_args += 1;
+    }
+}
+
+fn set_result(trace: u32, value: acc_stub_trace_value_t) {
+    let state = lock(&STATE);
+    if trace >= state.length {
+        return;
+    }
+
+    // SAFETY: in bounds, and the lock is held
+    unsafe { (*entry(trace)).result = value };
+}
+
+fn int_value(value: i64) -> acc_stub_trace_value_t {
+    acc_stub_trace_value_t {
+        kind: acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_INT,
+        integer: value,
+        ..EMPTY_VALUE
+    }
+}
+
+fn float_value(value: f64) -> acc_stub_trace_value_t {
+    acc_stub_trace_value_t {
+        kind: acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_FLOAT,
+        real: value,
+        ..EMPTY_VALUE
+    }
+}
+
+fn pointer_value<T>(value: *const T) -> acc_stub_trace_value_t {
+    acc_stub_trace_value_t {
+        kind: acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_POINTER,
+        integer: value as usize as i64,
+        ..EMPTY_VALUE
+    }
+}
+
+pub(super) fn int(trace: u32, value: i64) {
+    add_arg(trace, int_value(value));
+}
+
+pub(super) fn float(trace: u32, value: f64) {
+    add_arg(trace, float_value(value));
+}
+
+pub(super) fn pointer<T>(trace: u32, value: *const T) {
+    add_arg(trace, pointer_value(value));
+}
+
+pub(super) fn result_int(trace: u32, value: i64) {
+    set_result(trace, int_value(value));
+}
+
+pub(super) fn result_float(trace: u32, value: f64) {
+    set_result(trace, float_value(value));
+}
+
+pub(super) fn result_pointer<T>(trace: u32, value: *const T) {
+    set_result(trace, pointer_value(value));
+}
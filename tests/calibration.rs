@@ -0,0 +1,143 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+/// CRC-32 (IEEE) as at the end of a blob
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn sensor_calibration() -> SensorCalibration {
+    unsafe {
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        acc_sensor_destroy(sensor);
+        SensorCalibration::new(cal_result).unwrap()
+    }
+}
+
+#[test]
+fn sensor_calibration_round_trip() {
+    let _stubs = common::setup();
+    let calibration = sensor_calibration();
+    assert_eq!(
+        calibration.temperature(),
+        ACC_STUB_DEFAULT_TEMPERATURE as i16
+    );
+
+    // Bytes after the blob are ignored, like the rest of a flash page
+    let mut page = [0xFF; 512];
+    assert_eq!(
+        calibration.to_bytes(&mut page),
+        Ok(SensorCalibration::BLOB_SIZE)
+    );
+    let loaded = SensorCalibration::from_bytes(&page).unwrap();
+    assert_eq!(loaded.cal_result().data, calibration.cal_result().data);
+    assert_eq!(loaded.temperature(), calibration.temperature());
+
+    let mut short = [0; SensorCalibration::BLOB_SIZE - 1];
+    assert_eq!(
+        calibration.to_bytes(&mut short),
+        Err(CalibrationError::BufferTooSmall {
+            needed: SensorCalibration::BLOB_SIZE
+        })
+    );
+}
+
+#[test]
+fn invalid_results_are_rejected() {
+    let _stubs = common::setup();
+    let cal_result = acc_cal_result_t {
+        data: [0; (ACC_CAL_RESULT_DATA_SIZE / 4) as usize],
+    };
+    assert_eq!(
+        SensorCalibration::new(cal_result).unwrap_err(),
+        CalibrationError::InvalidCalibration
+    );
+    assert_eq!(
+        SensorCalibration::from_bytes(&[0xFF; SensorCalibration::BLOB_SIZE]).unwrap_err(),
+        CalibrationError::InvalidMagic
+    );
+}
+
+#[test]
+fn stored_temperature_has_to_match_the_result() {
+    let _stubs = common::setup();
+    let mut blob = [0; SensorCalibration::BLOB_SIZE];
+    sensor_calibration().to_bytes(&mut blob).unwrap();
+
+    let temperature = ACC_STUB_DEFAULT_TEMPERATURE as i16 + 10;
+    blob[6..8].copy_from_slice(&temperature.to_le_bytes());
+    let crc = crc32(&blob[..blob.len() - 4]);
+    let end = blob.len();
+    blob[end - 4..].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(
+        SensorCalibration::from_bytes(&blob).unwrap_err(),
+        CalibrationError::InvalidCalibration
+    );
+}
+
+#[test]
+fn blobs_from_another_rss_version_are_rejected() {
+    let _stubs = common::setup();
+    let mut blob = [0; SensorCalibration::BLOB_SIZE];
+    sensor_calibration().to_bytes(&mut blob).unwrap();
+    let current = unsafe { acc_version_get_hex() };
+    assert_eq!(blob[8..12], current.to_le_bytes());
+
+    let stored = current.wrapping_add(1);
+    blob[8..12].copy_from_slice(&stored.to_le_bytes());
+    let crc = crc32(&blob[..blob.len() - 4]);
+    let end = blob.len();
+    blob[end - 4..].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(
+        SensorCalibration::from_bytes(&blob).unwrap_err(),
+        CalibrationError::VersionMismatch { stored, current }
+    );
+}
+
+#[cfg(feature = "distance")]
+#[test]
+fn distance_calibration_round_trip() {
+    let _stubs = common::setup();
+    let sensor_calibration = sensor_calibration();
+    let static_result: Vec<u8> = (0..=200).collect();
+    let calibration = DistanceCalibration::new(&static_result, &sensor_calibration);
+    assert_eq!(calibration.blob_size(), 16 + static_result.len() + 4);
+
+    let mut blob = vec![0; calibration.blob_size() + 8];
+    assert_eq!(calibration.to_bytes(&mut blob), Ok(calibration.blob_size()));
+    let static_size = static_result.len() as u32;
+    let loaded = DistanceCalibration::from_bytes(&blob, static_size).unwrap();
+    assert_eq!(loaded.static_result(), &static_result[..]);
+    assert_eq!(loaded.temperature(), sensor_calibration.temperature());
+
+    // A result for a detector needing another size
+    for other_size in [static_size - 1, static_size + 1] {
+        assert_eq!(
+            DistanceCalibration::from_bytes(&blob, other_size).unwrap_err(),
+            CalibrationError::InvalidCalibration
+        );
+    }
+
+    // Each kind only loads its own blobs
+    assert_eq!(
+        SensorCalibration::from_bytes(&blob).unwrap_err(),
+        CalibrationError::WrongKind
+    );
+    let mut sensor_blob = [0; SensorCalibration::BLOB_SIZE];
+    sensor_calibration.to_bytes(&mut sensor_blob).unwrap();
+    assert_eq!(
+        DistanceCalibration::from_bytes(&sensor_blob, static_size).unwrap_err(),
+        CalibrationError::WrongKind
+    );
+}
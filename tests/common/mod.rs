@@ -0,0 +1,203 @@
+//! HAL and helpers shared by the tests driving the stub library
+//!
+//! The stubs keep their state in globals, so every test holds the guard returned by [`setup`].
+
+#![allow(dead_code)]
+
+use a121_sys::*;
+use std::alloc::{self, Layout};
+use std::ffi::{c_void, CStr};
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Largest SPI transfer of the HAL registered by [`setup`]
+pub const MAX_SPI_TRANSFER_SIZE: u16 = 1024;
+
+static STUBS: Mutex<()> = Mutex::new(());
+static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static TRANSFERS: Mutex<Vec<Transfer>> = Mutex::new(Vec::new());
+static LIVE_ALLOCATIONS: AtomicIsize = AtomicIsize::new(0);
+
+/// Alignment of the memory handed out by [`hal`], with the allocation size stored in front
+const ALIGN: usize = 16;
+
+/// An SPI transfer the stubs made through the HAL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub sensor_id: acc_sensor_id_t,
+    /// First word of the transfer, the register address with the top bit set for writes
+    pub header: u16,
+    pub size: usize,
+    pub transfer16: bool,
+}
+
+fn lock<T>(mutex: &'static Mutex<T>) -> MutexGuard<'static, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Takes the stubs for the calling test and resets them: the HAL from [`hal`] registered, no
+/// faults, an empty scene, an empty presence timeline and tracing off
+pub fn setup() -> MutexGuard<'static, ()> {
+    let guard = lock(&STUBS);
+    unsafe {
+        acc_stub_faults_clear();
+        acc_stub_scene_clear();
+        acc_stub_trace_enable(false);
+        acc_stub_trace_clear();
+        #[cfg(feature = "presence")]
+        acc_stub_presence_timeline_clear();
+        assert!(acc_rss_hal_register(&hal()));
+    }
+    set_log_sink(log_sink);
+    lock(&LOG).clear();
+    lock(&TRANSFERS).clear();
+    guard
+}
+
+/// HAL recording the log lines and transfers of the stubs, see [`take_log`] and
+/// [`take_transfers`]
+pub fn hal() -> acc_hal_a121_t {
+    acc_hal_a121_t {
+        max_spi_transfer_size: MAX_SPI_TRANSFER_SIZE,
+        mem_alloc: Some(mem_alloc),
+        mem_free: Some(mem_free),
+        transfer: Some(transfer),
+        #[cfg(not(feature = "rust_stubs"))]
+        log: Some(c_log_stub),
+        // The C log wrapper is not built with the Rust stubs
+        #[cfg(feature = "rust_stubs")]
+        log: None,
+        optimization: acc_hal_optimization_t { transfer16: None },
+    }
+}
+
+/// Log lines since the last call, as `"<module>: <message>"`
+pub fn take_log() -> Vec<String> {
+    core::mem::take(&mut *lock(&LOG))
+}
+
+/// Transfers since the last call
+pub fn take_transfers() -> Vec<Transfer> {
+    core::mem::take(&mut *lock(&TRANSFERS))
+}
+
+/// Memory handed out by the HAL and not returned yet
+pub fn live_allocations() -> isize {
+    LIVE_ALLOCATIONS.load(Ordering::SeqCst)
+}
+
+unsafe extern "C" fn mem_alloc(size: usize) -> *mut c_void {
+    let layout = Layout::from_size_align(size + ALIGN, ALIGN).unwrap();
+    let memory = alloc::alloc(layout);
+    if memory.is_null() {
+        return memory.cast();
+    }
+    LIVE_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+    memory.cast::<usize>().write(size);
+    memory.add(ALIGN).cast()
+}
+
+unsafe extern "C" fn mem_free(memory: *mut c_void) {
+    let memory = memory.cast::<u8>().sub(ALIGN);
+    let size = memory.cast::<usize>().read();
+    LIVE_ALLOCATIONS.fetch_sub(1, Ordering::SeqCst);
+    alloc::dealloc(
+        memory,
+        Layout::from_size_align(size + ALIGN, ALIGN).unwrap(),
+    );
+}
+
+unsafe extern "C" fn transfer(sensor_id: acc_sensor_id_t, buffer: *mut u8, size: usize) {
+    lock(&TRANSFERS).push(Transfer {
+        sensor_id,
+        header: u16::from_ne_bytes([*buffer, *buffer.add(1)]),
+        size,
+        transfer16: false,
+    });
+}
+
+/// Calls recorded by the stub trace so far
+pub fn trace() -> Vec<acc_stub_trace_entry_t> {
+    unsafe {
+        (0..acc_stub_trace_length())
+            .map(|index| *acc_stub_trace_get(index))
+            .collect()
+    }
+}
+
+/// Name of the function called in `entry`
+pub fn function(entry: &acc_stub_trace_entry_t) -> &'static str {
+    unsafe { CStr::from_ptr(entry.function) }.to_str().unwrap()
+}
+
+/// Calibrates `sensor` to completion, failing the test if a call fails
+pub unsafe fn calibrate(sensor: *mut acc_sensor_t) -> acc_cal_result_t {
+    let mut cal_result = acc_cal_result_t {
+        data: [0; (ACC_CAL_RESULT_DATA_SIZE / 4) as usize],
+    };
+    let mut cal_complete = false;
+    while !cal_complete {
+        assert!(acc_sensor_calibrate(
+            sensor,
+            &mut cal_complete,
+            &mut cal_result,
+            core::ptr::null_mut(),
+            0
+        ));
+    }
+    cal_result
+}
+
+/// Prepares `sensor` for `config`, returning the work buffer it must keep
+pub unsafe fn prepare(
+    sensor: *mut acc_sensor_t,
+    config: *const acc_config_t,
+    cal_result: &acc_cal_result_t,
+) -> Vec<u32> {
+    let mut buffer_size = 0;
+    assert!(acc_rss_get_buffer_size(config, &mut buffer_size));
+    let mut buffer = vec![0u32; (buffer_size as usize).div_ceil(4)];
+    assert!(acc_sensor_prepare(
+        sensor,
+        config,
+        cal_result,
+        buffer.as_mut_ptr().cast(),
+        buffer_size
+    ));
+    buffer
+}
+
+/// Measures and reads one frame of `length` points
+pub unsafe fn read_frame(sensor: *mut acc_sensor_t, length: usize) -> Vec<acc_int16_complex_t> {
+    let mut frame = vec![acc_int16_complex_t { real: 0, imag: 0 }; length];
+    assert!(acc_sensor_measure(sensor));
+    assert!(acc_sensor_read(
+        sensor,
+        frame.as_mut_ptr().cast(),
+        (length * size_of::<acc_int16_complex_t>()) as u32
+    ));
+    frame
+}
+
+/// `transfer16` for HALs built from [`hal`], recording like the plain transfer
+pub unsafe extern "C" fn transfer16(sensor_id: acc_sensor_id_t, buffer: *mut u16, length: usize) {
+    assert!(buffer.is_aligned());
+    lock(&TRANSFERS).push(Transfer {
+        sensor_id,
+        header: *buffer,
+        size: length * size_of::<u16>(),
+        transfer16: true,
+    });
+}
+
+fn record(module: &CStr, message: &CStr) {
+    lock(&LOG).push(format!(
+        "{}: {}",
+        module.to_string_lossy(),
+        message.to_string_lossy()
+    ));
+}
+
+fn log_sink(_level: acc_log_level_t, module: &CStr, message: &CStr, _part: LogPart) {
+    record(module, message);
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+#[test]
+fn builder_rejects_invalid_values() {
+    let _stubs = common::setup();
+    assert_eq!(
+        Config::builder().step_length(5).build().err(),
+        Some(ConfigError::InvalidStepLength(5))
+    );
+    assert_eq!(
+        Config::builder().sweeps_per_frame(0).build().err(),
+        Some(ConfigError::ZeroSweepsPerFrame)
+    );
+    assert_eq!(
+        Config::builder().profile(0).build().err(),
+        Some(ConfigError::InvalidProfile(0))
+    );
+    assert_eq!(
+        Config::builder().frame_rate(-1.0).build().err(),
+        Some(ConfigError::InvalidFrameRate(-1.0))
+    );
+    assert!(matches!(
+        Config::builder().frame_rate(f32::INFINITY).build(),
+        Err(ConfigError::InvalidFrameRate(_))
+    ));
+    // Configs of failed builds are destroyed
+    assert_eq!(common::live_allocations(), 0);
+}
+
+#[test]
+fn builder_rejects_invalid_combinations() {
+    let _stubs = common::setup();
+    assert_eq!(
+        Config::builder()
+            .profile(acc_config_profile_t_ACC_CONFIG_PROFILE_3)
+            .prf(acc_config_prf_t_ACC_CONFIG_PRF_19_5_MHZ)
+            .build()
+            .err(),
+        Some(ConfigError::PrfNeedsProfile1)
+    );
+    assert_eq!(
+        Config::builder()
+            .profile(acc_config_profile_t_ACC_CONFIG_PROFILE_2)
+            .enable_loopback(true)
+            .build()
+            .err(),
+        Some(ConfigError::LoopbackWithProfile2)
+    );
+    assert_eq!(
+        Config::builder()
+            .inter_frame_idle_state(acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY)
+            .inter_sweep_idle_state(acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_DEEP_SLEEP)
+            .build()
+            .err(),
+        Some(ConfigError::IdleStateOrder)
+    );
+    assert!(Config::builder()
+        .profile(acc_config_profile_t_ACC_CONFIG_PROFILE_1)
+        .prf(acc_config_prf_t_ACC_CONFIG_PRF_19_5_MHZ)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn builder_round_trips_through_getters() {
+    let _stubs = common::setup();
+    let config = Config::builder()
+        .start_point(100)
+        .num_points(40)
+        .step_length(4)
+        .profile(acc_config_profile_t_ACC_CONFIG_PROFILE_2)
+        .hwaas(16)
+        .receiver_gain(12)
+        .sweeps_per_frame(8)
+        .sweep_rate(100.0)
+        .frame_rate(5.5)
+        .enable_tx(false)
+        .inter_frame_idle_state(acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_SLEEP)
+        .inter_sweep_idle_state(acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY)
+        .prf(acc_config_prf_t_ACC_CONFIG_PRF_13_0_MHZ)
+        .phase_enhancement(true)
+        .iq_imbalance_compensation(true)
+        .double_buffering(true)
+        .num_subsweeps(2)
+        .build()
+        .unwrap();
+
+    assert_eq!(config.start_point(), 100);
+    assert_eq!(config.num_points(), 40);
+    assert_eq!(config.step_length(), 4);
+    assert_eq!(config.profile(), acc_config_profile_t_ACC_CONFIG_PROFILE_2);
+    assert_eq!(config.hwaas(), 16);
+    assert_eq!(config.receiver_gain(), 12);
+    assert_eq!(config.sweeps_per_frame(), 8);
+    assert_eq!(config.sweep_rate(), 100.0);
+    assert!(!config.continuous_sweep_mode());
+    assert_eq!(config.frame_rate(), 5.5);
+    assert!(!config.enable_tx());
+    assert_eq!(
+        config.inter_frame_idle_state(),
+        acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_SLEEP
+    );
+    assert_eq!(
+        config.inter_sweep_idle_state(),
+        acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY
+    );
+    assert_eq!(config.prf(), acc_config_prf_t_ACC_CONFIG_PRF_13_0_MHZ);
+    assert!(config.phase_enhancement());
+    assert!(config.iq_imbalance_compensation());
+    assert!(!config.enable_loopback());
+    assert!(config.double_buffering());
+    assert_eq!(config.num_subsweeps(), 2);
+    // The values go to the first subsweep
+    assert_eq!(config.subsweep(0).unwrap().num_points(), 40);
+    assert_eq!(config.subsweep(1).unwrap().num_points(), 160);
+    assert_eq!(config.check(), Ok(()));
+}
+
+#[test]
+fn subsweeps_stop_at_num_subsweeps() {
+    let _stubs = common::setup();
+    let mut config = Config::builder().num_subsweeps(3).build().unwrap();
+    assert!(config.subsweep(2).is_some());
+    assert!(config.subsweep(3).is_none());
+    assert!(config.subsweep_mut(3).is_none());
+    assert!(config.subsweep(ACC_MAX_NUM_SUBSWEEPS as u8).is_none());
+    assert!(config.subsweep(u8::MAX).is_none());
+
+    let subsweeps = config.subsweeps();
+    assert_eq!(subsweeps.len(), 3);
+    let indices: Vec<u8> = subsweeps.map(|subsweep| subsweep.index()).collect();
+    assert_eq!(indices, [0, 1, 2]);
+
+    config.subsweep_mut(1).unwrap().set_num_points(20);
+    assert_eq!(config.subsweep(1).unwrap().num_points(), 20);
+
+    // A count above the maximum set through the raw config only gives the existing subsweeps
+    unsafe { acc_config_num_subsweeps_set(config.as_mut_ptr(), ACC_MAX_NUM_SUBSWEEPS as u8 + 2) };
+    assert_eq!(config.subsweeps().count(), ACC_MAX_NUM_SUBSWEEPS as usize);
+    assert!(config.subsweep(ACC_MAX_NUM_SUBSWEEPS as u8 - 1).is_some());
+    assert!(config.subsweep(ACC_MAX_NUM_SUBSWEEPS as u8).is_none());
+}
@@ -0,0 +1,1259 @@
+//! Runs the same scenarios against golden values, so the C and the Rust stub backends, tested in
+//! separate CI jobs, synthesize the same frames up to rounding, fill in the same results and
+//! record the same calls. Together the scenarios reach every model of the stubs.
+
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+use core::ffi::CStr;
+use core::ptr;
+
+const INT: acc_stub_trace_kind_t = acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_INT;
+const FLOAT: acc_stub_trace_kind_t = acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_FLOAT;
+const POINTER: acc_stub_trace_kind_t = acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_POINTER;
+
+/// Two sweeps of six points per frame, two frames
+#[rustfmt::skip]
+const FRAMES: [(i16, i16); 24] = [
+    (501, 926), (569, 1016), (607, 1073), (616, 1086), (615, 1069), (589, 996),
+    (516, 926), (559, 1031), (601, 1085), (616, 1104), (618, 1074), (584, 1006),
+    (533, 920), (603, 1010), (637, 1084), (649, 1108), (639, 1083), (612, 1012),
+    (550, 918), (597, 1018), (643, 1085), (655, 1103), (640, 1084), (622, 1018),
+];
+
+/// Two subsweeps of three and two points, two sweeps per frame, two frames
+#[rustfmt::skip]
+const SUBSWEEP_FRAMES: [(i16, i16); 20] = [
+    (406, -263), (431, -276), (451, -288), (670, -429), (662, -418),
+    (411, -261), (432, -276), (447, -283), (670, -424), (659, -415),
+    (410, -256), (429, -270), (447, -287), (673, -429), (660, -418),
+    (406, -257), (427, -271), (450, -288), (675, -428), (660, -420),
+];
+
+/// Calls reading the frame layout of a config, with the subsweeps as
+/// `[start_point, num_points, step_length, hwaas, profile]`
+fn layout(subsweeps: &[[u32; 5]], sweeps_per_frame: u16, frame_rate: u32) -> Vec<String> {
+    let mut calls = vec![format!(
+        "acc_config_num_subsweeps_get(pointer) = {}",
+        subsweeps.len()
+    )];
+    for (index, [start_point, num_points, step_length, hwaas, profile]) in
+        subsweeps.iter().enumerate()
+    {
+        calls.extend([
+            format!(
+                "acc_config_subsweep_start_point_get(pointer, {}) = {}",
+                index, start_point
+            ),
+            format!(
+                "acc_config_subsweep_num_points_get(pointer, {}) = {}",
+                index, num_points
+            ),
+            format!(
+                "acc_config_subsweep_step_length_get(pointer, {}) = {}",
+                index, step_length
+            ),
+            format!(
+                "acc_config_subsweep_hwaas_get(pointer, {}) = {}",
+                index, hwaas
+            ),
+            format!(
+                "acc_config_subsweep_profile_get(pointer, {}) = {}",
+                index, profile
+            ),
+        ]);
+    }
+    calls.extend([
+        format!(
+            "acc_config_sweeps_per_frame_get(pointer) = {}",
+            sweeps_per_frame
+        ),
+        "acc_config_sweep_rate_get(pointer) = 0".to_string(),
+        format!("acc_config_frame_rate_get(pointer) = {}", frame_rate),
+        "acc_config_continuous_sweep_mode_get(pointer) = 0".to_string(),
+        "acc_config_inter_sweep_idle_state_get(pointer) = 2".to_string(),
+    ]);
+    calls
+}
+
+fn calls(calls: &[&str]) -> Vec<String> {
+    calls.iter().map(|call| call.to_string()).collect()
+}
+
+fn expected_trace() -> Vec<String> {
+    let layout = layout(&[[190, 6, 4, 8, 3]], 2, 20);
+    let frame = calls(&[
+        "acc_sensor_measure(pointer) = 1",
+        "acc_sensor_read(pointer, pointer, 48) = 1",
+        "acc_processing_execute(pointer, pointer, pointer)",
+    ]);
+    [
+        calls(&[
+            "acc_config_create() = pointer",
+            "acc_config_start_point_set(pointer, 190)",
+            "acc_config_num_points_set(pointer, 6)",
+            "acc_config_step_length_set(pointer, 4)",
+            "acc_config_sweeps_per_frame_set(pointer, 2)",
+            "acc_config_frame_rate_set(pointer, 20)",
+            "acc_processing_create(pointer, null) = pointer",
+        ]),
+        layout.clone(),
+        calls(&[
+            "acc_sensor_create(1) = pointer",
+            "acc_sensor_calibrate(pointer, pointer, pointer, null, 0) = 1",
+            "acc_sensor_calibrate(pointer, pointer, pointer, null, 0) = 1",
+            "acc_sensor_prepare(pointer, pointer, pointer, pointer, 64) = 0",
+        ]),
+        layout.clone(),
+        calls(&["acc_rss_get_buffer_size(pointer, pointer) = 1"]),
+        layout.clone(),
+        calls(&["acc_sensor_prepare(pointer, pointer, pointer, pointer, 304) = 1"]),
+        layout,
+        frame.clone(),
+        frame,
+        calls(&[
+            "acc_sensor_destroy(pointer)",
+            "acc_processing_destroy(pointer)",
+            "acc_config_destroy(pointer)",
+        ]),
+    ]
+    .concat()
+}
+
+fn value(value: &acc_stub_trace_value_t) -> String {
+    match value.kind {
+        INT => value.integer.to_string(),
+        FLOAT => value.real.to_string(),
+        // Addresses differ between runs
+        POINTER if value.integer == 0 => "null".to_string(),
+        POINTER => "pointer".to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+/// `"function(args) = result"`, without the result for functions returning nothing
+fn describe(entry: &acc_stub_trace_entry_t) -> String {
+    let args: Vec<_> = entry.args[..entry.num_args as usize]
+        .iter()
+        .map(value)
+        .collect();
+    let call = format!("{}({})", common::function(entry), args.join(", "));
+    match entry.result.kind {
+        INT | FLOAT | POINTER => format!("{} = {}", call, value(&entry.result)),
+        _ => call,
+    }
+}
+
+/// The recorded trace, checking that nothing was dropped
+fn trace() -> Vec<String> {
+    assert_eq!(unsafe { acc_stub_trace_dropped() }, 0);
+    common::trace().iter().map(describe).collect()
+}
+
+/// LSBs an IQ component may be off by, the frames are synthesized with `sin`, `cos` and a
+/// distance falloff in floating point, which the C toolchain's libm and Rust's `f32` may round
+/// differently
+const POINT_TOLERANCE: i32 = 1;
+
+/// Compares IQ points within `POINT_TOLERANCE` per component instead of bit for bit
+fn assert_points(actual: &[acc_int16_complex_t], expected: &[(i16, i16)]) {
+    let close = |actual: i16, expected: i16| {
+        (i32::from(actual) - i32::from(expected)).abs() <= POINT_TOLERANCE
+    };
+    assert_eq!(actual.len(), expected.len());
+    for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            close(actual.real, expected.0) && close(actual.imag, expected.1),
+            "point {}: {:?} != {:?}",
+            index,
+            actual,
+            expected
+        );
+    }
+}
+
+/// Values written through the out parameters of the stubs, by name
+#[derive(Default)]
+struct Outputs(Vec<(String, f64)>);
+
+impl Outputs {
+    fn push(&mut self, name: &str, value: impl Into<f64>) {
+        self.0.push((name.to_string(), value.into()));
+    }
+
+    fn push_all<T: Into<f64> + Copy>(&mut self, name: &str, values: &[T]) {
+        for (index, value) in values.iter().enumerate() {
+            self.push(&format!("{}[{}]", name, index), *value);
+        }
+    }
+
+    /// Floats may differ in the last bits between the backends
+    fn assert_eq(&self, expected: &[(&str, f64)]) {
+        let names: Vec<_> = self.0.iter().map(|(name, _)| name.as_str()).collect();
+        let expected_names: Vec<_> = expected.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, expected_names);
+        for ((name, actual), (_, expected)) in self.0.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() <= 1e-4 * expected.abs().max(1.0),
+                "{}: {} != {}",
+                name,
+                actual,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn backends_match_golden_scenario() {
+    let _stubs = common::setup();
+    let mut frames = Vec::new();
+    unsafe {
+        acc_stub_scene_add_reflector(0.5, 1000.0, 0.05);
+        acc_stub_scene_add_reflector(0.52, 300.0, -0.1);
+        acc_stub_scene_set_noise(10.0);
+        acc_stub_fault_calibration(2, false);
+        acc_stub_trace_enable(true);
+
+        let config = acc_config_create();
+        acc_config_start_point_set(config, 190);
+        acc_config_num_points_set(config, 6);
+        acc_config_step_length_set(config, 4);
+        acc_config_sweeps_per_frame_set(config, 2);
+        acc_config_frame_rate_set(config, 20.0);
+        let processing = acc_processing_create(config, ptr::null_mut());
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut small = [0u32; 16];
+        assert!(!acc_sensor_prepare(
+            sensor,
+            config,
+            &cal_result,
+            small.as_mut_ptr().cast(),
+            64
+        ));
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        for _ in 0..2 {
+            let mut frame = common::read_frame(sensor, 12);
+            let mut result = core::mem::zeroed();
+            acc_processing_execute(processing, frame.as_mut_ptr().cast(), &mut result);
+            frames.extend(frame);
+        }
+        acc_sensor_destroy(sensor);
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+        acc_stub_trace_enable(false);
+    }
+
+    assert_points(&frames, &FRAMES);
+    assert_eq!(trace(), expected_trace());
+    assert_eq!(
+        common::take_log(),
+        ["sensor: Buffer of 64 bytes is too small, 304 bytes needed"]
+    );
+}
+
+#[test]
+fn sensor_and_processing_outputs() {
+    let _stubs = common::setup();
+    let mut outputs = Outputs::default();
+    let mut frames = Vec::new();
+    unsafe {
+        acc_stub_scene_add_reflector(0.3, 800.0, 0.0);
+        acc_stub_scene_set_noise(4.0);
+        acc_stub_scene_set_temperature(-5);
+        assert!(acc_stub_fault_frame_flags(
+            1,
+            1,
+            ACC_STUB_FLAG_DATA_SATURATED | ACC_STUB_FLAG_CALIBRATION_NEEDED
+        ));
+        acc_stub_fault_sensor_disconnected(2, true);
+        acc_stub_trace_enable(true);
+
+        let mut small_hal = common::hal();
+        small_hal.max_spi_transfer_size = 8;
+        outputs.push("hal_register small", acc_rss_hal_register(&small_hal));
+        outputs.push("sensor_connected 1", acc_sensor_connected(1));
+        outputs.push("sensor_connected 2", acc_sensor_connected(2));
+
+        let config = acc_config_create();
+        acc_config_num_subsweeps_set(config, 2);
+        acc_config_subsweep_start_point_set(config, 100, 0);
+        acc_config_subsweep_num_points_set(config, 3, 0);
+        acc_config_subsweep_start_point_set(config, 118, 1);
+        acc_config_subsweep_num_points_set(config, 2, 1);
+        acc_config_subsweep_step_length_set(config, 6, 1);
+        acc_config_sweeps_per_frame_set(config, 2);
+        acc_config_log(config);
+
+        let mut metadata: acc_processing_metadata_t = core::mem::zeroed();
+        let processing = acc_processing_create(config, &mut metadata);
+        outputs.push("frame_data_length", metadata.frame_data_length);
+        outputs.push("sweep_data_length", metadata.sweep_data_length);
+        outputs.push_all("subsweep_data_offset", &metadata.subsweep_data_offset);
+        outputs.push_all("subsweep_data_length", &metadata.subsweep_data_length);
+        outputs.push("max_sweep_rate", metadata.max_sweep_rate);
+        outputs.push("high_speed_mode", metadata.high_speed_mode);
+        let mut buffer_size = 0;
+        assert!(acc_rss_get_buffer_size(config, &mut buffer_size));
+        outputs.push("buffer_size", buffer_size);
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut cal_info = acc_cal_info_t { temperature: 0 };
+        assert!(acc_sensor_get_cal_info(&cal_result, &mut cal_info));
+        outputs.push("cal_info.temperature", cal_info.temperature);
+        outputs.push(
+            "validate_calibration",
+            acc_sensor_validate_calibration(&cal_result),
+        );
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        acc_sensor_status(sensor);
+        for frame_index in 0..2 {
+            let mut frame = common::read_frame(sensor, 10);
+            let mut result: acc_processing_result_t = core::mem::zeroed();
+            acc_processing_execute(processing, frame.as_mut_ptr().cast(), &mut result);
+            let name = |field: &str| format!("result {}.{}", frame_index, field);
+            outputs.push(&name("data_saturated"), result.data_saturated);
+            outputs.push(&name("frame_delayed"), result.frame_delayed);
+            outputs.push(&name("calibration_needed"), result.calibration_needed);
+            outputs.push(&name("temperature"), result.temperature);
+            outputs.push(&name("frame"), result.frame == frame.as_mut_ptr());
+            frames.extend(frame);
+        }
+        outputs.push("points_to_meter", acc_processing_points_to_meter(321));
+        outputs.push("meter_to_points", acc_processing_meter_to_points(1.234));
+
+        acc_sensor_destroy(sensor);
+        acc_processing_destroy(processing);
+        acc_stub_hal_unregister();
+        outputs.push("config_create unregistered", acc_config_create().is_null());
+        assert!(acc_rss_hal_register(&common::hal()));
+        acc_config_destroy(config);
+        acc_stub_trace_enable(false);
+    }
+
+    assert_points(&frames, &SUBSWEEP_FRAMES);
+    outputs.assert_eq(&[
+        ("hal_register small", 0.0),
+        ("sensor_connected 1", 1.0),
+        ("sensor_connected 2", 0.0),
+        ("frame_data_length", 10.0),
+        ("sweep_data_length", 5.0),
+        ("subsweep_data_offset[0]", 0.0),
+        ("subsweep_data_offset[1]", 3.0),
+        ("subsweep_data_offset[2]", 5.0),
+        ("subsweep_data_offset[3]", 5.0),
+        ("subsweep_data_length[0]", 3.0),
+        ("subsweep_data_length[1]", 2.0),
+        ("subsweep_data_length[2]", 0.0),
+        ("subsweep_data_length[3]", 0.0),
+        ("max_sweep_rate", 16666.66796875),
+        ("high_speed_mode", 0.0),
+        ("buffer_size", 296.0),
+        ("cal_info.temperature", -5.0),
+        ("validate_calibration", 1.0),
+        ("result 0.data_saturated", 0.0),
+        ("result 0.frame_delayed", 0.0),
+        ("result 0.calibration_needed", 0.0),
+        ("result 0.temperature", -5.0),
+        ("result 0.frame", 1.0),
+        ("result 1.data_saturated", 1.0),
+        ("result 1.frame_delayed", 0.0),
+        ("result 1.calibration_needed", 1.0),
+        ("result 1.temperature", -5.0),
+        ("result 1.frame", 1.0),
+        ("points_to_meter", 0.8025000095367432),
+        ("meter_to_points", 494.0),
+        ("config_create unregistered", 1.0),
+    ]);
+    let layout = layout(&[[100, 3, 1, 8, 3], [118, 2, 6, 8, 3]], 2, 0);
+    assert_eq!(
+        trace(),
+        [
+            calls(&[
+                "acc_rss_hal_register(pointer) = 0",
+                "acc_sensor_connected(1) = 1",
+                "acc_sensor_connected(2) = 0",
+                "acc_config_create() = pointer",
+                "acc_config_num_subsweeps_set(pointer, 2)",
+                "acc_config_subsweep_start_point_set(pointer, 100, 0)",
+                "acc_config_subsweep_num_points_set(pointer, 3, 0)",
+                "acc_config_subsweep_start_point_set(pointer, 118, 1)",
+                "acc_config_subsweep_num_points_set(pointer, 2, 1)",
+                "acc_config_subsweep_step_length_set(pointer, 6, 1)",
+                "acc_config_sweeps_per_frame_set(pointer, 2)",
+                "acc_config_log(pointer)",
+            ]),
+            layout.clone(),
+            calls(&["acc_processing_create(pointer, pointer) = pointer",]),
+            layout.clone(),
+            calls(&["acc_rss_get_buffer_size(pointer, pointer) = 1",]),
+            layout.clone(),
+            calls(&[
+                "acc_sensor_create(1) = pointer",
+                "acc_sensor_calibrate(pointer, pointer, pointer, null, 0) = 1",
+                "acc_sensor_get_cal_info(pointer, pointer) = 1",
+                "acc_sensor_validate_calibration(pointer) = 1",
+                "acc_sensor_validate_calibration(pointer) = 1",
+                "acc_rss_get_buffer_size(pointer, pointer) = 1",
+            ]),
+            layout.clone(),
+            calls(&["acc_sensor_prepare(pointer, pointer, pointer, pointer, 296) = 1",]),
+            layout,
+            calls(&[
+                "acc_sensor_status(pointer)",
+                "acc_sensor_measure(pointer) = 1",
+                "acc_sensor_read(pointer, pointer, 40) = 1",
+                "acc_processing_execute(pointer, pointer, pointer)",
+                "acc_sensor_measure(pointer) = 1",
+                "acc_sensor_read(pointer, pointer, 40) = 1",
+                "acc_processing_execute(pointer, pointer, pointer)",
+                "acc_processing_points_to_meter(321) = 0.8025000095367432",
+                "acc_processing_meter_to_points(1.2339999675750732) = 494",
+                "acc_sensor_destroy(pointer)",
+                "acc_processing_destroy(pointer)",
+                "acc_config_create() = null",
+                "acc_rss_hal_register(pointer) = 1",
+                "acc_config_destroy(pointer)",
+            ]),
+        ]
+        .concat()
+    );
+    assert_eq!(
+        common::take_log(),
+        [
+            "rss: max_spi_transfer_size 8 is below the required 16",
+            "config: sweeps_per_frame: 2, num_subsweeps: 2",
+            "config: sweep_rate: 0.000 Hz, frame_rate: 0.000 Hz",
+            "config: subsweep 0: start_point 100, num_points 3, step_length 1, profile 3, hwaas 8",
+            "config: subsweep 1: start_point 118, num_points 2, step_length 6, profile 3, hwaas 8",
+            "sensor: sensor_id: 1, state: prepared, frames read: 0",
+        ]
+    );
+}
+
+#[cfg(feature = "distance")]
+#[test]
+fn distance_detector_outputs() {
+    let _stubs = common::setup();
+    let mut outputs = Outputs::default();
+    unsafe {
+        let config = acc_detector_distance_config_create();
+        acc_detector_distance_config_start_set(config, 0.2);
+        acc_detector_distance_config_end_set(config, 0.3);
+        acc_detector_distance_config_max_profile_set(
+            config,
+            acc_config_profile_t_ACC_CONFIG_PROFILE_2,
+        );
+        acc_detector_distance_config_max_step_length_set(config, 1);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        acc_stub_trace_enable(true);
+
+        let handle = acc_detector_distance_create(config);
+        acc_detector_distance_config_log(handle, config);
+        let (mut buffer_size, mut static_size) = (0, 0);
+        assert!(acc_detector_distance_get_sizes(
+            handle,
+            &mut buffer_size,
+            &mut static_size
+        ));
+        outputs.push("buffer_size", buffer_size);
+        outputs.push("static_size", static_size);
+
+        let mut buffer = vec![0u32; (buffer_size as usize).div_ceil(4)];
+        let mut static_result = vec![0xFFu8; static_size as usize + 1];
+        let mut dynamic_result = acc_detector_cal_result_dynamic_t {
+            data: [0xFF; (ACC_DETECTOR_CAL_RESULT_DYNAMIC_DATA_SIZE / 4) as usize],
+        };
+        let mut complete = false;
+        outputs.push(
+            "calibrate small",
+            acc_detector_distance_calibrate(
+                sensor,
+                handle,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size - 1,
+                static_result.as_mut_ptr(),
+                static_size,
+                &mut dynamic_result,
+                &mut complete,
+            ),
+        );
+        outputs.push(
+            "calibrate",
+            acc_detector_distance_calibrate(
+                sensor,
+                handle,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+                static_result.as_mut_ptr(),
+                static_size,
+                &mut dynamic_result,
+                &mut complete,
+            ),
+        );
+        outputs.push("calibrate complete", complete);
+        let zeroed = static_result.iter().take_while(|&&byte| byte == 0).count();
+        outputs.push("static_result zeroed", zeroed as u32);
+        outputs.push(
+            "static_result past the size",
+            static_result[static_size as usize],
+        );
+        outputs.push("dynamic_result", dynamic_result.data.iter().sum::<u32>());
+        dynamic_result.data.fill(0xFF);
+        complete = false;
+        outputs.push(
+            "update_calibration",
+            acc_detector_distance_update_calibration(
+                sensor,
+                handle,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+                &mut dynamic_result,
+                &mut complete,
+            ),
+        );
+        outputs.push("update_calibration complete", complete);
+        outputs.push(
+            "update_calibration dynamic_result",
+            dynamic_result.data.iter().sum::<u32>(),
+        );
+        outputs.push(
+            "prepare",
+            acc_detector_distance_prepare(
+                handle,
+                config,
+                sensor,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+            ),
+        );
+        acc_detector_distance_destroy(handle);
+        acc_stub_trace_enable(false);
+        acc_sensor_destroy(sensor);
+        acc_detector_distance_config_destroy(config);
+    }
+    outputs.assert_eq(&[
+        ("buffer_size", 420.0),
+        ("static_size", 164.0),
+        ("calibrate small", 0.0),
+        ("calibrate", 1.0),
+        ("calibrate complete", 1.0),
+        ("static_result zeroed", 164.0),
+        ("static_result past the size", 255.0),
+        ("dynamic_result", 0.0),
+        ("update_calibration", 1.0),
+        ("update_calibration complete", 1.0),
+        ("update_calibration dynamic_result", 0.0),
+        ("prepare", 1.0),
+    ]);
+    let layout = layout(&[[80, 41, 1, 8, 2]], 1, 0);
+    assert_eq!(
+        trace(),
+        [
+        calls(&[
+        "acc_detector_distance_create(pointer) = pointer",
+        "acc_detector_distance_config_max_profile_get(pointer) = 2",
+        "acc_detector_distance_config_max_step_length_get(pointer) = 1",
+        "acc_detector_distance_config_start_get(pointer) = 0.20000000298023224",
+        "acc_processing_meter_to_points(0.20000000298023224) = 80",
+        "acc_detector_distance_config_end_get(pointer) = 0.30000001192092896",
+        "acc_processing_meter_to_points(0.30000001192092896) = 120",
+        "acc_config_create() = pointer",
+        "acc_config_start_point_set(pointer, 80)",
+        "acc_config_num_points_set(pointer, 41)",
+        "acc_config_step_length_set(pointer, 1)",
+        "acc_config_profile_set(pointer, 2)",
+        "acc_detector_distance_config_log(pointer, pointer)",
+        "acc_detector_distance_config_start_get(pointer) = 0.20000000298023224",
+        "acc_detector_distance_config_end_get(pointer) = 0.30000001192092896",
+        "acc_detector_distance_config_max_profile_get(pointer) = 2",
+        "acc_detector_distance_config_max_step_length_get(pointer) = 1",
+        "acc_detector_distance_get_sizes(pointer, pointer, pointer) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_distance_calibrate(pointer, pointer, pointer, pointer, 419, pointer, 164, pointer, pointer) = 0",
+        "acc_sensor_validate_calibration(pointer) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_distance_calibrate(pointer, pointer, pointer, pointer, 420, pointer, 164, pointer, pointer) = 1",
+        "acc_sensor_validate_calibration(pointer) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_distance_update_calibration(pointer, pointer, pointer, pointer, 420, pointer, pointer) = 1",
+        "acc_sensor_validate_calibration(pointer) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_distance_prepare(pointer, pointer, pointer, pointer, pointer, 420) = 1",
+        "acc_sensor_prepare(pointer, pointer, pointer, pointer, 420) = 1",
+    ]),
+        layout,
+        calls(&[
+        "acc_detector_distance_destroy(pointer)",
+        "acc_config_destroy(pointer)",
+    ]),
+    ]
+    .concat()
+    );
+    assert_eq!(
+        common::take_log(),
+        [
+            "distance: start: 0.200 m, end: 0.300 m, max_profile: 2, max_step_length: 1",
+            "distance: num_points: 41",
+        ]
+    );
+}
+
+#[cfg(feature = "distance")]
+#[test]
+fn distance_detector_step_lengths() {
+    let _stubs = common::setup();
+    let mut outputs = Outputs::default();
+    unsafe {
+        let config = acc_detector_distance_config_create();
+        acc_detector_distance_config_start_set(config, 0.2);
+        acc_detector_distance_config_end_set(config, 0.3);
+        let mut num_points = |name: &str, profile, max_step_length| {
+            acc_detector_distance_config_max_profile_set(config, profile);
+            acc_detector_distance_config_max_step_length_set(config, max_step_length);
+            let handle = acc_detector_distance_create(config);
+            let (mut buffer_size, mut static_size) = (0, 0);
+            assert!(acc_detector_distance_get_sizes(
+                handle,
+                &mut buffer_size,
+                &mut static_size
+            ));
+            // A threshold per point
+            outputs.push(name, static_size / 4);
+            acc_detector_distance_destroy(handle);
+        };
+        num_points("profile 1", acc_config_profile_t_ACC_CONFIG_PROFILE_1, 0);
+        num_points("profile 2", acc_config_profile_t_ACC_CONFIG_PROFILE_2, 0);
+        num_points("profile 3", acc_config_profile_t_ACC_CONFIG_PROFILE_3, 0);
+        num_points("profile 4", acc_config_profile_t_ACC_CONFIG_PROFILE_4, 0);
+        num_points("profile 5", acc_config_profile_t_ACC_CONFIG_PROFILE_5, 0);
+        // Limited to the longest step length the RSS accepts
+        num_points(
+            "profile 4 max 5",
+            acc_config_profile_t_ACC_CONFIG_PROFILE_4,
+            5,
+        );
+        num_points(
+            "profile 5 max 11",
+            acc_config_profile_t_ACC_CONFIG_PROFILE_5,
+            11,
+        );
+
+        acc_detector_distance_config_max_profile_set(
+            config,
+            acc_config_profile_t_ACC_CONFIG_PROFILE_1,
+        );
+        acc_detector_distance_config_max_step_length_set(config, 0);
+        acc_detector_distance_config_end_set(config, 200.0);
+        outputs.push(
+            "too many points",
+            acc_detector_distance_create(config).is_null(),
+        );
+        acc_detector_distance_config_destroy(config);
+    }
+    outputs.assert_eq(&[
+        ("profile 1", 41.0),
+        ("profile 2", 21.0),
+        ("profile 3", 7.0),
+        ("profile 4", 4.0),
+        ("profile 5", 4.0),
+        ("profile 4 max 5", 11.0),
+        ("profile 5 max 11", 6.0),
+        ("too many points", 1.0),
+    ]);
+    assert_eq!(
+        common::take_log(),
+        ["distance: Range of 79921 points does not fit a sensor config"]
+    );
+}
+
+#[cfg(feature = "presence")]
+#[test]
+fn presence_detector_outputs() {
+    let _stubs = common::setup();
+    let mut outputs = Outputs::default();
+    unsafe {
+        let intra = [0.5f32, 1.5];
+        assert!(acc_stub_presence_timeline_add(
+            &acc_stub_presence_segment_t {
+                first_frame: 1,
+                last_frame: 2,
+                presence_detected: true,
+                intra_presence_score: 1.5,
+                inter_presence_score: 2.5,
+                presence_distance: 0.5,
+                depthwise_intra_presence_scores: intra.as_ptr(),
+                depthwise_inter_presence_scores: ptr::null(),
+                depthwise_presence_scores_length: intra.len() as u32,
+            }
+        ));
+        let config = acc_detector_presence_config_create();
+        acc_detector_presence_config_start_set(config, 0.4);
+        acc_detector_presence_config_end_set(config, 0.6);
+        acc_detector_presence_config_step_length_set(config, 20);
+        acc_detector_presence_config_sweeps_per_frame_set(config, 2);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        acc_stub_trace_enable(true);
+
+        let mut metadata: acc_detector_presence_metadata_t = core::mem::zeroed();
+        let handle = acc_detector_presence_create(config, &mut metadata);
+        acc_detector_presence_config_log(config);
+        outputs.push("start_m", metadata.start_m);
+        outputs.push("end_m", metadata.end_m);
+        outputs.push("step_length_m", metadata.step_length_m);
+        outputs.push("num_points", metadata.num_points);
+        outputs.push("profile", metadata.profile);
+        let processing_metadata = &*metadata.processing_metadata;
+        outputs.push("frame_data_length", processing_metadata.frame_data_length);
+        outputs.push("sweep_data_length", processing_metadata.sweep_data_length);
+        outputs.push("max_sweep_rate", processing_metadata.max_sweep_rate);
+        outputs.push(
+            "distance_m 2",
+            acc_detector_presence_get_distance_m(handle, 2),
+        );
+
+        let mut buffer_size = 0;
+        assert!(acc_detector_presence_get_buffer_size(
+            handle,
+            &mut buffer_size
+        ));
+        outputs.push("buffer_size", buffer_size);
+        let mut buffer = vec![0u32; (buffer_size as usize).div_ceil(4)];
+        outputs.push(
+            "prepare",
+            acc_detector_presence_prepare(
+                handle,
+                config,
+                sensor,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+            ),
+        );
+        for frame_index in 0..3 {
+            let mut result: acc_detector_presence_result_t = core::mem::zeroed();
+            assert!(acc_sensor_measure(sensor));
+            assert!(acc_sensor_read(
+                sensor,
+                buffer.as_mut_ptr().cast(),
+                buffer_size
+            ));
+            assert!(acc_detector_presence_process(
+                handle,
+                buffer.as_mut_ptr().cast(),
+                &mut result
+            ));
+            let name = |field: &str| format!("result {}.{}", frame_index, field);
+            outputs.push(&name("presence_detected"), result.presence_detected);
+            outputs.push(&name("intra_presence_score"), result.intra_presence_score);
+            outputs.push(&name("inter_presence_score"), result.inter_presence_score);
+            outputs.push(&name("presence_distance"), result.presence_distance);
+            let length = result.depthwise_presence_scores_length as usize;
+            outputs.push_all(
+                &name("depthwise_intra_presence_scores"),
+                core::slice::from_raw_parts(result.depthwise_intra_presence_scores, length),
+            );
+            outputs.push_all(
+                &name("depthwise_inter_presence_scores"),
+                core::slice::from_raw_parts(result.depthwise_inter_presence_scores, length),
+            );
+            outputs.push(&name("temperature"), result.processing_result.temperature);
+        }
+        acc_detector_presence_destroy(handle);
+        acc_stub_trace_enable(false);
+        acc_sensor_destroy(sensor);
+        acc_detector_presence_config_destroy(config);
+    }
+    outputs.assert_eq(&[
+        ("start_m", 0.3999999761581421),
+        ("end_m", 0.5999999642372131),
+        ("step_length_m", 0.04999999701976776),
+        ("num_points", 5.0),
+        ("profile", 4.0),
+        ("frame_data_length", 10.0),
+        ("sweep_data_length", 5.0),
+        ("max_sweep_rate", 20000.0),
+        ("distance_m 2", 0.4999999701976776),
+        ("buffer_size", 336.0),
+        ("prepare", 1.0),
+        ("result 0.presence_detected", 0.0),
+        ("result 0.intra_presence_score", 0.0),
+        ("result 0.inter_presence_score", 0.0),
+        ("result 0.presence_distance", 0.0),
+        ("result 0.depthwise_intra_presence_scores[0]", 0.0),
+        ("result 0.depthwise_intra_presence_scores[1]", 0.0),
+        ("result 0.depthwise_intra_presence_scores[2]", 0.0),
+        ("result 0.depthwise_intra_presence_scores[3]", 0.0),
+        ("result 0.depthwise_intra_presence_scores[4]", 0.0),
+        ("result 0.depthwise_inter_presence_scores[0]", 0.0),
+        ("result 0.depthwise_inter_presence_scores[1]", 0.0),
+        ("result 0.depthwise_inter_presence_scores[2]", 0.0),
+        ("result 0.depthwise_inter_presence_scores[3]", 0.0),
+        ("result 0.depthwise_inter_presence_scores[4]", 0.0),
+        ("result 0.temperature", 25.0),
+        ("result 1.presence_detected", 1.0),
+        ("result 1.intra_presence_score", 1.5),
+        ("result 1.inter_presence_score", 2.5),
+        ("result 1.presence_distance", 0.5),
+        ("result 1.depthwise_intra_presence_scores[0]", 0.5),
+        ("result 1.depthwise_intra_presence_scores[1]", 1.5),
+        ("result 1.depthwise_intra_presence_scores[2]", 0.0),
+        ("result 1.depthwise_intra_presence_scores[3]", 0.0),
+        ("result 1.depthwise_intra_presence_scores[4]", 0.0),
+        (
+            "result 1.depthwise_inter_presence_scores[0]",
+            1.5163261890411377,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[1]",
+            2.206242084503174,
+        ),
+        ("result 1.depthwise_inter_presence_scores[2]", 2.5),
+        (
+            "result 1.depthwise_inter_presence_scores[3]",
+            2.206242799758911,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[4]",
+            1.5163272619247437,
+        ),
+        ("result 1.temperature", 25.0),
+        ("result 2.presence_detected", 1.0),
+        ("result 2.intra_presence_score", 1.5),
+        ("result 2.inter_presence_score", 2.5),
+        ("result 2.presence_distance", 0.5),
+        ("result 2.depthwise_intra_presence_scores[0]", 0.5),
+        ("result 2.depthwise_intra_presence_scores[1]", 1.5),
+        ("result 2.depthwise_intra_presence_scores[2]", 0.0),
+        ("result 2.depthwise_intra_presence_scores[3]", 0.0),
+        ("result 2.depthwise_intra_presence_scores[4]", 0.0),
+        (
+            "result 2.depthwise_inter_presence_scores[0]",
+            1.5163261890411377,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[1]",
+            2.206242084503174,
+        ),
+        ("result 2.depthwise_inter_presence_scores[2]", 2.5),
+        (
+            "result 2.depthwise_inter_presence_scores[3]",
+            2.206242799758911,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[4]",
+            1.5163272619247437,
+        ),
+        ("result 2.temperature", 25.0),
+    ]);
+    let layout = layout(&[[160, 5, 20, 32, 4]], 2, 10);
+    assert_eq!(
+        trace(),
+        [
+        calls(&[
+        "acc_detector_presence_create(pointer, pointer) = pointer",
+        "acc_detector_presence_config_start_get(pointer) = 0.4000000059604645",
+        "acc_detector_presence_config_end_get(pointer) = 0.6000000238418579",
+        "acc_detector_presence_config_step_length_get(pointer) = 20",
+        "acc_processing_meter_to_points(0.4000000059604645) = 160",
+        "acc_processing_meter_to_points(0.6000000238418579) = 240",
+        "acc_processing_points_to_meter(160) = 0.3999999761581421",
+        "acc_processing_points_to_meter(20) = 0.04999999701976776",
+        "acc_detector_presence_config_reset_filters_on_prepare_get(pointer) = 1",
+        "acc_config_create() = pointer",
+        "acc_config_start_point_set(pointer, 160)",
+        "acc_config_num_points_set(pointer, 5)",
+        "acc_config_step_length_set(pointer, 20)",
+        "acc_detector_presence_config_profile_get(pointer) = 4",
+        "acc_config_profile_set(pointer, 4)",
+        "acc_detector_presence_config_hwaas_get(pointer) = 32",
+        "acc_config_hwaas_set(pointer, 32)",
+        "acc_detector_presence_config_sweeps_per_frame_get(pointer) = 2",
+        "acc_config_sweeps_per_frame_set(pointer, 2)",
+        "acc_detector_presence_config_frame_rate_app_driven_get(pointer) = 0",
+        "acc_detector_presence_config_frame_rate_get(pointer) = 10",
+        "acc_config_frame_rate_set(pointer, 10)",
+        "acc_detector_presence_config_inter_frame_idle_state_get(pointer) = 0",
+        "acc_config_inter_frame_idle_state_set(pointer, 0)",
+        "acc_processing_create(pointer, pointer) = pointer",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_config_profile_get(pointer) = 4",
+        "acc_detector_presence_config_log(pointer)",
+        "acc_detector_presence_config_start_get(pointer) = 0.4000000059604645",
+        "acc_detector_presence_config_end_get(pointer) = 0.6000000238418579",
+        "acc_detector_presence_config_step_length_get(pointer) = 20",
+        "acc_detector_presence_config_frame_rate_get(pointer) = 10",
+        "acc_detector_presence_get_distance_m(pointer, 2) = 0.4999999701976776",
+        "acc_detector_presence_get_buffer_size(pointer, pointer) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_presence_prepare(pointer, pointer, pointer, pointer, pointer, 336) = 1",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_sensor_prepare(pointer, pointer, pointer, pointer, 336) = 1",
+    ]),
+        layout,
+        calls(&[
+        "acc_sensor_measure(pointer) = 1",
+        "acc_sensor_read(pointer, pointer, 336) = 1",
+        "acc_detector_presence_process(pointer, pointer, pointer) = 1",
+        "acc_processing_execute(pointer, pointer, pointer)",
+        "acc_sensor_measure(pointer) = 1",
+        "acc_sensor_read(pointer, pointer, 336) = 1",
+        "acc_detector_presence_process(pointer, pointer, pointer) = 1",
+        "acc_processing_execute(pointer, pointer, pointer)",
+        "acc_sensor_measure(pointer) = 1",
+        "acc_sensor_read(pointer, pointer, 336) = 1",
+        "acc_detector_presence_process(pointer, pointer, pointer) = 1",
+        "acc_processing_execute(pointer, pointer, pointer)",
+        "acc_detector_presence_destroy(pointer)",
+        "acc_processing_destroy(pointer)",
+        "acc_config_destroy(pointer)",
+    ]),
+    ]
+    .concat()
+    );
+    assert_eq!(
+        common::take_log(),
+        ["presence: start: 0.400 m, end: 0.600 m, step_length: 20, frame_rate: 10.000 Hz",]
+    );
+}
+
+#[cfg(feature = "presence")]
+#[test]
+fn presence_processing_outputs() {
+    let _stubs = common::setup();
+    let mut outputs = Outputs::default();
+    unsafe {
+        acc_stub_scene_add_reflector(0.5, 2000.0, 0.05);
+        acc_stub_scene_set_noise(20.0);
+        let config = acc_config_create();
+        acc_config_start_point_set(config, 190);
+        acc_config_num_points_set(config, 5);
+        acc_config_step_length_set(config, 4);
+        acc_config_sweeps_per_frame_set(config, 4);
+        acc_config_frame_rate_set(config, 10.0);
+        let processing_config = acc_detector_presence_processing_config_create();
+        acc_detector_presence_processing_config_frame_rate_set(processing_config, 10.0);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _sensor_buffer = common::prepare(sensor, config, &cal_result);
+        acc_stub_trace_enable(true);
+
+        let buffer_size = acc_detector_presence_processing_get_buffer_size(config);
+        outputs.push("buffer_size", buffer_size);
+        let processing = acc_detector_presence_processing_create(processing_config, config);
+        let mut buffer = vec![0u32; (buffer_size as usize).div_ceil(4)];
+        for frame_index in 0..3 {
+            if frame_index == 2 {
+                assert!(acc_detector_presence_processing_reset(processing));
+            }
+            acc_stub_trace_enable(false);
+            let frame = common::read_frame(sensor, 20);
+            acc_stub_trace_enable(true);
+            let mut result: acc_detector_presence_processing_result_t = core::mem::zeroed();
+            assert!(acc_detector_presence_processing_process(
+                processing,
+                buffer.as_mut_ptr().cast(),
+                frame.as_ptr(),
+                &mut result
+            ));
+            let name = |field: &str| format!("result {}.{}", frame_index, field);
+            outputs.push(&name("presence_detected"), result.presence_detected);
+            outputs.push(&name("intra_presence_score"), result.intra_presence_score);
+            outputs.push(&name("inter_presence_score"), result.inter_presence_score);
+            outputs.push(&name("presence_distance"), result.presence_distance);
+            let length = result.depthwise_presence_scores_length as usize;
+            outputs.push_all(
+                &name("depthwise_intra_presence_scores"),
+                core::slice::from_raw_parts(result.depthwise_intra_presence_scores, length),
+            );
+            outputs.push_all(
+                &name("depthwise_inter_presence_scores"),
+                core::slice::from_raw_parts(result.depthwise_inter_presence_scores, length),
+            );
+        }
+        acc_detector_presence_processing_destroy(processing);
+        acc_stub_trace_enable(false);
+        acc_detector_presence_processing_config_destroy(processing_config);
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+    outputs.assert_eq(&[
+        ("buffer_size", 160.0),
+        ("result 0.presence_detected", 0.0),
+        ("result 0.intra_presence_score", 0.047292329370975494),
+        ("result 0.inter_presence_score", -0.008840599097311497),
+        ("result 0.presence_distance", 0.0),
+        (
+            "result 0.depthwise_intra_presence_scores[0]",
+            0.10514289140701294,
+        ),
+        ("result 0.depthwise_intra_presence_scores[1]", 0.0),
+        (
+            "result 0.depthwise_intra_presence_scores[2]",
+            -0.02701212279498577,
+        ),
+        (
+            "result 0.depthwise_intra_presence_scores[3]",
+            0.16683438420295715,
+        ),
+        (
+            "result 0.depthwise_intra_presence_scores[4]",
+            -0.13683782517910004,
+        ),
+        (
+            "result 0.depthwise_inter_presence_scores[0]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 0.depthwise_inter_presence_scores[1]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 0.depthwise_inter_presence_scores[2]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 0.depthwise_inter_presence_scores[3]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 0.depthwise_inter_presence_scores[4]",
+            -0.18126922845840454,
+        ),
+        ("result 1.presence_detected", 0.0),
+        ("result 1.intra_presence_score", 0.0658390000462532),
+        ("result 1.inter_presence_score", 0.1892130970954895),
+        ("result 1.presence_distance", 0.0),
+        (
+            "result 1.depthwise_intra_presence_scores[0]",
+            0.11271990835666656,
+        ),
+        (
+            "result 1.depthwise_intra_presence_scores[1]",
+            0.0670134648680687,
+        ),
+        (
+            "result 1.depthwise_intra_presence_scores[2]",
+            -0.013868486508727074,
+        ),
+        (
+            "result 1.depthwise_intra_presence_scores[3]",
+            0.046543970704078674,
+        ),
+        (
+            "result 1.depthwise_intra_presence_scores[4]",
+            -0.30248793959617615,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[0]",
+            3.877310276031494,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[1]",
+            3.8128793239593506,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[2]",
+            3.9425740242004395,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[3]",
+            4.052087783813477,
+        ),
+        (
+            "result 1.depthwise_inter_presence_scores[4]",
+            4.051059722900391,
+        ),
+        ("result 2.presence_detected", 0.0),
+        ("result 2.intra_presence_score", 0.012245473451912403),
+        ("result 2.inter_presence_score", -0.008840599097311497),
+        ("result 2.presence_distance", 0.0),
+        (
+            "result 2.depthwise_intra_presence_scores[0]",
+            -0.02722804620862007,
+        ),
+        (
+            "result 2.depthwise_intra_presence_scores[1]",
+            0.04319867491722107,
+        ),
+        (
+            "result 2.depthwise_intra_presence_scores[2]",
+            -0.1081986352801323,
+        ),
+        ("result 2.depthwise_intra_presence_scores[3]", 0.0),
+        (
+            "result 2.depthwise_intra_presence_scores[4]",
+            0.01095776166766882,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[0]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[1]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[2]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[3]",
+            -0.18126922845840454,
+        ),
+        (
+            "result 2.depthwise_inter_presence_scores[4]",
+            -0.18126922845840454,
+        ),
+    ]);
+    let layout = layout(&[[190, 5, 4, 8, 3]], 4, 10);
+    assert_eq!(
+        trace(),
+        [
+        calls(&[
+        "acc_detector_presence_processing_get_buffer_size(pointer) = 160",
+    ]),
+        layout.clone(),
+        calls(&[
+        "acc_detector_presence_processing_create(pointer, pointer) = pointer",
+    ]),
+        layout,
+        calls(&[
+        "acc_detector_presence_processing_config_frame_rate_get(pointer) = 10",
+        "acc_detector_presence_processing_config_intra_detection_get(pointer) = 1",
+        "acc_detector_presence_processing_config_intra_detection_threshold_get(pointer) = 1.2999999523162842",
+        "acc_detector_presence_processing_config_inter_detection_get(pointer) = 1",
+        "acc_detector_presence_processing_config_inter_detection_threshold_get(pointer) = 1",
+        "acc_detector_presence_processing_config_inter_frame_deviation_time_const_get(pointer) = 0.5",
+        "acc_detector_presence_processing_config_inter_frame_fast_cutoff_get(pointer) = 6",
+        "acc_detector_presence_processing_config_inter_frame_slow_cutoff_get(pointer) = 0.20000000298023224",
+        "acc_detector_presence_processing_config_intra_frame_time_const_get(pointer) = 0.15000000596046448",
+        "acc_detector_presence_processing_config_intra_output_time_const_get(pointer) = 0.30000001192092896",
+        "acc_detector_presence_processing_config_inter_output_time_const_get(pointer) = 2",
+        "acc_detector_presence_processing_process(pointer, pointer, pointer, pointer) = 1",
+        "acc_detector_presence_processing_process(pointer, pointer, pointer, pointer) = 1",
+        "acc_detector_presence_processing_reset(pointer) = 1",
+        "acc_detector_presence_processing_process(pointer, pointer, pointer, pointer) = 1",
+        "acc_detector_presence_processing_destroy(pointer)",
+    ]),
+    ]
+    .concat()
+    );
+    assert!(common::take_log().is_empty());
+}
+
+#[test]
+fn assembly_test_outputs() {
+    let _stubs = common::setup();
+    let mut results = Vec::new();
+    unsafe {
+        acc_stub_fault_assembly_test(
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_COMMUNICATION,
+            true,
+        );
+        acc_stub_trace_enable(true);
+        let mut buffer = vec![0u32; (ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE as usize).div_ceil(4)];
+        let test = acc_rss_assembly_test_create(
+            1,
+            buffer.as_mut_ptr().cast(),
+            ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE,
+        );
+        acc_rss_assembly_test_disable_all_tests(test);
+        acc_rss_assembly_test_enable(
+            test,
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_COMMUNICATION,
+        );
+        acc_rss_assembly_test_enable(
+            test,
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT,
+        );
+        let timeout = acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_TIMEOUT;
+        for _ in 0..2 {
+            for _ in 0..10 {
+                let state = acc_rss_assembly_test_execute(test, timeout);
+                if state == acc_rss_test_state_t_ACC_RSS_TEST_STATE_COMPLETE {
+                    break;
+                }
+            }
+            let mut length = 0;
+            let test_results = acc_rss_assembly_test_get_results(test, &mut length);
+            for result in core::slice::from_raw_parts(test_results, length.into()) {
+                let name = CStr::from_ptr(result.test_name).to_str().unwrap();
+                results.push((name, result.test_result));
+            }
+            acc_rss_assembly_test_enable_all_tests(test);
+            acc_rss_assembly_test_disable(
+                test,
+                acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_ENABLE_PIN,
+            );
+        }
+        acc_rss_assembly_test_destroy(test);
+        acc_stub_trace_enable(false);
+    }
+    assert_eq!(
+        trace(),
+        calls(&[
+            "acc_rss_assembly_test_create(1, pointer, 4096) = pointer",
+            "acc_rss_assembly_test_enable_all_tests(pointer)",
+            "acc_rss_assembly_test_disable_all_tests(pointer)",
+            "acc_rss_assembly_test_enable(pointer, 1)",
+            "acc_rss_assembly_test_enable(pointer, 3)",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 2",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 3",
+            "acc_rss_assembly_test_get_results(pointer, pointer) = pointer",
+            "acc_rss_assembly_test_enable_all_tests(pointer)",
+            "acc_rss_assembly_test_disable(pointer, 2)",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 2",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 0",
+            "acc_rss_assembly_test_execute(pointer, 1) = 3",
+            "acc_rss_assembly_test_get_results(pointer, pointer) = pointer",
+            "acc_rss_assembly_test_enable_all_tests(pointer)",
+            "acc_rss_assembly_test_disable(pointer, 2)",
+            "acc_rss_assembly_test_destroy(pointer)",
+        ])
+    );
+    assert_eq!(
+        results,
+        [
+            ("communication", false),
+            ("interrupt", false),
+            // All but the enable pin test in the second run
+            ("basic_read", true),
+            ("communication", false),
+            ("interrupt", false),
+            ("clock_and_supply", true),
+            ("sensor_calibration", true),
+        ]
+    );
+    assert!(common::take_log().is_empty());
+}
@@ -0,0 +1,216 @@
+//! The bundled printf against the host libc's `snprintf`
+
+#![cfg(all(feature = "stub_library", feature = "bundled_printf"))]
+
+use core::ffi::{c_char, c_int, CStr};
+
+// Links the log wrapper holding the bundled printf
+extern crate a121_sys;
+
+extern "C" {
+    fn a121_snprintf(buffer: *mut c_char, size: usize, format: *const c_char, ...) -> c_int;
+    fn snprintf(buffer: *mut c_char, size: usize, format: *const c_char, ...) -> c_int;
+}
+
+/// Formats with both printfs into 64 byte buffers and compares the returned length and text
+macro_rules! assert_same {
+    ($format:literal $(, $arg:expr)* $(,)?) => {{
+        let mut bundled = [0 as c_char; 64];
+        let mut libc = [0 as c_char; 64];
+        let (bundled_length, libc_length) = unsafe {
+            (
+                a121_snprintf(bundled.as_mut_ptr(), bundled.len(), $format.as_ptr() $(, $arg)*),
+                snprintf(libc.as_mut_ptr(), libc.len(), $format.as_ptr() $(, $arg)*),
+            )
+        };
+        let bundled = unsafe { CStr::from_ptr(bundled.as_ptr()) };
+        let libc = unsafe { CStr::from_ptr(libc.as_ptr()) };
+        assert_eq!((bundled, bundled_length), (libc, libc_length), "format {:?}", $format);
+    }};
+}
+
+#[test]
+fn integers() {
+    assert_same!(c"%d %i %u", -42 as c_int, 17 as c_int, 3_000_000_000u32);
+    assert_same!(c"%o %x %X", 8 as c_int, 0xbeef as c_int, 0xbeef as c_int);
+    assert_same!(c"%hhd %hhu", 300 as c_int, -1 as c_int);
+    assert_same!(c"%hd %hx", 70_000 as c_int, -1 as c_int);
+    assert_same!(c"%ld %lu", i64::MIN, u64::MAX);
+    assert_same!(c"%lld %llx", -1i64, u64::MAX);
+    assert_same!(c"%zu %jd", usize::MAX, i64::MIN);
+    assert_same!(
+        c"%d %u %x %o",
+        0 as c_int,
+        0 as c_int,
+        0 as c_int,
+        0 as c_int
+    );
+}
+
+#[test]
+fn flags_width_and_precision() {
+    assert_same!(
+        c"[%5d] [%-5d] [%05d]",
+        42 as c_int,
+        42 as c_int,
+        -42 as c_int
+    );
+    assert_same!(
+        c"[%+d] [% d] [%+d] [% d]",
+        7 as c_int,
+        7 as c_int,
+        -7 as c_int,
+        0 as c_int
+    );
+    assert_same!(
+        c"[%#x] [%#X] [%#o] [%#x]",
+        255 as c_int,
+        255 as c_int,
+        8 as c_int,
+        0 as c_int
+    );
+    assert_same!(
+        c"[%.3d] [%8.3d] [%-8.3x] [%.0d]",
+        5 as c_int,
+        -5 as c_int,
+        10 as c_int,
+        0 as c_int
+    );
+    // Precision overrides the 0 flag
+    assert_same!(c"[%08.3d] [%-05d]", 5 as c_int, 5 as c_int);
+    assert_same!(
+        c"[%*d] [%-*d] [%.*d]",
+        6 as c_int,
+        1 as c_int,
+        6 as c_int,
+        2 as c_int,
+        4 as c_int,
+        3 as c_int
+    );
+    // A negative width from `*` is the - flag
+    assert_same!(c"[%*d]", -6 as c_int, 1 as c_int);
+}
+
+#[test]
+fn characters_and_strings() {
+    assert_same!(
+        c"[%c] [%3c] [%-3c]",
+        b'a' as c_int,
+        b'b' as c_int,
+        b'c' as c_int
+    );
+    assert_same!(
+        c"[%s] [%8s] [%-8s]",
+        c"abc".as_ptr(),
+        c"abc".as_ptr(),
+        c"abc".as_ptr()
+    );
+    assert_same!(
+        c"[%.2s] [%6.2s] [%.*s]",
+        c"abc".as_ptr(),
+        c"abc".as_ptr(),
+        1 as c_int,
+        c"abc".as_ptr()
+    );
+    assert_same!(c"[%%] [%5%]");
+}
+
+/// glibc prints `"(null)"`, and the bundled printf does the same to keep log lines readable
+#[test]
+fn null_string() {
+    assert_same!(c"[%s]", core::ptr::null::<c_char>());
+}
+
+/// glibc prints a null pointer as `"(nil)"` and others as `%#x`
+#[test]
+fn pointers() {
+    assert_same!(c"%p", 0x1234_usize as *const u8);
+    assert_same!(
+        c"[%20p] [%-20p]",
+        0xdead_beef_usize as *const u8,
+        0xdead_beef_usize as *const u8
+    );
+    assert_same!(c"%p", core::ptr::null::<u8>());
+}
+
+#[test]
+fn floats() {
+    assert_same!(c"%f %f %f", 1.5, -0.25, 0.0);
+    assert_same!(
+        c"%.2f %.0f %#.0f %10.3f %-10.1f|",
+        1.23456,
+        2.5,
+        3.0,
+        -1.0005,
+        9.96
+    );
+    assert_same!(c"%+f % f %08.2f", 1.0, 1.0, -3.5);
+    assert_same!(c"%e %E %.2e %.0e", 12345.678, 0.000123, -9.999, 5.0);
+    assert_same!(c"%g %g %g %G", 100000.0, 1000000.0, 0.0001, 0.00001);
+    assert_same!(c"%.3g %#g %g %g", 1.23456, 2.0, 123456789.0, 0.0);
+    assert_same!(c"%f %e", 123456789012.5, 1e17);
+}
+
+/// Rounding follows the exact binary value, 0.05 is just above the tie and 0.15 just below.
+/// Only exact ties like 0.25 round half to even.
+#[test]
+fn rounding() {
+    assert_same!(c"%.1f %.1f %.1f %.1f", 0.05, 0.15, 0.25, 0.35);
+    assert_same!(c"%.1f %.1f %.1f %.1f", 0.45, 0.55, 0.65, 0.75);
+    assert_same!(c"%.2f %.2f %.2f %.2f", 1.005, 1.015, 1.125, 2.675);
+    assert_same!(c"%.0f %.0f %.0f %.0f", 0.5, 1.5, 2.5, 0.5000000000000001);
+    assert_same!(c"%.3f %.5f %.1f", 0.0005, 1.000005, 9.95);
+
+    let mut buffer = [0 as c_char; 16];
+    unsafe {
+        a121_snprintf(
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            c"%.1f %.1f".as_ptr(),
+            0.05,
+            0.15,
+        );
+        assert_eq!(CStr::from_ptr(buffer.as_ptr()), c"0.1 0.1");
+    }
+}
+
+#[test]
+fn truncation() {
+    let format = c"%s-%d";
+    let mut buffer = [b'x' as c_char; 8];
+    unsafe {
+        // Nothing is written with n = 0, and the length of the full output is returned
+        let length = a121_snprintf(buffer.as_mut_ptr(), 0, format.as_ptr(), c"abc".as_ptr(), 42);
+        assert_eq!(length, 6);
+        assert!(buffer.iter().all(|&c| c == b'x' as c_char));
+
+        // n = 1 only leaves room for the terminator
+        let length = a121_snprintf(buffer.as_mut_ptr(), 1, format.as_ptr(), c"abc".as_ptr(), 42);
+        assert_eq!(length, 6);
+        assert_eq!(buffer[0], 0);
+        assert_eq!(buffer[1], b'x' as c_char);
+
+        let length = a121_snprintf(buffer.as_mut_ptr(), 4, format.as_ptr(), c"abc".as_ptr(), 42);
+        assert_eq!(length, 6);
+        assert_eq!(CStr::from_ptr(buffer.as_ptr()), c"abc");
+        assert_eq!(buffer[4], b'x' as c_char);
+    }
+}
+
+#[test]
+fn long_widths_and_precisions_are_clamped() {
+    // Past what libc accepts, the bundled printf clamps them instead of overflowing
+    let mut buffer = [0 as c_char; 64];
+    let length = unsafe {
+        a121_snprintf(
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            c"%.99999999999999999999s|%99999999999999999999d".as_ptr(),
+            c"text".as_ptr(),
+            7 as c_int,
+        )
+    };
+    assert_eq!(length, 5 + 0xFFFF);
+    let text = unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap();
+    assert_eq!(text, format!("text|{}", " ".repeat(58)));
+}
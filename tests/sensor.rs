@@ -0,0 +1,195 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+/// Enough for the default config, whose frames are 160 points
+const BUFFER_WORDS: usize = 512;
+
+/// Number of `acc_sensor_destroy` calls on `sensor` in the trace
+fn destroy_calls(sensor: *const acc_sensor_t) -> usize {
+    common::trace()
+        .iter()
+        .filter(|entry| {
+            common::function(entry) == "acc_sensor_destroy"
+                && entry.args[0].integer == sensor as usize as i64
+        })
+        .count()
+}
+
+#[test]
+fn measures_a_frame() {
+    let _stubs = common::setup();
+    let config = Config::new().unwrap();
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+
+    let sensor = Sensor::new(1).unwrap();
+    let sensor = sensor.calibrate(&mut buffer, || true).unwrap();
+    let cal_result = *sensor.cal_result();
+    assert!(unsafe { acc_sensor_validate_calibration(&cal_result) });
+
+    let sensor = sensor.prepare(&config, &mut buffer).unwrap();
+    assert!(core::ptr::eq(sensor.config(), &config));
+    assert_eq!(sensor.cal_result().data, cal_result.data);
+
+    let mut sensor = sensor;
+    for _ in 0..3 {
+        let measuring = sensor.measure().unwrap();
+        assert!(core::ptr::eq(measuring.config(), &config));
+        sensor = measuring.read(&mut buffer, || true).unwrap();
+    }
+
+    // Another config after releasing the first
+    let other = Config::builder().num_points(20).build().unwrap();
+    let sensor = sensor
+        .into_calibrated()
+        .prepare(&other, &mut buffer)
+        .unwrap();
+    let sensor = sensor
+        .measure()
+        .unwrap()
+        .read(&mut buffer, || true)
+        .unwrap();
+    drop(sensor);
+    assert_eq!(common::live_allocations(), 2);
+}
+
+#[test]
+fn calibration_waits_for_each_call() {
+    let _stubs = common::setup();
+    unsafe { acc_stub_fault_calibration(3, false) };
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+    let mut waits = 0;
+    let sensor = Sensor::new(1)
+        .unwrap()
+        .calibrate(&mut buffer, || {
+            waits += 1;
+            true
+        })
+        .unwrap();
+    // No wait after the call that completes
+    assert_eq!(waits, 2);
+
+    let mut cal_info = acc_cal_info_t { temperature: 0 };
+    assert!(unsafe { acc_sensor_get_cal_info(sensor.cal_result(), &mut cal_info) });
+    assert_eq!(cal_info.temperature, ACC_STUB_DEFAULT_TEMPERATURE as i16);
+}
+
+#[test]
+fn calibration_is_retried_after_a_failure() {
+    let _stubs = common::setup();
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+
+    unsafe { acc_stub_fault_calibration(1, true) };
+    let failure = Sensor::new(2)
+        .unwrap()
+        .calibrate(&mut buffer, || true)
+        .unwrap_err();
+    assert_eq!(failure.error, SensorError::CalibrationFailed);
+    assert_eq!(
+        common::take_log(),
+        ["sensor: Calibration of sensor 2 failed"]
+    );
+
+    unsafe { acc_stub_fault_calibration(2, false) };
+    let failure = failure.sensor.calibrate(&mut buffer, || false).unwrap_err();
+    assert_eq!(failure.error, SensorError::InterruptTimeout);
+
+    failure.sensor.calibrate(&mut buffer, || true).unwrap();
+}
+
+#[test]
+fn read_is_retried_after_an_interrupt_timeout() {
+    let _stubs = common::setup();
+    let config = Config::new().unwrap();
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+    let sensor = Sensor::new(1)
+        .unwrap()
+        .calibrate(&mut buffer, || true)
+        .unwrap()
+        .prepare(&config, &mut buffer)
+        .unwrap();
+
+    let failure = sensor
+        .measure()
+        .unwrap()
+        .read(&mut buffer, || false)
+        .unwrap_err();
+    assert_eq!(failure.error, SensorError::InterruptTimeout);
+    // Still measuring, so the frame is read without another measure
+    failure.sensor.read(&mut buffer, || true).unwrap();
+}
+
+#[test]
+fn prepare_fails_with_a_small_buffer() {
+    let _stubs = common::setup();
+    let config = Config::new().unwrap();
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+    let sensor = Sensor::new(1)
+        .unwrap()
+        .calibrate(&mut buffer, || true)
+        .unwrap();
+
+    let failure = sensor.prepare(&config, &mut buffer[..16]).unwrap_err();
+    assert_eq!(failure.error, SensorError::PrepareFailed);
+    failure.sensor.prepare(&config, &mut buffer).unwrap();
+}
+
+#[test]
+fn create_failure_is_reported() {
+    let _stubs = common::setup();
+    unsafe { acc_stub_fault_sensor_create(true) };
+    assert_eq!(Sensor::new(1).unwrap_err(), SensorError::CreateFailed);
+}
+
+#[test]
+fn sensor_is_destroyed_once() {
+    let _stubs = common::setup();
+    let config = Config::new().unwrap();
+    let mut buffer = vec![0u64; BUFFER_WORDS];
+    unsafe { acc_stub_trace_enable(true) };
+
+    // Through every state, with failures handing the sensor back
+    let sensor = Sensor::new(1).unwrap();
+    let raw = sensor.as_ptr();
+    unsafe { acc_stub_fault_calibration(1, true) };
+    let sensor = sensor.calibrate(&mut buffer, || true).unwrap_err().sensor;
+    unsafe { acc_stub_faults_clear() };
+    let sensor = sensor.calibrate(&mut buffer, || true).unwrap();
+    let sensor = sensor.prepare(&config, &mut buffer).unwrap();
+    let sensor = sensor.measure().unwrap();
+    let sensor = sensor.read(&mut buffer, || false).unwrap_err().sensor;
+    let sensor = sensor.read(&mut buffer, || true).unwrap();
+    let sensor = sensor.into_calibrated().into_created();
+    assert_eq!(destroy_calls(raw), 0);
+    drop(sensor);
+    assert_eq!(destroy_calls(raw), 1);
+
+    // And in the other states. The trace is cleared as a new sensor may get the same address.
+    unsafe { acc_stub_trace_clear() };
+    let calibrated = Sensor::new(2)
+        .unwrap()
+        .calibrate(&mut buffer, || true)
+        .unwrap();
+    let raw = calibrated.as_ptr();
+    drop(calibrated);
+    assert_eq!(destroy_calls(raw), 1);
+
+    unsafe { acc_stub_trace_clear() };
+    let measuring = Sensor::new(3)
+        .unwrap()
+        .calibrate(&mut buffer, || true)
+        .unwrap()
+        .prepare(&config, &mut buffer)
+        .unwrap()
+        .measure()
+        .unwrap();
+    let raw = measuring.as_ptr();
+    drop(measuring);
+    assert_eq!(destroy_calls(raw), 1);
+
+    unsafe { acc_stub_trace_enable(false) };
+    drop(config);
+    assert_eq!(common::live_allocations(), 0);
+}
@@ -0,0 +1,184 @@
+#![cfg(feature = "stub_library")]
+#![allow(non_upper_case_globals)]
+
+mod common;
+
+use a121_sys::*;
+use core::ffi::CStr;
+
+/// An assembly test of sensor 1 with its buffer
+struct AssemblyTest {
+    handle: *mut acc_rss_assembly_test_t,
+    _buffer: Vec<u32>,
+}
+
+impl AssemblyTest {
+    unsafe fn new() -> Self {
+        let mut buffer = vec![0u32; (ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE as usize).div_ceil(4)];
+        let handle = acc_rss_assembly_test_create(
+            1,
+            buffer.as_mut_ptr().cast(),
+            ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE,
+        );
+        assert!(!handle.is_null());
+        Self {
+            handle,
+            _buffer: buffer,
+        }
+    }
+
+    /// Executes until complete, returning the states on the way: `O` for ongoing, `T` for an
+    /// enable pin toggle and `W` for an interrupt wait
+    unsafe fn run(&self, integration_status: acc_rss_test_integration_status_t) -> String {
+        let mut states = String::new();
+        loop {
+            let state = match acc_rss_assembly_test_execute(self.handle, integration_status) {
+                acc_rss_test_state_t_ACC_RSS_TEST_STATE_COMPLETE => return states,
+                acc_rss_test_state_t_ACC_RSS_TEST_STATE_ONGOING => 'O',
+                acc_rss_test_state_t_ACC_RSS_TEST_STATE_TOGGLE_ENABLE_PIN => 'T',
+                acc_rss_test_state_t_ACC_RSS_TEST_STATE_WAIT_FOR_INTERRUPT => 'W',
+                state => panic!("unexpected state {}", state),
+            };
+            states.push(state);
+            assert!(states.len() < 20);
+        }
+    }
+
+    /// Name and outcome of every test that ran
+    unsafe fn results(&self) -> Vec<(&'static str, bool)> {
+        let mut length = 0;
+        let results = acc_rss_assembly_test_get_results(self.handle, &mut length);
+        core::slice::from_raw_parts(results, length.into())
+            .iter()
+            .map(|result| {
+                let name = CStr::from_ptr(result.test_name).to_str().unwrap();
+                (name, result.test_result)
+            })
+            .collect()
+    }
+}
+
+impl Drop for AssemblyTest {
+    fn drop(&mut self) {
+        unsafe { acc_rss_assembly_test_destroy(self.handle) };
+    }
+}
+
+#[test]
+fn create_needs_the_minimum_buffer() {
+    let _stubs = common::setup();
+    unsafe {
+        let mut buffer = vec![0u32; ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE as usize];
+        let too_small = ACC_RSS_ASSEMBLY_TEST_MIN_BUFFER_SIZE - 1;
+        assert!(acc_rss_assembly_test_create(1, buffer.as_mut_ptr().cast(), too_small).is_null());
+        assert!(acc_rss_assembly_test_create(1, core::ptr::null_mut(), u32::MAX).is_null());
+    }
+}
+
+#[test]
+fn all_tests_pass_by_default() {
+    let _stubs = common::setup();
+    unsafe {
+        let test = AssemblyTest::new();
+        let ok = acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_OK;
+        assert_eq!(test.run(ok), "OOTOWOOO");
+        assert_eq!(
+            test.results(),
+            [
+                ("basic_read", true),
+                ("communication", true),
+                ("enable_pin", true),
+                ("interrupt", true),
+                ("clock_and_supply", true),
+                ("sensor_calibration", true),
+            ]
+        );
+
+        // Completing starts over on the next execute
+        assert_eq!(test.run(ok), "OOTOWOOO");
+        assert_eq!(test.results().len(), 6);
+    }
+}
+
+#[test]
+fn disabled_tests_are_skipped() {
+    let _stubs = common::setup();
+    unsafe {
+        let test = AssemblyTest::new();
+        acc_rss_assembly_test_disable(
+            test.handle,
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_ENABLE_PIN,
+        );
+        acc_rss_assembly_test_disable(
+            test.handle,
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT,
+        );
+        let ok = acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_OK;
+        assert_eq!(test.run(ok), "OOOO");
+        let names: Vec<_> = test.results().iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                "basic_read",
+                "communication",
+                "clock_and_supply",
+                "sensor_calibration"
+            ]
+        );
+
+        acc_rss_assembly_test_disable_all_tests(test.handle);
+        acc_rss_assembly_test_enable(
+            test.handle,
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_INTERRUPT,
+        );
+        assert_eq!(test.run(ok), "WO");
+        assert_eq!(test.results(), [("interrupt", true)]);
+
+        acc_rss_assembly_test_disable_all_tests(test.handle);
+        assert_eq!(test.run(ok), "");
+        assert!(test.results().is_empty());
+    }
+}
+
+#[test]
+fn injected_faults_and_timeouts_fail_tests() {
+    let _stubs = common::setup();
+    unsafe {
+        let test = AssemblyTest::new();
+        acc_stub_fault_assembly_test(
+            acc_rss_assembly_test_test_id_t_ACC_RSS_ASSEMBLY_TEST_ID_CLOCK_AND_SUPPLY,
+            true,
+        );
+        let timeout = acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_TIMEOUT;
+        assert_eq!(test.run(timeout), "OOTOWOOO");
+        assert_eq!(
+            test.results(),
+            [
+                ("basic_read", true),
+                ("communication", true),
+                ("enable_pin", true),
+                ("interrupt", false),
+                ("clock_and_supply", false),
+                ("sensor_calibration", true),
+            ]
+        );
+    }
+}
+
+#[test]
+fn disconnected_sensor_fails_read_and_communication() {
+    let _stubs = common::setup();
+    unsafe {
+        let test = AssemblyTest::new();
+        acc_stub_fault_sensor_disconnected(1, true);
+        let ok = acc_rss_test_integration_status_t_ACC_RSS_TEST_INTEGRATION_STATUS_OK;
+        test.run(ok);
+        let failed: Vec<_> = test
+            .results()
+            .iter()
+            .filter(|(_, passed)| !passed)
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(failed, ["basic_read", "communication"]);
+    }
+}
@@ -0,0 +1,190 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+#[test]
+fn rss_buffer_holds_a_frame() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_num_subsweeps_set(config, 2);
+        acc_config_subsweep_num_points_set(config, 200, 0);
+        acc_config_subsweep_num_points_set(config, 50, 1);
+        acc_config_sweeps_per_frame_set(config, 4);
+        let mut buffer_size = 0;
+        assert!(acc_rss_get_buffer_size(config, &mut buffer_size));
+        assert_eq!(buffer_size, 250 * 4 * 4 + ACC_STUB_BUFFER_OVERHEAD);
+        assert!(!acc_rss_get_buffer_size(
+            core::ptr::null(),
+            &mut buffer_size
+        ));
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut buffer = vec![0u32; buffer_size as usize / 4];
+        assert!(!acc_sensor_prepare(
+            sensor,
+            config,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size - 1
+        ));
+        assert_eq!(
+            common::take_log(),
+            ["sensor: Buffer of 4255 bytes is too small, 4256 bytes needed"]
+        );
+        assert!(acc_sensor_prepare(
+            sensor,
+            config,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size
+        ));
+
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn read_needs_room_for_the_frame() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 10);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+
+        let mut frame = [0u32; 10];
+        assert!(acc_sensor_measure(sensor));
+        assert!(!acc_sensor_read(sensor, frame.as_mut_ptr().cast(), 39));
+        assert!(acc_sensor_read(sensor, frame.as_mut_ptr().cast(), 40));
+
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+}
+
+#[cfg(feature = "distance")]
+#[test]
+fn distance_sizes_follow_the_range() {
+    let _stubs = common::setup();
+    unsafe {
+        // Profile 5 measures in steps of 12 points, 17 points from 0.5 m to 1.0 m
+        let config = acc_detector_distance_config_create();
+        acc_detector_distance_config_start_set(config, 0.5);
+        acc_detector_distance_config_end_set(config, 1.0);
+        let handle = acc_detector_distance_create(config);
+        assert!(!handle.is_null());
+        let (mut buffer_size, mut static_size) = (0, 0);
+        assert!(acc_detector_distance_get_sizes(
+            handle,
+            &mut buffer_size,
+            &mut static_size
+        ));
+        assert_eq!(buffer_size, 17 * 4 + ACC_STUB_BUFFER_OVERHEAD);
+        assert_eq!(static_size, 17 * 4);
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut buffer = vec![0u32; buffer_size as usize / 4];
+        let mut static_result = vec![0xFFu8; static_size as usize];
+        let mut dynamic_result = core::mem::zeroed();
+        let mut complete = false;
+        let mut calibrate = |buffer_size, static_size| {
+            acc_detector_distance_calibrate(
+                sensor,
+                handle,
+                &cal_result,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+                static_result.as_mut_ptr(),
+                static_size,
+                &mut dynamic_result,
+                &mut complete,
+            )
+        };
+        assert!(!calibrate(buffer_size - 1, static_size));
+        assert!(!calibrate(buffer_size, static_size - 1));
+        assert!(calibrate(buffer_size, static_size));
+        assert!(complete);
+        assert!(static_result.iter().all(|&byte| byte == 0));
+
+        assert!(!acc_detector_distance_update_calibration(
+            sensor,
+            handle,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size - 1,
+            &mut dynamic_result,
+            &mut complete
+        ));
+        assert!(!acc_detector_distance_prepare(
+            handle,
+            config,
+            sensor,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size - 1
+        ));
+        assert!(acc_detector_distance_prepare(
+            handle,
+            config,
+            sensor,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size
+        ));
+
+        acc_sensor_destroy(sensor);
+        acc_detector_distance_destroy(handle);
+        acc_detector_distance_config_destroy(config);
+    }
+}
+
+#[cfg(feature = "presence")]
+#[test]
+fn presence_buffer_holds_frame_and_scores() {
+    let _stubs = common::setup();
+    unsafe {
+        // 37 points of 16 sweeps, plus two depthwise scores per point
+        let config = acc_detector_presence_config_create();
+        let handle = acc_detector_presence_create(config, core::ptr::null_mut());
+        let mut buffer_size = 0;
+        assert!(acc_detector_presence_get_buffer_size(
+            handle,
+            &mut buffer_size
+        ));
+        assert_eq!(
+            buffer_size,
+            37 * 16 * 4 + ACC_STUB_BUFFER_OVERHEAD + 2 * 37 * 4
+        );
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut buffer = vec![0u32; buffer_size as usize / 4];
+        assert!(!acc_detector_presence_prepare(
+            handle,
+            config,
+            sensor,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size - 1
+        ));
+        assert!(acc_detector_presence_prepare(
+            handle,
+            config,
+            sensor,
+            &cal_result,
+            buffer.as_mut_ptr().cast(),
+            buffer_size
+        ));
+
+        acc_sensor_destroy(sensor);
+        acc_detector_presence_destroy(handle);
+        acc_detector_presence_config_destroy(config);
+    }
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+#[test]
+fn fresh_config_reports_sdk_defaults() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        assert!(!config.is_null());
+        assert_eq!(acc_config_num_subsweeps_get(config), 1);
+        assert_eq!(acc_config_sweeps_per_frame_get(config), 1);
+        assert_eq!(acc_config_frame_rate_get(config), 0.0);
+        assert_eq!(
+            acc_config_inter_frame_idle_state_get(config),
+            acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_DEEP_SLEEP
+        );
+        assert_eq!(
+            acc_config_inter_sweep_idle_state_get(config),
+            acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_READY
+        );
+        for subsweep in 0..ACC_MAX_NUM_SUBSWEEPS as u8 {
+            assert_eq!(acc_config_subsweep_start_point_get(config, subsweep), 80);
+            assert_eq!(acc_config_subsweep_num_points_get(config, subsweep), 160);
+            assert_eq!(acc_config_subsweep_step_length_get(config, subsweep), 1);
+            assert_eq!(acc_config_subsweep_hwaas_get(config, subsweep), 8);
+            assert_eq!(
+                acc_config_subsweep_profile_get(config, subsweep),
+                acc_config_profile_t_ACC_CONFIG_PROFILE_3
+            );
+            assert_eq!(
+                acc_config_subsweep_prf_get(config, subsweep),
+                acc_config_prf_t_ACC_CONFIG_PRF_15_6_MHZ
+            );
+            assert!(acc_config_subsweep_enable_tx_get(config, subsweep));
+        }
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn config_setters_round_trip() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_sweeps_per_frame_set(config, 7);
+        acc_config_frame_rate_set(config, 12.5);
+        acc_config_continuous_sweep_mode_set(config, true);
+        acc_config_inter_frame_idle_state_set(
+            config,
+            acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_SLEEP,
+        );
+        acc_config_num_subsweeps_set(config, 3);
+        acc_config_subsweep_num_points_set(config, 33, 2);
+        acc_config_subsweep_profile_set(config, acc_config_profile_t_ACC_CONFIG_PROFILE_5, 2);
+
+        assert_eq!(acc_config_sweeps_per_frame_get(config), 7);
+        assert_eq!(acc_config_frame_rate_get(config), 12.5);
+        assert!(acc_config_continuous_sweep_mode_get(config));
+        assert_eq!(
+            acc_config_inter_frame_idle_state_get(config),
+            acc_config_idle_state_t_ACC_CONFIG_IDLE_STATE_SLEEP
+        );
+        assert_eq!(acc_config_num_subsweeps_get(config), 3);
+        assert_eq!(acc_config_subsweep_num_points_get(config, 2), 33);
+        assert_eq!(
+            acc_config_subsweep_profile_get(config, 2),
+            acc_config_profile_t_ACC_CONFIG_PROFILE_5
+        );
+        // Other subsweeps keep their values
+        assert_eq!(acc_config_subsweep_num_points_get(config, 1), 160);
+
+        // The plain setters and getters act on the first subsweep
+        acc_config_start_point_set(config, 100);
+        assert_eq!(acc_config_subsweep_start_point_get(config, 0), 100);
+        acc_config_subsweep_hwaas_set(config, 64, 0);
+        assert_eq!(acc_config_hwaas_get(config), 64);
+
+        // Configs do not share values
+        let other = acc_config_create();
+        assert_eq!(acc_config_sweeps_per_frame_get(other), 1);
+        assert_eq!(acc_config_start_point_get(other), 80);
+        acc_config_destroy(other);
+        acc_config_destroy(config);
+    }
+}
+
+#[cfg(feature = "distance")]
+#[test]
+fn distance_config_round_trip() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_detector_distance_config_create();
+        assert_eq!(acc_detector_distance_config_start_get(config), 0.25);
+        assert_eq!(acc_detector_distance_config_end_get(config), 3.0);
+        assert_eq!(
+            acc_detector_distance_config_max_profile_get(config),
+            acc_config_profile_t_ACC_CONFIG_PROFILE_5
+        );
+        assert_eq!(
+            acc_detector_distance_config_threshold_method_get(config),
+            acc_detector_distance_threshold_method_t_ACC_DETECTOR_DISTANCE_THRESHOLD_METHOD_CFAR
+        );
+
+        acc_detector_distance_config_start_set(config, 0.5);
+        acc_detector_distance_config_end_set(config, 1.5);
+        acc_detector_distance_config_threshold_sensitivity_set(config, 0.8);
+        acc_detector_distance_config_peak_sorting_set(
+            config,
+            acc_detector_distance_peak_sorting_t_ACC_DETECTOR_DISTANCE_PEAK_SORTING_CLOSEST,
+        );
+        assert_eq!(acc_detector_distance_config_start_get(config), 0.5);
+        assert_eq!(acc_detector_distance_config_end_get(config), 1.5);
+        assert_eq!(
+            acc_detector_distance_config_threshold_sensitivity_get(config),
+            0.8
+        );
+        assert_eq!(
+            acc_detector_distance_config_peak_sorting_get(config),
+            acc_detector_distance_peak_sorting_t_ACC_DETECTOR_DISTANCE_PEAK_SORTING_CLOSEST
+        );
+        acc_detector_distance_config_destroy(config);
+    }
+}
+
+#[cfg(feature = "presence")]
+#[test]
+fn presence_config_round_trip() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_detector_presence_config_create();
+        assert_eq!(acc_detector_presence_config_start_get(config), 0.3);
+        assert_eq!(acc_detector_presence_config_end_get(config), 2.5);
+        assert_eq!(acc_detector_presence_config_hwaas_get(config), 32);
+        assert_eq!(acc_detector_presence_config_frame_rate_get(config), 10.0);
+        assert!(acc_detector_presence_config_intra_detection_get(config));
+
+        acc_detector_presence_config_hwaas_set(config, 16);
+        acc_detector_presence_config_frame_rate_set(config, 2.0);
+        acc_detector_presence_config_intra_detection_set(config, false);
+        acc_detector_presence_config_sensor_set(config, 3);
+        assert_eq!(acc_detector_presence_config_hwaas_get(config), 16);
+        assert_eq!(acc_detector_presence_config_frame_rate_get(config), 2.0);
+        assert!(!acc_detector_presence_config_intra_detection_get(config));
+        assert_eq!(acc_detector_presence_config_sensor_get(config), 3);
+        acc_detector_presence_config_destroy(config);
+
+        let processing_config = acc_detector_presence_processing_config_create();
+        assert_eq!(
+            acc_detector_presence_processing_config_intra_detection_threshold_get(
+                processing_config
+            ),
+            1.3
+        );
+        acc_detector_presence_processing_config_inter_detection_threshold_set(
+            processing_config,
+            2.5,
+        );
+        assert_eq!(
+            acc_detector_presence_processing_config_inter_detection_threshold_get(
+                processing_config
+            ),
+            2.5
+        );
+        acc_detector_presence_processing_config_destroy(processing_config);
+    }
+}
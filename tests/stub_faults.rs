@@ -0,0 +1,237 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+unsafe fn process(
+    processing: *mut acc_processing_t,
+    frame: &mut [acc_int16_complex_t],
+) -> acc_processing_result_t {
+    let mut result = core::mem::zeroed();
+    acc_processing_execute(processing, frame.as_mut_ptr().cast(), &mut result);
+    result
+}
+
+fn flags(result: &acc_processing_result_t) -> u32 {
+    let mut flags = 0;
+    if result.data_saturated {
+        flags |= ACC_STUB_FLAG_DATA_SATURATED;
+    }
+    if result.frame_delayed {
+        flags |= ACC_STUB_FLAG_FRAME_DELAYED;
+    }
+    if result.calibration_needed {
+        flags |= ACC_STUB_FLAG_CALIBRATION_NEEDED;
+    }
+    flags
+}
+
+#[test]
+fn sensor_create_fails_and_logs() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_fault_sensor_create(true);
+        assert!(acc_sensor_create(1).is_null());
+        assert_eq!(
+            common::take_log(),
+            ["sensor: Sensor 1 could not be created"]
+        );
+
+        acc_stub_faults_clear();
+        let sensor = acc_sensor_create(1);
+        assert!(!sensor.is_null());
+        acc_sensor_destroy(sensor);
+    }
+}
+
+#[test]
+fn calibration_fails_and_logs() {
+    let _stubs = common::setup();
+    unsafe {
+        let sensor = acc_sensor_create(2);
+        let mut cal_result = core::mem::zeroed();
+        let mut cal_complete = false;
+        acc_stub_fault_calibration(1, true);
+        assert!(!acc_sensor_calibrate(
+            sensor,
+            &mut cal_complete,
+            &mut cal_result,
+            core::ptr::null_mut(),
+            0
+        ));
+        assert!(!cal_complete);
+        assert_eq!(
+            common::take_log(),
+            ["sensor: Calibration of sensor 2 failed"]
+        );
+
+        acc_stub_fault_calibration(1, false);
+        let cal_result = common::calibrate(sensor);
+        assert!(acc_sensor_validate_calibration(&cal_result));
+        acc_sensor_destroy(sensor);
+    }
+}
+
+#[test]
+fn calibration_completes_after_the_configured_calls() {
+    let _stubs = common::setup();
+    unsafe {
+        let sensor = acc_sensor_create(1);
+        let mut cal_result = core::mem::zeroed();
+        let mut cal_complete = false;
+        acc_stub_fault_calibration(3, false);
+        for _ in 0..2 {
+            for expected in [false, false, true] {
+                assert!(acc_sensor_calibrate(
+                    sensor,
+                    &mut cal_complete,
+                    &mut cal_result,
+                    core::ptr::null_mut(),
+                    0
+                ));
+                assert_eq!(cal_complete, expected);
+            }
+        }
+
+        let mut cal_info = acc_cal_info_t { temperature: 0 };
+        assert!(acc_sensor_get_cal_info(&cal_result, &mut cal_info));
+        assert_eq!(cal_info.temperature, ACC_STUB_DEFAULT_TEMPERATURE as i16);
+        acc_sensor_destroy(sensor);
+    }
+}
+
+#[test]
+fn disconnected_sensors_are_reported() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_fault_sensor_disconnected(3, true);
+        assert!(!acc_sensor_connected(3));
+        assert!(acc_sensor_connected(2));
+        acc_stub_fault_sensor_disconnected(3, false);
+        assert!(acc_sensor_connected(3));
+
+        // Ids past the mask are ignored
+        acc_stub_fault_sensor_disconnected(40, true);
+        assert!(acc_sensor_connected(40));
+        acc_stub_fault_sensor_disconnected(5, true);
+        acc_stub_faults_clear();
+        assert!(acc_sensor_connected(5));
+    }
+}
+
+#[test]
+fn flags_are_raised_on_the_chosen_frames() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(acc_stub_fault_frame_flags(
+            1,
+            2,
+            ACC_STUB_FLAG_FRAME_DELAYED
+        ));
+        assert!(acc_stub_fault_frame_flags(
+            2,
+            2,
+            ACC_STUB_FLAG_DATA_SATURATED
+        ));
+        assert!(acc_stub_fault_frame_flags(
+            4,
+            4,
+            ACC_STUB_FLAG_CALIBRATION_NEEDED
+        ));
+        assert!(!acc_stub_fault_frame_flags(
+            3,
+            2,
+            ACC_STUB_FLAG_FRAME_DELAYED
+        ));
+
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 10);
+        let processing = acc_processing_create(config, core::ptr::null_mut());
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        let flags: Vec<u32> = (0..6)
+            .map(|_| flags(&process(processing, &mut common::read_frame(sensor, 10))))
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                0,
+                ACC_STUB_FLAG_FRAME_DELAYED,
+                ACC_STUB_FLAG_FRAME_DELAYED | ACC_STUB_FLAG_DATA_SATURATED,
+                0,
+                ACC_STUB_FLAG_CALIBRATION_NEEDED,
+                0,
+            ]
+        );
+
+        acc_sensor_destroy(sensor);
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn flags_follow_the_last_frame_read() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(acc_stub_fault_frame_flags(
+            1,
+            1,
+            ACC_STUB_FLAG_FRAME_DELAYED
+        ));
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 10);
+        let processing = acc_processing_create(config, core::ptr::null_mut());
+        let first = acc_sensor_create(1);
+        let second = acc_sensor_create(2);
+        let cal_result = common::calibrate(first);
+        let _first_buffer = common::prepare(first, config, &cal_result);
+        let _second_buffer = common::prepare(second, config, &cal_result);
+
+        common::read_frame(first, 10);
+        let mut frame = common::read_frame(first, 10);
+        assert!(process(processing, &mut frame).frame_delayed);
+        // Frames are counted per sensor, this is frame 0 of the second one
+        let mut frame = common::read_frame(second, 10);
+        assert!(!process(processing, &mut frame).frame_delayed);
+        let mut frame = common::read_frame(second, 10);
+        assert!(process(processing, &mut frame).frame_delayed);
+
+        // Prepare restarts the count
+        let _first_buffer = common::prepare(first, config, &cal_result);
+        let mut frame = common::read_frame(first, 10);
+        assert!(!process(processing, &mut frame).frame_delayed);
+
+        acc_sensor_destroy(first);
+        acc_sensor_destroy(second);
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn frame_fault_ranges_are_limited() {
+    let _stubs = common::setup();
+    unsafe {
+        for frame in 0..ACC_STUB_MAX_FRAME_FAULTS {
+            assert!(acc_stub_fault_frame_flags(
+                frame,
+                frame,
+                ACC_STUB_FLAG_FRAME_DELAYED
+            ));
+        }
+        assert!(!acc_stub_fault_frame_flags(
+            20,
+            20,
+            ACC_STUB_FLAG_FRAME_DELAYED
+        ));
+        acc_stub_faults_clear();
+        assert!(acc_stub_fault_frame_flags(
+            20,
+            20,
+            ACC_STUB_FLAG_FRAME_DELAYED
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+#[test]
+fn nothing_is_created_before_registration() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_hal_unregister();
+        assert!(acc_config_create().is_null());
+        assert!(acc_sensor_create(1).is_null());
+        assert!(!acc_sensor_connected(1));
+        #[cfg(feature = "distance")]
+        assert!(acc_detector_distance_config_create().is_null());
+        #[cfg(feature = "presence")]
+        assert!(acc_detector_presence_config_create().is_null());
+        assert!(common::take_log().is_empty());
+
+        assert!(acc_rss_hal_register(&common::hal()));
+        let config = acc_config_create();
+        assert!(!config.is_null());
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn register_rejects_a_small_max_spi_transfer_size() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_hal_unregister();
+        let mut hal = common::hal();
+        hal.max_spi_transfer_size = ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED as u16 - 1;
+        assert!(!acc_rss_hal_register(&hal));
+        assert_eq!(
+            common::take_log(),
+            ["rss: max_spi_transfer_size 15 is below the required 16"]
+        );
+        assert!(acc_config_create().is_null());
+
+        hal.max_spi_transfer_size = ACC_HAL_SPI_TRANSFER_SIZE_REQUIRED as u16;
+        assert!(acc_rss_hal_register(&hal));
+        assert!(common::take_log().is_empty());
+    }
+}
+
+#[test]
+fn register_rejects_missing_functions() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_hal_unregister();
+        let mut without: Vec<fn(&mut acc_hal_a121_t)> = vec![
+            |hal| hal.mem_alloc = None,
+            |hal| hal.mem_free = None,
+            |hal| hal.transfer = None,
+        ];
+        // The Rust stubs take a HAL without `log`, as there is no `c_log_stub` to put there
+        #[cfg(not(feature = "rust_stubs"))]
+        without.push(|hal| hal.log = None);
+        for remove in without {
+            let mut hal = common::hal();
+            remove(&mut hal);
+            assert!(!acc_rss_hal_register(&hal));
+        }
+        assert!(!acc_rss_hal_register(core::ptr::null()));
+        assert!(acc_config_create().is_null());
+    }
+}
+
+#[test]
+fn handles_are_allocated_through_the_hal() {
+    let _stubs = common::setup();
+    unsafe {
+        let before = common::live_allocations();
+        let config = acc_config_create();
+        let processing = acc_processing_create(config, core::ptr::null_mut());
+        let sensor = acc_sensor_create(1);
+        assert_eq!(common::live_allocations(), before + 3);
+        #[cfg(feature = "distance")]
+        {
+            let distance_config = acc_detector_distance_config_create();
+            let distance = acc_detector_distance_create(distance_config);
+            assert!(!distance.is_null());
+            assert!(common::live_allocations() > before + 4);
+            acc_detector_distance_destroy(distance);
+            acc_detector_distance_config_destroy(distance_config);
+        }
+        #[cfg(feature = "presence")]
+        {
+            let presence_config = acc_detector_presence_config_create();
+            let presence = acc_detector_presence_create(presence_config, core::ptr::null_mut());
+            assert!(!presence.is_null());
+            assert!(common::live_allocations() > before + 4);
+            acc_detector_presence_destroy(presence);
+            acc_detector_presence_config_destroy(presence_config);
+        }
+
+        acc_sensor_destroy(sensor);
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+        assert_eq!(common::live_allocations(), before);
+    }
+}
+
+unsafe extern "C" fn no_memory(_size: usize) -> *mut core::ffi::c_void {
+    core::ptr::null_mut()
+}
+
+#[test]
+fn out_of_memory_is_logged() {
+    let _stubs = common::setup();
+    unsafe {
+        let mut hal = common::hal();
+        hal.mem_alloc = Some(no_memory);
+        assert!(acc_rss_hal_register(&hal));
+        assert!(acc_sensor_create(1).is_null());
+        let log = common::take_log();
+        assert_eq!(log.len(), 1);
+        assert!(log[0].starts_with("mem: Out of memory, "), "{:?}", log);
+    }
+}
@@ -0,0 +1,292 @@
+#![cfg(all(feature = "stub_library", feature = "presence"))]
+
+mod common;
+
+use a121_sys::*;
+use core::ptr;
+
+/// A presence detector prepared on sensor 1 with the default config
+struct Detector {
+    config: *mut acc_detector_presence_config_t,
+    handle: *mut acc_detector_presence_handle_t,
+    metadata: acc_detector_presence_metadata_t,
+    sensor: *mut acc_sensor_t,
+    cal_result: acc_cal_result_t,
+    buffer: Vec<u32>,
+}
+
+impl Detector {
+    unsafe fn new(configure: impl FnOnce(*mut acc_detector_presence_config_t)) -> Self {
+        let config = acc_detector_presence_config_create();
+        configure(config);
+        let mut metadata = core::mem::zeroed();
+        let handle = acc_detector_presence_create(config, &mut metadata);
+        assert!(!handle.is_null());
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let mut detector = Self {
+            config,
+            handle,
+            metadata,
+            sensor,
+            cal_result,
+            buffer: Vec::new(),
+        };
+        detector.prepare();
+        detector
+    }
+
+    unsafe fn prepare(&mut self) {
+        let mut buffer_size = 0;
+        assert!(acc_detector_presence_get_buffer_size(
+            self.handle,
+            &mut buffer_size
+        ));
+        self.buffer = vec![0u32; (buffer_size as usize).div_ceil(4)];
+        assert!(acc_detector_presence_prepare(
+            self.handle,
+            self.config,
+            self.sensor,
+            &self.cal_result,
+            self.buffer.as_mut_ptr().cast(),
+            buffer_size
+        ));
+    }
+
+    unsafe fn process(&mut self) -> acc_detector_presence_result_t {
+        let mut result = core::mem::zeroed();
+        assert!(acc_sensor_measure(self.sensor));
+        assert!(acc_sensor_read(
+            self.sensor,
+            self.buffer.as_mut_ptr().cast(),
+            (self.buffer.len() * 4) as u32
+        ));
+        assert!(acc_detector_presence_process(
+            self.handle,
+            self.buffer.as_mut_ptr().cast(),
+            &mut result
+        ));
+        result
+    }
+}
+
+impl Drop for Detector {
+    fn drop(&mut self) {
+        unsafe {
+            acc_detector_presence_destroy(self.handle);
+            acc_detector_presence_config_destroy(self.config);
+            acc_sensor_destroy(self.sensor);
+        }
+    }
+}
+
+unsafe fn depthwise(scores: *const f32, result: &acc_detector_presence_result_t) -> &[f32] {
+    core::slice::from_raw_parts(scores, result.depthwise_presence_scores_length as usize)
+}
+
+fn segment(first_frame: u32, last_frame: u32, score: f32) -> acc_stub_presence_segment_t {
+    acc_stub_presence_segment_t {
+        first_frame,
+        last_frame,
+        presence_detected: true,
+        intra_presence_score: score,
+        inter_presence_score: score,
+        presence_distance: 1.2,
+        depthwise_intra_presence_scores: ptr::null(),
+        depthwise_inter_presence_scores: ptr::null(),
+        depthwise_presence_scores_length: 0,
+    }
+}
+
+#[test]
+fn metadata_follows_the_config() {
+    let _stubs = common::setup();
+    unsafe {
+        // 0.3 m to 2.5 m in steps of 24 points, 0.06 m
+        let detector = Detector::new(|_| {});
+        let metadata = &detector.metadata;
+        assert_eq!(metadata.num_points, 37);
+        assert!((metadata.start_m - 0.3).abs() < 1e-6);
+        assert!((metadata.step_length_m - 0.06).abs() < 1e-6);
+        assert!((metadata.end_m - 2.46).abs() < 1e-5);
+        assert_eq!(metadata.profile, acc_config_profile_t_ACC_CONFIG_PROFILE_4);
+        assert_eq!(
+            (*metadata.processing_metadata).sweep_data_length,
+            metadata.num_points
+        );
+        assert_eq!(acc_config_sweeps_per_frame_get(metadata.sensor_config), 16);
+        assert!((acc_detector_presence_get_distance_m(detector.handle, 10) - 0.9).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn results_follow_the_timeline() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(acc_stub_presence_timeline_add(&segment(2, 5, 2.0)));
+        // Overlapping segments added later win
+        let mut absent = segment(4, 4, 0.5);
+        absent.presence_detected = false;
+        assert!(acc_stub_presence_timeline_add(&absent));
+
+        let mut detector = Detector::new(|_| {});
+        let detected: Vec<bool> = (0..8)
+            .map(|_| detector.process().presence_detected)
+            .collect();
+        assert_eq!(
+            detected,
+            [false, false, true, true, false, true, false, false]
+        );
+    }
+}
+
+#[test]
+fn frames_outside_the_timeline_report_zero_scores() {
+    let _stubs = common::setup();
+    unsafe {
+        let mut detector = Detector::new(|_| {});
+        let result = detector.process();
+        assert!(!result.presence_detected);
+        assert_eq!(result.intra_presence_score, 0.0);
+        assert_eq!(result.inter_presence_score, 0.0);
+        assert_eq!(result.depthwise_presence_scores_length, 37);
+        assert!(depthwise(result.depthwise_intra_presence_scores, &result)
+            .iter()
+            .all(|&score| score == 0.0));
+    }
+}
+
+#[test]
+fn depthwise_scores_peak_at_the_distance() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(acc_stub_presence_timeline_add(&segment(0, 0, 2.0)));
+        let mut detector = Detector::new(|_| {});
+        let result = detector.process();
+        assert_eq!(result.intra_presence_score, 2.0);
+        assert_eq!(result.presence_distance, 1.2);
+
+        // 1.2 m is point 15 of 0.3 m in steps of 0.06 m
+        let scores = depthwise(result.depthwise_inter_presence_scores, &result);
+        let peak = (0..scores.len())
+            .max_by(|&a, &b| scores[a].total_cmp(&scores[b]))
+            .unwrap();
+        assert_eq!(peak, 15);
+        assert!((scores[peak] - 2.0).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn scripted_depthwise_scores_are_copied() {
+    let _stubs = common::setup();
+    unsafe {
+        let intra = [1.0f32, 2.0, 3.0];
+        let mut scripted = segment(0, 0, 5.0);
+        scripted.depthwise_intra_presence_scores = intra.as_ptr();
+        scripted.depthwise_presence_scores_length = intra.len() as u32;
+        assert!(acc_stub_presence_timeline_add(&scripted));
+
+        let mut detector = Detector::new(|_| {});
+        let result = detector.process();
+        let scores = depthwise(result.depthwise_intra_presence_scores, &result);
+        assert_eq!(scores[..4], [1.0, 2.0, 3.0, 0.0]);
+        // Without a scripted array the other scores still peak at the distance
+        let inter = depthwise(result.depthwise_inter_presence_scores, &result);
+        assert!(inter[15] > 4.9);
+    }
+}
+
+#[test]
+fn frames_restart_on_prepare_unless_filters_are_kept() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(acc_stub_presence_timeline_add(&segment(1, 1, 2.0)));
+
+        let mut detector = Detector::new(|_| {});
+        detector.process();
+        assert!(detector.process().presence_detected);
+        detector.prepare();
+        detector.process();
+        assert!(detector.process().presence_detected);
+
+        let mut detector = Detector::new(|config| {
+            acc_detector_presence_config_reset_filters_on_prepare_set(config, false)
+        });
+        detector.process();
+        detector.prepare();
+        assert!(detector.process().presence_detected);
+    }
+}
+
+#[test]
+fn timeline_rejects_empty_ranges_and_overflow() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(!acc_stub_presence_timeline_add(&segment(3, 2, 1.0)));
+        assert!(!acc_stub_presence_timeline_add(ptr::null()));
+        for frame in 0..ACC_STUB_PRESENCE_MAX_SEGMENTS {
+            assert!(acc_stub_presence_timeline_add(&segment(frame, frame, 1.0)));
+        }
+        assert!(!acc_stub_presence_timeline_add(&segment(40, 40, 1.0)));
+        acc_stub_presence_timeline_clear();
+        assert!(acc_stub_presence_timeline_add(&segment(40, 40, 1.0)));
+    }
+}
+
+/// Runs presence processing over 30 frames of a reflector starting at 1 m
+unsafe fn process_moving_reflector(velocity: f32) -> acc_detector_presence_processing_result_t {
+    acc_stub_scene_clear();
+    acc_stub_scene_add_reflector(1.0, 2000.0, velocity);
+    acc_stub_scene_set_noise(20.0);
+    let config = acc_config_create();
+    acc_config_start_point_set(config, 200);
+    acc_config_num_points_set(config, 80);
+    acc_config_step_length_set(config, 4);
+    acc_config_sweeps_per_frame_set(config, 16);
+    acc_config_hwaas_set(config, 32);
+    acc_config_frame_rate_set(config, 10.0);
+    let processing_config = acc_detector_presence_processing_config_create();
+    let processing = acc_detector_presence_processing_create(processing_config, config);
+    assert!(!processing.is_null());
+    let mut buffer =
+        vec![0u32; (acc_detector_presence_processing_get_buffer_size(config) as usize).div_ceil(4)];
+
+    let sensor = acc_sensor_create(1);
+    let cal_result = common::calibrate(sensor);
+    let _sensor_buffer = common::prepare(sensor, config, &cal_result);
+    let mut result = core::mem::zeroed();
+    for _ in 0..30 {
+        let frame = common::read_frame(sensor, 80 * 16);
+        assert!(acc_detector_presence_processing_process(
+            processing,
+            buffer.as_mut_ptr().cast(),
+            frame.as_ptr(),
+            &mut result
+        ));
+    }
+    assert_eq!(result.depthwise_presence_scores_length, 80);
+
+    assert!(acc_detector_presence_processing_reset(processing));
+    acc_detector_presence_processing_destroy(processing);
+    acc_detector_presence_processing_config_destroy(processing_config);
+    acc_sensor_destroy(sensor);
+    acc_config_destroy(config);
+    result
+}
+
+#[test]
+fn processing_detects_moving_reflectors() {
+    let _stubs = common::setup();
+    unsafe {
+        assert!(!process_moving_reflector(0.0).presence_detected);
+
+        // After 3 s the reflector has moved 0.15 m away, or 0.06 m closer
+        let away = process_moving_reflector(0.05);
+        assert!(away.presence_detected);
+        assert!((away.presence_distance - 1.15).abs() < 0.05);
+        let closer = process_moving_reflector(-0.02);
+        assert!(closer.presence_detected);
+        assert!((closer.presence_distance - 0.94).abs() < 0.05);
+        assert!(closer.inter_presence_score > 0.0);
+    }
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+fn amplitude(point: &acc_int16_complex_t) -> i32 {
+    i32::from(point.real).pow(2) + i32::from(point.imag).pow(2)
+}
+
+fn peak(sweep: &[acc_int16_complex_t]) -> usize {
+    (0..sweep.len())
+        .max_by_key(|&i| amplitude(&sweep[i]))
+        .unwrap()
+}
+
+#[test]
+fn reflector_peaks_at_its_distance() {
+    let _stubs = common::setup();
+    unsafe {
+        // Points are 2.5 mm apart, so 0.25 m is point 100, the 21st from start point 80
+        acc_stub_scene_add_reflector(0.25, 1000.0, 0.0);
+        acc_stub_scene_set_noise(5.0);
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 40);
+        acc_config_sweeps_per_frame_set(config, 2);
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        let frame = common::read_frame(sensor, 80);
+        for sweep in frame.chunks_exact(40) {
+            assert_eq!(peak(sweep), 20, "{:?}", sweep);
+            assert!(amplitude(&sweep[20]) > 900 * 900);
+            assert!(amplitude(&sweep[0]) < amplitude(&sweep[20]) / 2);
+        }
+
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn moving_reflector_follows_its_velocity() {
+    let _stubs = common::setup();
+    unsafe {
+        // 0.1 m/s at 10 Hz moves 4 points per frame
+        acc_stub_scene_add_reflector(0.25, 1000.0, 0.1);
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 60);
+        acc_config_frame_rate_set(config, 10.0);
+
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        for frame_index in 0..5 {
+            let frame = common::read_frame(sensor, 60);
+            assert_eq!(peak(&frame), 20 + 4 * frame_index);
+        }
+
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn empty_scene_reads_zero_and_noise_restarts_on_clear() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_num_points_set(config, 20);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+
+        let frame = common::read_frame(sensor, 20);
+        assert!(frame.iter().all(|point| point.real == 0 && point.imag == 0));
+
+        acc_stub_scene_set_noise(50.0);
+        let noise = common::read_frame(sensor, 20);
+        assert!(noise.iter().any(|point| point.real != 0));
+        assert!(noise
+            .iter()
+            .all(|point| point.real.abs() <= 50 && point.imag.abs() <= 50));
+
+        acc_stub_scene_clear();
+        acc_stub_scene_set_noise(50.0);
+        let repeated = common::read_frame(sensor, 20);
+        assert!(noise
+            .iter()
+            .zip(&repeated)
+            .all(|(a, b)| a.real == b.real && a.imag == b.imag));
+
+        acc_sensor_destroy(sensor);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn scene_holds_a_limited_number_of_reflectors() {
+    let _stubs = common::setup();
+    unsafe {
+        for _ in 0..ACC_STUB_SCENE_MAX_REFLECTORS {
+            assert!(acc_stub_scene_add_reflector(1.0, 100.0, 0.0));
+        }
+        assert!(!acc_stub_scene_add_reflector(1.0, 100.0, 0.0));
+        acc_stub_scene_clear();
+        assert!(acc_stub_scene_add_reflector(1.0, 100.0, 0.0));
+    }
+}
+
+#[test]
+fn processing_reports_layout_and_temperature() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_num_subsweeps_set(config, 2);
+        acc_config_subsweep_num_points_set(config, 30, 0);
+        acc_config_subsweep_num_points_set(config, 50, 1);
+        acc_config_sweeps_per_frame_set(config, 4);
+
+        let mut metadata: acc_processing_metadata_t = core::mem::zeroed();
+        let processing = acc_processing_create(config, &mut metadata);
+        assert!(!processing.is_null());
+        assert_eq!(metadata.sweep_data_length, 80);
+        assert_eq!(metadata.frame_data_length, 320);
+        assert_eq!(metadata.subsweep_data_offset[..3], [0, 30, 80]);
+        assert_eq!(metadata.subsweep_data_length[..3], [30, 50, 0]);
+        assert!(metadata.max_sweep_rate > 0.0);
+        assert!(!metadata.high_speed_mode);
+
+        acc_stub_scene_set_temperature(-10);
+        let sensor = acc_sensor_create(1);
+        let cal_result = common::calibrate(sensor);
+        let _buffer = common::prepare(sensor, config, &cal_result);
+        let mut frame = common::read_frame(sensor, 320);
+        let mut result: acc_processing_result_t = core::mem::zeroed();
+        acc_processing_execute(processing, frame.as_mut_ptr().cast(), &mut result);
+        assert_eq!(result.temperature, -10);
+        assert_eq!(result.frame, frame.as_mut_ptr());
+        assert!(!result.data_saturated && !result.frame_delayed && !result.calibration_needed);
+
+        acc_sensor_destroy(sensor);
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+    }
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+use common::Transfer;
+
+const WRITE: u16 = 0x8000;
+const REG_CHIP_ID: u16 = 0x0000;
+const REG_COMMAND: u16 = 0x0001;
+const REG_CONFIG: u16 = 0x0100;
+const REG_CAL_RESULT: u16 = 0x0400;
+const REG_FRAME: u16 = 0x1000;
+
+/// Transfers of sensor 3 going from create to reading a frame of the default 160 points, split
+/// by step
+unsafe fn transfers(hal: acc_hal_a121_t) -> [Vec<Transfer>; 5] {
+    assert!(acc_rss_hal_register(&hal));
+    let config = acc_config_create();
+    let mut buffer_size = 0;
+    assert!(acc_rss_get_buffer_size(config, &mut buffer_size));
+    let mut buffer = vec![0u32; buffer_size as usize / 4];
+    let mut cal_result = core::mem::zeroed();
+    let mut cal_complete = false;
+
+    let sensor = acc_sensor_create(3);
+    let create = common::take_transfers();
+    assert!(acc_sensor_calibrate(
+        sensor,
+        &mut cal_complete,
+        &mut cal_result,
+        buffer.as_mut_ptr().cast(),
+        buffer_size
+    ));
+    let calibrate = common::take_transfers();
+    assert!(acc_sensor_prepare(
+        sensor,
+        config,
+        &cal_result,
+        buffer.as_mut_ptr().cast(),
+        buffer_size
+    ));
+    let prepare = common::take_transfers();
+    assert!(acc_sensor_measure(sensor));
+    let measure = common::take_transfers();
+    assert!(acc_sensor_read(sensor, buffer.as_mut_ptr().cast(), 640));
+    let read = common::take_transfers();
+
+    acc_sensor_destroy(sensor);
+    acc_config_destroy(config);
+    [create, calibrate, prepare, measure, read]
+}
+
+fn headers(transfers: &[Transfer]) -> Vec<u16> {
+    transfers.iter().map(|transfer| transfer.header).collect()
+}
+
+/// Words after the header, summed over `transfers`
+fn payload_words(transfers: &[Transfer]) -> usize {
+    transfers.iter().map(|transfer| transfer.size / 2 - 1).sum()
+}
+
+#[test]
+fn transfers_address_the_emulated_registers() {
+    let _stubs = common::setup();
+    unsafe {
+        let [create, calibrate, prepare, measure, read] = transfers(common::hal());
+        assert_eq!(headers(&create), [REG_CHIP_ID]);
+        assert_eq!(headers(&calibrate), [WRITE | REG_COMMAND, REG_CAL_RESULT]);
+        assert_eq!(headers(&prepare), [WRITE | REG_CONFIG]);
+        assert_eq!(headers(&measure), [WRITE | REG_COMMAND]);
+        // Split in two by the 640 byte read buffer
+        assert_eq!(headers(&read), [REG_FRAME, REG_FRAME + 319]);
+
+        // One config block for a single subsweep, two words per IQ point
+        assert_eq!(payload_words(&prepare), 16 + 8);
+        assert_eq!(payload_words(&read), 160 * 2);
+        assert_eq!(
+            payload_words(&calibrate[1..]),
+            ACC_CAL_RESULT_DATA_SIZE as usize / 2
+        );
+        for transfer in [create, calibrate, prepare, measure, read].iter().flatten() {
+            assert_eq!(transfer.sensor_id, 3);
+            assert!(!transfer.transfer16);
+        }
+    }
+}
+
+#[test]
+fn bursts_are_split_at_max_spi_transfer_size() {
+    let _stubs = common::setup();
+    unsafe {
+        // The read buffer of 640 bytes bounds the transfers too
+        for (max_spi_transfer_size, read_transfers) in [(16, 46), (255, 3), (1024, 2)] {
+            let mut hal = common::hal();
+            hal.max_spi_transfer_size = max_spi_transfer_size;
+            let steps = transfers(hal);
+            for transfer in steps.iter().flatten() {
+                assert!(transfer.size <= max_spi_transfer_size.into());
+            }
+
+            let read = &steps[4];
+            assert_eq!(read.len(), read_transfers);
+            assert_eq!(payload_words(read), 160 * 2);
+            // Each transfer continues at the register after the last one
+            let mut address = REG_FRAME;
+            for transfer in read {
+                assert_eq!(transfer.header, address);
+                address += (transfer.size / 2 - 1) as u16;
+            }
+        }
+    }
+}
+
+#[test]
+fn transfer16_is_used_when_set() {
+    let _stubs = common::setup();
+    unsafe {
+        let mut hal = common::hal();
+        hal.max_spi_transfer_size = 255;
+        hal.optimization.transfer16 = Some(common::transfer16);
+        let steps = transfers(hal);
+        assert!(steps.iter().flatten().all(|transfer| transfer.transfer16));
+        // Whole words only, below the maximum
+        assert_eq!(steps[4].iter().map(|t| t.size).max(), Some(254));
+        assert_eq!(payload_words(&steps[4]), 160 * 2);
+    }
+}
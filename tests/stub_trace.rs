@@ -0,0 +1,127 @@
+#![cfg(feature = "stub_library")]
+
+mod common;
+
+use a121_sys::*;
+
+fn int(value: i64) -> (acc_stub_trace_kind_t, i64) {
+    (acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_INT, value)
+}
+
+fn pointer<T>(value: *const T) -> (acc_stub_trace_kind_t, i64) {
+    (
+        acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_POINTER,
+        value as usize as i64,
+    )
+}
+
+fn value(value: &acc_stub_trace_value_t) -> (acc_stub_trace_kind_t, i64) {
+    (value.kind, value.integer)
+}
+
+#[test]
+fn nothing_is_recorded_until_enabled() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_config_destroy(config);
+        assert_eq!(acc_stub_trace_length(), 0);
+        assert!(acc_stub_trace_get(0).is_null());
+
+        acc_stub_trace_enable(true);
+        acc_version_get_hex();
+        acc_stub_trace_enable(false);
+        acc_version_get_hex();
+        assert_eq!(acc_stub_trace_length(), 1);
+    }
+}
+
+#[test]
+fn calls_are_recorded_with_arguments_and_result() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_trace_enable(true);
+        let config = acc_config_create();
+        acc_config_subsweep_num_points_set(config, 33, 2);
+        acc_config_frame_rate_set(config, 12.5);
+        let sweeps_per_frame = acc_config_sweeps_per_frame_get(config);
+        acc_config_destroy(config);
+        acc_stub_trace_enable(false);
+
+        let trace = common::trace();
+        let functions: Vec<_> = trace.iter().map(common::function).collect();
+        assert_eq!(
+            functions,
+            [
+                "acc_config_create",
+                "acc_config_subsweep_num_points_set",
+                "acc_config_frame_rate_set",
+                "acc_config_sweeps_per_frame_get",
+                "acc_config_destroy",
+            ]
+        );
+
+        assert_eq!(trace[0].num_args, 0);
+        assert_eq!(value(&trace[0].result), pointer(config));
+
+        assert_eq!(trace[1].num_args, 3);
+        assert_eq!(value(&trace[1].args[0]), pointer(config));
+        assert_eq!(value(&trace[1].args[1]), int(33));
+        assert_eq!(value(&trace[1].args[2]), int(2));
+        assert_eq!(
+            trace[1].result.kind,
+            acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_NONE
+        );
+
+        let frame_rate = &trace[2].args[1];
+        assert_eq!(
+            frame_rate.kind,
+            acc_stub_trace_kind_t_ACC_STUB_TRACE_KIND_FLOAT
+        );
+        assert_eq!(frame_rate.real, 12.5);
+
+        assert_eq!(value(&trace[3].result), int(i64::from(sweeps_per_frame)));
+    }
+}
+
+#[test]
+fn calls_between_stubs_are_recorded() {
+    let _stubs = common::setup();
+    unsafe {
+        let config = acc_config_create();
+        acc_stub_trace_enable(true);
+        let processing = acc_processing_create(config, core::ptr::null_mut());
+        acc_stub_trace_enable(false);
+
+        let trace = common::trace();
+        assert_eq!(common::function(&trace[0]), "acc_processing_create");
+        assert_eq!(value(&trace[0].result), pointer(processing));
+        assert!(trace[1..]
+            .iter()
+            .any(|entry| common::function(entry) == "acc_config_num_subsweeps_get"));
+
+        acc_processing_destroy(processing);
+        acc_config_destroy(config);
+    }
+}
+
+#[test]
+fn full_trace_counts_dropped_calls_until_cleared() {
+    let _stubs = common::setup();
+    unsafe {
+        acc_stub_trace_enable(true);
+        for _ in 0..300 {
+            acc_version_get_hex();
+        }
+        acc_stub_trace_enable(false);
+        assert_eq!(acc_stub_trace_length(), ACC_STUB_TRACE_CAPACITY);
+        assert_eq!(acc_stub_trace_dropped(), 300 - ACC_STUB_TRACE_CAPACITY);
+        assert!(!acc_stub_trace_get(ACC_STUB_TRACE_CAPACITY - 1).is_null());
+        assert!(acc_stub_trace_get(ACC_STUB_TRACE_CAPACITY).is_null());
+
+        acc_stub_trace_clear();
+        assert_eq!(acc_stub_trace_length(), 0);
+        assert_eq!(acc_stub_trace_dropped(), 0);
+        assert!(acc_stub_trace_get(0).is_null());
+    }
+}